@@ -0,0 +1,84 @@
+;;; oatmeal.el --- Oatmeal editor integration -*- lexical-binding: t; -*-
+
+;; URL: https://github.com/dustinblackman/oatmeal
+;; Package-Requires: ((emacs "27.1"))
+
+;;; Commentary:
+
+;; Lets `oatmeal --editor emacs' read the active buffer and region, and send
+;; accepted code blocks back to it, through `emacsclient --eval'.
+;;
+;; Load this file and start the server:
+;;
+;;   (require 'oatmeal)
+;;   (server-start)
+;;
+;; Code is exchanged base64 encoded, and line numbers are 1-based and
+;; inclusive.
+
+;;; Code:
+
+(require 'json)
+(require 'subr-x)
+
+(defun oatmeal--source-window ()
+  "Return the most recently used window showing a file."
+  (let ((windows (seq-filter (lambda (win) (buffer-file-name (window-buffer win)))
+                             (window-list-1 nil 'nomini 'visible))))
+    (car (sort windows (lambda (a b) (> (window-use-time a) (window-use-time b)))))))
+
+(defun oatmeal--language (mode)
+  "Return a language name for major MODE, such as rust for `rust-ts-mode'."
+  (string-remove-suffix "-ts" (string-remove-suffix "-mode" (symbol-name mode))))
+
+(defun oatmeal--encode (text)
+  "Base64 encode TEXT as UTF-8."
+  (base64-encode-string (encode-coding-string text 'utf-8) t))
+
+(defun oatmeal--decode (text)
+  "Decode base64 TEXT as UTF-8."
+  (decode-coding-string (base64-decode-string text) 'utf-8))
+
+(defun oatmeal-get-context ()
+  "Return the active file and region as a JSON string, or nil."
+  (when-let ((win (oatmeal--source-window)))
+    (with-current-buffer (window-buffer win)
+      (let* ((region (region-active-p))
+             (start (if region (region-beginning) (window-point win)))
+             (end (when region (region-end)))
+             (start-line (line-number-at-pos start))
+             (end-line (when end (line-number-at-pos (if (and (> end start) (bolp)) (1- end) end)))))
+        (json-encode
+         `((file_path . ,(buffer-file-name))
+           (language . ,(oatmeal--language major-mode))
+           (code . ,(oatmeal--encode (if region (buffer-substring-no-properties start end) "")))
+           (start_line . ,start-line)
+           (end_line . ,end-line)))))))
+
+(defun oatmeal-clear-context ()
+  "Nothing is kept between calls, so there's nothing to clear."
+  t)
+
+(defun oatmeal-submit-changes (payload)
+  "Append or replace code in a file from the JSON string PAYLOAD."
+  (let* ((req (json-read-from-string payload))
+         (accept-type (alist-get 'accept_type req))
+         (code (oatmeal--decode (alist-get 'code req)))
+         (start-line (alist-get 'start_line req))
+         (end-line (alist-get 'end_line req)))
+    (with-current-buffer (find-file-noselect (alist-get 'file_path req))
+      (save-excursion
+        (goto-char (point-min))
+        (forward-line (1- start-line))
+        (if (and (equal accept-type "replace") end-line)
+            (let ((beg (point)))
+              (forward-line (1+ (- end-line start-line)))
+              (delete-region beg (point))
+              (insert code "\n"))
+          (forward-line (1+ (- (or end-line start-line) start-line)))
+          (insert code "\n")))))
+  t)
+
+(provide 'oatmeal)
+
+;;; oatmeal.el ends here
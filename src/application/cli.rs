@@ -15,6 +15,7 @@ use clap_complete::Generator;
 use clap_complete::Shell;
 use dialoguer::theme::ColorfulTheme;
 use dialoguer::Select;
+use strum::IntoEnumIterator;
 use strum::VariantNames;
 use tokio::fs;
 use tokio::io::AsyncWriteExt;
@@ -26,9 +27,13 @@ use crate::domain::models::BackendName;
 use crate::domain::models::EditorName;
 use crate::domain::models::Session;
 use crate::domain::services::actions::help_text;
+use crate::domain::services::clipboard::ClipboardProvider;
 use crate::domain::services::Sessions;
 use crate::domain::services::Syntaxes;
+use crate::domain::services::TextWidth;
 use crate::domain::services::Themes;
+use crate::infrastructure::backends::BackendManager;
+use crate::infrastructure::editors::jsonrpc_server::EditorServer;
 
 fn print_completions<G: Generator>(gen: G, cmd: &mut Command) {
     generate(gen, cmd, cmd.get_name().to_string(), &mut io::stdout());
@@ -46,16 +51,12 @@ fn format_session(session: &Session) -> String {
     }
 
     if !session.state.messages.is_empty() {
-        let mut line = session.state.messages[0]
+        let line = session.state.messages[0]
             .text
             .split('\n')
-            .collect::<Vec<_>>()[0]
-            .to_string();
+            .collect::<Vec<_>>()[0];
 
-        if line.len() >= 70 {
-            line = format!("{}...", &line[..67]);
-        }
-        res = format!("{res}, {line}");
+        res = format!("{res}, {}", TextWidth::truncate(line, 70));
     }
 
     return res;
@@ -169,6 +170,11 @@ fn subcommand_config() -> Command {
         );
 }
 
+fn subcommand_serve_editor() -> Command {
+    return Command::new("serve-editor")
+        .about("Relays JSON-RPC between an editor plugin on stdin/stdout and chats started with `--editor jsonrpc`. Meant to be spawned by editor plugins.");
+}
+
 fn subcommand_debug() -> Command {
     let mut cmd = Command::new("debug");
     cmd = cmd.about("Debug helpers for Oatmeal")
@@ -195,6 +201,9 @@ fn subcommand_debug() -> Command {
         )
         .subcommand(
             Command::new("enum-config").about("List all config keys as strings.")
+        )
+        .subcommand(
+            Command::new("backends").about("List all backends and the features they support, using the current configuration.")
         );
 
     return cmd;
@@ -246,13 +255,31 @@ fn arg_backend_health_check_timeout() -> Arg {
         );
 }
 
+fn arg_backend_response_timeout() -> Arg {
+    return Arg::new(ConfigKey::BackendResponseTimeout.to_string())
+        .long(ConfigKey::BackendResponseTimeout.to_string())
+        .env("OATMEAL_BACKEND_RESPONSE_TIMEOUT")
+        .num_args(1)
+        .help(
+            format!("Time to wait in milliseconds for a backend to start responding to a prompt before retrying on the next fallback model. Only used when fallback models are set. [default: {}]", Config::default(ConfigKey::BackendResponseTimeout)),
+        );
+}
+
+fn arg_fallback_models() -> Arg {
+    return Arg::new(ConfigKey::FallbackModels.to_string())
+        .long(ConfigKey::FallbackModels.to_string())
+        .env("OATMEAL_FALLBACK_MODELS")
+        .num_args(1)
+        .help("Comma separated list of models to retry a prompt on, in order, when the active backend fails to respond. Each entry is an alias from the `[models]` config table, or BACKEND:MODEL.");
+}
+
 fn arg_model() -> Arg {
     return Arg::new(ConfigKey::Model.to_string())
         .short('m')
         .long(ConfigKey::Model.to_string())
         .env("OATMEAL_MODEL")
         .num_args(1)
-        .help("The initial model on a backend to consume. Defaults to the first model available from the backend if not set.");
+        .help("The initial model on a backend to consume, or an alias from the `[models]` config table. Defaults to the first model available from the backend if not set.");
 }
 
 fn subcommand_chat() -> Command {
@@ -260,7 +287,9 @@ fn subcommand_chat() -> Command {
         .about("Start a new chat session.")
         .arg(arg_backend())
         .arg(arg_backend_health_check_timeout())
-        .arg(arg_model());
+        .arg(arg_backend_response_timeout())
+        .arg(arg_model())
+        .arg(arg_fallback_models());
 }
 
 fn subcommand_sessions() -> Command {
@@ -292,6 +321,7 @@ pub fn build() -> Command {
             }
             if line.starts_with("COMMANDS:")
                 || line.starts_with("HOTKEYS:")
+                || line.starts_with("NAVIGATE MODE:")
                 || line.starts_with("CODE ACTIONS:")
             {
                 return Paint::new(format!("CHAT {line}"))
@@ -324,10 +354,13 @@ pub fn build() -> Command {
         .subcommand(subcommand_config())
         .subcommand(subcommand_debug())
         .subcommand(Command::new("manpages").about("Generates manpages and outputs to stdout."))
+        .subcommand(subcommand_serve_editor())
         .subcommand(subcommand_sessions())
         .arg(arg_backend())
         .arg(arg_backend_health_check_timeout())
+        .arg(arg_backend_response_timeout())
         .arg(arg_model())
+        .arg(arg_fallback_models())
         .arg(
             Arg::new(ConfigKey::ConfigFile.to_string())
                 .short('c')
@@ -347,6 +380,31 @@ pub fn build() -> Command {
                 .value_parser(PossibleValuesParser::new(EditorName::VARIANTS))
                 .global(true),
         )
+        .arg(
+            Arg::new(ConfigKey::EditorServerAddress.to_string())
+                .long(ConfigKey::EditorServerAddress.to_string())
+                .env("OATMEAL_EDITOR_SERVER_ADDRESS")
+                .num_args(1)
+                .help(format!("Address `oatmeal serve-editor` listens on. Port 0 picks a free port. [default: {}]", Config::default(ConfigKey::EditorServerAddress)))
+                .global(true),
+        )
+        .arg(
+            Arg::new(ConfigKey::TmuxPane.to_string())
+                .long(ConfigKey::TmuxPane.to_string())
+                .env("OATMEAL_TMUX_PANE")
+                .num_args(1)
+                .help(format!("tmux pane the tmux editor captures context from, and sends code blocks to. Any tmux target, such as %3 or 1.2. [default: {}]", Config::default(ConfigKey::TmuxPane)))
+                .global(true),
+        )
+        .arg(
+            Arg::new(ConfigKey::ClipboardProvider.to_string())
+                .long(ConfigKey::ClipboardProvider.to_string())
+                .env("OATMEAL_CLIPBOARD_PROVIDER")
+                .num_args(1)
+                .help(format!("Clipboard to copy to. `osc52` asks your terminal to copy with OSC 52 escape sequences, which works over SSH and within containers. `auto` uses the system clipboard, falling back to OSC 52 when there isn't one. [default: {}]", Config::default(ConfigKey::ClipboardProvider)))
+                .value_parser(PossibleValuesParser::new(ClipboardProvider::VARIANTS))
+                .global(true),
+        )
         .arg(
             Arg::new(ConfigKey::Theme.to_string())
                 .short('t')
@@ -375,6 +433,22 @@ pub fn build() -> Command {
                 .help(format!("LangChain Serve API URL when using the LangChain backend. [default: {}]", Config::default(ConfigKey::LangChainURL)))
                 .global(true),
         )
+        .arg(
+            Arg::new(ConfigKey::LangChainInputKey.to_string())
+                .long(ConfigKey::LangChainInputKey.to_string())
+                .env("OATMEAL_LANGCHAIN_INPUT_KEY")
+                .num_args(1)
+                .help("Input key that receives the prompt when using the LangChain backend. Detected from the runnable's input schema if not set.")
+                .global(true),
+        )
+        .arg(
+            Arg::new(ConfigKey::LangChainHistoryKey.to_string())
+                .long(ConfigKey::LangChainHistoryKey.to_string())
+                .env("OATMEAL_LANGCHAIN_HISTORY_KEY")
+                .num_args(1)
+                .help("Input key that receives chat history when using the LangChain backend. Detected from the runnable's input schema if not set.")
+                .global(true),
+        )
         .arg(
             Arg::new(ConfigKey::OllamaURL.to_string())
                 .long(ConfigKey::OllamaURL.to_string())
@@ -399,6 +473,30 @@ pub fn build() -> Command {
                 .help("OpenAI API token when using the OpenAI backend.")
                 .global(true),
         )
+        .arg(
+            Arg::new(ConfigKey::AzureOpenAiResource.to_string())
+                .long(ConfigKey::AzureOpenAiResource.to_string())
+                .env("OATMEAL_AZURE_OPENAI_RESOURCE")
+                .num_args(1)
+                .help("Azure OpenAI resource name when using the AzureOpenAI backend. Models are the resource's deployment names.")
+                .global(true),
+        )
+        .arg(
+            Arg::new(ConfigKey::AzureOpenAiToken.to_string())
+                .long(ConfigKey::AzureOpenAiToken.to_string())
+                .env("OATMEAL_AZURE_OPENAI_TOKEN")
+                .num_args(1)
+                .help("Azure OpenAI API key when using the AzureOpenAI backend.")
+                .global(true),
+        )
+        .arg(
+            Arg::new(ConfigKey::AzureOpenAiAPIVersion.to_string())
+                .long(ConfigKey::AzureOpenAiAPIVersion.to_string())
+                .env("OATMEAL_AZURE_OPENAI_API_VERSION")
+                .num_args(1)
+                .help(format!("Azure OpenAI API version when using the AzureOpenAI backend. [default: {}]", Config::default(ConfigKey::AzureOpenAiAPIVersion)))
+                .global(true),
+        )
         .arg(
             Arg::new(ConfigKey::ClaudeToken.to_string())
                 .long(ConfigKey::ClaudeToken.to_string())
@@ -414,6 +512,38 @@ pub fn build() -> Command {
                 .num_args(1)
                 .help("Google Gemini API token when using the Gemini backend.")
                 .global(true),
+        )
+        .arg(
+            Arg::new(ConfigKey::LlamaCppURL.to_string())
+                .long(ConfigKey::LlamaCppURL.to_string())
+                .env("OATMEAL_LLAMACPP_URL")
+                .num_args(1)
+                .help(format!("llama.cpp server URL when using the LlamaCpp backend. [default: {}]", Config::default(ConfigKey::LlamaCppURL)))
+                .global(true),
+        )
+        .arg(
+            Arg::new(ConfigKey::LlamaCppGrammarFile.to_string())
+                .long(ConfigKey::LlamaCppGrammarFile.to_string())
+                .env("OATMEAL_LLAMACPP_GRAMMAR_FILE")
+                .num_args(1)
+                .help("Absolute path to a GBNF grammar file to constrain output when using the LlamaCpp backend.")
+                .global(true),
+        )
+        .arg(
+            Arg::new(ConfigKey::LlamaCppJsonSchemaFile.to_string())
+                .long(ConfigKey::LlamaCppJsonSchemaFile.to_string())
+                .env("OATMEAL_LLAMACPP_JSON_SCHEMA_FILE")
+                .num_args(1)
+                .help("Absolute path to a JSON schema file to constrain output when using the LlamaCpp backend. Takes precedence over the grammar file.")
+                .global(true),
+        )
+        .arg(
+            Arg::new(ConfigKey::LlamaCppNProbs.to_string())
+                .long(ConfigKey::LlamaCppNProbs.to_string())
+                .env("OATMEAL_LLAMACPP_N_PROBS")
+                .num_args(1)
+                .help(format!("Number of top token probabilities to request per token when using the LlamaCpp backend, listed below each response. [default: {}]", Config::default(ConfigKey::LlamaCppNProbs)))
+                .global(true),
         );
 }
 
@@ -442,6 +572,13 @@ pub async fn parse() -> Result<bool> {
                     let res = ConfigKey::VARIANTS.join("\n");
                     println!("{}", res);
                 }
+                Some(("backends", backends_matches)) => {
+                    Config::load(build(), vec![&matches, debug_matches, backends_matches]).await?;
+                    for name in BackendName::iter() {
+                        let backend = BackendManager::get(name.clone())?;
+                        println!("{name}\n{}\n", backend.capabilities().await.format());
+                    }
+                }
                 _ => {
                     subcommand_debug().print_long_help()?;
                 }
@@ -478,6 +615,11 @@ pub async fn parse() -> Result<bool> {
                 }
             }
         }
+        Some(("serve-editor", _)) => {
+            Config::load(build(), vec![&matches]).await?;
+            EditorServer::start().await?;
+            return Ok(false);
+        }
         Some(("manpages", _)) => {
             clap_mangen::Man::new(build()).render(&mut io::stdout())?;
             return Ok(false);
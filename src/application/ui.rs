@@ -21,30 +21,38 @@ use tokio::sync::mpsc;
 
 use crate::configuration::Config;
 use crate::configuration::ConfigKey;
+use crate::configuration::ModelAliases;
 use crate::domain::models::Action;
 use crate::domain::models::Author;
 use crate::domain::models::BackendName;
 use crate::domain::models::BackendPrompt;
 use crate::domain::models::EditorName;
 use crate::domain::models::Event;
-use crate::domain::models::Loading;
 use crate::domain::models::Message;
+use crate::domain::models::MessageType;
 use crate::domain::models::SlashCommand;
 use crate::domain::models::TextArea;
 use crate::domain::services::events::EventsService;
 use crate::domain::services::AppState;
 use crate::domain::services::AppStateProps;
 use crate::domain::services::Bubble;
+use crate::domain::services::DiffPreviewDecision;
+use crate::domain::services::ModelCache;
+use crate::domain::services::ModelPicker;
 use crate::domain::services::Sessions;
+use crate::domain::services::TextWidth;
 use crate::infrastructure::backends::BackendManager;
 use crate::infrastructure::editors::EditorManager;
 
 /// Verifies that the current window size is large enough to handle the bare
-/// minimum width that includes the model name, username, bubbles, and padding.
-fn is_line_width_sufficient(line_width: u16) -> bool {
-    let author_lengths = vec![Author::User, Author::Oatmeal, Author::Model]
-        .into_iter()
-        .map(|e| return e.to_string().len())
+/// minimum width that includes the longest author name in the chat, bubbles,
+/// and padding.
+fn is_line_width_sufficient(line_width: u16, messages: &[Message]) -> bool {
+    let author_lengths = messages
+        .iter()
+        .map(|message| return message.author_name())
+        .chain([Author::User.to_string(), Author::Oatmeal.to_string()])
+        .map(|name| return TextWidth::of(&name))
         .max()
         .unwrap();
 
@@ -57,7 +65,36 @@ fn is_line_width_sufficient(line_width: u16) -> bool {
     return trimmed_line_width >= min_width;
 }
 
-async fn start_loop<B: Backend>(
+/// Opens text in `$EDITOR`, handing it the terminal until it exits. The inner
+/// result is the editor's, which is `None` when it's closed without saving.
+fn edit_in_editor<B: Backend + io::Write>(
+    terminal: &mut Terminal<B>,
+    text: &str,
+) -> Result<io::Result<Option<String>>> {
+    disable_raw_mode()?;
+    crossterm::execute!(
+        terminal.backend_mut(),
+        LeaveAlternateScreen,
+        DisableMouseCapture,
+        DisableBracketedPaste
+    )?;
+    terminal.show_cursor()?;
+
+    let edited = dialoguer::Editor::new().edit(text);
+
+    enable_raw_mode()?;
+    crossterm::execute!(
+        terminal.backend_mut(),
+        EnterAlternateScreen,
+        EnableMouseCapture,
+        EnableBracketedPaste
+    )?;
+    terminal.clear()?;
+
+    return Ok(edited);
+}
+
+async fn start_loop<B: Backend + io::Write>(
     terminal: &mut Terminal<B>,
     app_state_props: AppStateProps,
     tx: mpsc::UnboundedSender<Action>,
@@ -66,7 +103,6 @@ async fn start_loop<B: Backend>(
     let mut events = EventsService::new(rx);
     let mut textarea = TextArea::default();
     let mut app_state = AppState::new(app_state_props).await?;
-    let loading = Loading::default();
 
     #[cfg(feature = "dev")]
     {
@@ -76,7 +112,7 @@ async fn start_loop<B: Backend>(
 
     loop {
         terminal.draw(|frame| {
-            if !is_line_width_sufficient(frame.size().width) {
+            if !is_line_width_sufficient(frame.size().width, &app_state.messages) {
                 frame.render_widget(
                     Paragraph::new("I'm too small, make me bigger!").alignment(Alignment::Left),
                     frame.size(),
@@ -85,9 +121,14 @@ async fn start_loop<B: Backend>(
             }
 
             let textarea_len = (textarea.lines().len() + 3).try_into().unwrap();
+            let queue_len = app_state.prompt_queue.height();
             let layout = Layout::default()
                 .direction(Direction::Vertical)
-                .constraints(vec![Constraint::Min(1), Constraint::Max(textarea_len)])
+                .constraints(vec![
+                    Constraint::Min(1),
+                    Constraint::Max(queue_len),
+                    Constraint::Max(textarea_len),
+                ])
                 .split(frame.size());
 
             if layout[0].width as usize != app_state.last_known_width
@@ -111,10 +152,31 @@ async fn start_loop<B: Backend>(
                 &mut app_state.scroll.scrollbar_state,
             );
 
-            if app_state.waiting_for_backend {
-                loading.render(frame, layout[1]);
+            if queue_len > 0 {
+                app_state.prompt_queue.render(frame, layout[1]);
+            }
+
+            if let Some(notice) = app_state.navigate_notice.as_deref() {
+                textarea.set_block(TextArea::block(notice));
+            } else if app_state.focused_message.is_some() {
+                textarea.set_block(TextArea::block(
+                    "Navigating: ↑/↓ move, y copy, 1-9 copy code block, r re-ask, e edit, d delete, Esc back",
+                ));
+            } else if app_state.waiting_for_backend {
+                textarea.set_block(TextArea::block(
+                    "Waiting for response... Enter queues your prompt",
+                ));
             } else {
-                frame.render_widget(textarea.widget(), layout[1]);
+                textarea.set_block(TextArea::block("Enter prompt"));
+            }
+            frame.render_widget(textarea.widget(), layout[2]);
+
+            if let Some(model_picker) = &app_state.model_picker {
+                model_picker.render(frame, layout[0]);
+            }
+
+            if let Some(diff_preview) = &app_state.diff_preview {
+                diff_preview.render(frame, layout[0]);
             }
         })?;
 
@@ -140,9 +202,11 @@ async fn start_loop<B: Backend>(
                 let mut prompt =
                     BackendPrompt::new(input_str.to_string(), app_state.backend_context.clone());
 
-                if app_state.backend_context.is_empty() && SlashCommand::parse(&input_str).is_none()
-                {
-                    prompt.append_chat_context(&app_state.editor_context);
+                if SlashCommand::parse(&input_str).is_none() {
+                    let contexts = app_state.take_prompt_contexts();
+                    if app_state.backend_context.is_empty() || !contexts.is_empty() {
+                        prompt.append_chat_context(&contexts);
+                    }
                 }
 
                 tx.send(Action::BackendRequest(prompt))?;
@@ -151,18 +215,125 @@ async fn start_loop<B: Backend>(
         }
 
         match events.next().await? {
+            Event::BackendAborted(context) => {
+                app_state.handle_backend_aborted(context);
+                app_state.save_session().await?;
+            }
+            Event::BackendChanged(capabilities) => {
+                app_state.backend_capabilities = capabilities;
+            }
+            Event::BackendFallback(msg, capabilities) => {
+                app_state.add_message(msg);
+                app_state.backend_capabilities = capabilities;
+                app_state.backend_context = "".to_string();
+            }
             Event::BackendMessage(msg) => {
                 app_state.add_message(msg);
                 app_state.waiting_for_backend = false;
             }
+            Event::BackendModelList(backend_name, models, capabilities) => {
+                app_state.waiting_for_backend = false;
+                app_state.model_picker = Some(ModelPicker::new(
+                    backend_name,
+                    models,
+                    ModelAliases::list(),
+                    capabilities,
+                ));
+            }
             Event::BackendPromptResponse(msg) => {
+                // Left over from the response that's being aborted.
+                if app_state.waiting_for_abort {
+                    continue;
+                }
                 app_state.handle_backend_response(msg.clone());
                 if msg.done {
                     app_state.save_session().await?;
                 }
             }
+            Event::ClipboardPasted(text) => {
+                app_state.share_clipboard(&text);
+            }
+            Event::EditorContextAdded(context) => {
+                app_state.share_editor_context(context, false);
+            }
+            Event::EditorContextChanged(context) => {
+                app_state.editor_context = Some(context);
+            }
+            Event::EditorContextRefreshed(context) => {
+                app_state.share_editor_context(context, true);
+            }
             Event::KeyboardCharInput(input) => {
-                if app_state.waiting_for_backend {
+                if let Some(diff_preview) = app_state.diff_preview.as_mut() {
+                    if let Some(decision) = diff_preview.input(input) {
+                        app_state.close_diff_preview(decision, &tx)?;
+                    }
+                    continue;
+                }
+
+                if let Some(model_picker) = app_state.model_picker.as_mut() {
+                    if input.key == tui_textarea::Key::Esc {
+                        app_state.model_picker = None;
+                    } else {
+                        model_picker.input(input);
+                    }
+                    continue;
+                }
+
+                if app_state.focused_message.is_some() {
+                    app_state.navigate_notice = None;
+                    match input.key {
+                        tui_textarea::Key::Esc | tui_textarea::Key::Char('q') => {
+                            app_state.stop_navigating();
+                        }
+                        tui_textarea::Key::Char('k') => app_state.move_focus(-1),
+                        tui_textarea::Key::Char('j') => app_state.move_focus(1),
+                        tui_textarea::Key::Char('g') => app_state.move_focus(isize::MIN),
+                        tui_textarea::Key::Char('G') => app_state.move_focus(isize::MAX),
+                        tui_textarea::Key::Char('y') => app_state.copy_focused_message(&tx)?,
+                        tui_textarea::Key::Char(c @ '1'..='9') => {
+                            let number = c.to_digit(10).unwrap() as usize;
+                            app_state.copy_focused_codeblock(number, &tx)?;
+                        }
+                        tui_textarea::Key::Char('d') => {
+                            app_state.delete_focused_message();
+                            app_state.save_session().await?;
+                        }
+                        tui_textarea::Key::Char('e') => {
+                            let text = app_state.messages[app_state.focused_message.unwrap()]
+                                .text
+                                .to_string();
+                            match edit_in_editor(terminal, &text)? {
+                                Ok(Some(edited)) => {
+                                    textarea = TextArea::default();
+                                    textarea.insert_str(edited.trim_end());
+                                    app_state.stop_navigating();
+                                }
+                                Ok(None) => {}
+                                Err(err) => {
+                                    app_state.add_message(Message::new_with_type(
+                                        Author::Oatmeal,
+                                        MessageType::Error,
+                                        &format!("Failed to open $EDITOR: {err}"),
+                                    ));
+                                }
+                            }
+                        }
+                        tui_textarea::Key::Char('r') => {
+                            if let Some(prompt) = app_state.reask_focused_message() {
+                                if app_state.is_waiting() {
+                                    app_state.prompt_queue.push(prompt);
+                                    continue;
+                                }
+                                send_user_message!(&prompt);
+                            }
+                        }
+                        _ => {}
+                    }
+                    continue;
+                }
+
+                if input.key == tui_textarea::Key::Esc {
+                    app_state.start_navigating();
                     continue;
                 }
 
@@ -174,9 +345,22 @@ async fn start_loop<B: Backend>(
                 textarea.input(input);
             }
             Event::KeyboardCTRLC() => {
+                if app_state.diff_preview.is_some() {
+                    app_state.close_diff_preview(DiffPreviewDecision::Reject, &tx)?;
+                    continue;
+                }
+                if app_state.model_picker.is_some() {
+                    app_state.model_picker = None;
+                    continue;
+                }
+                if app_state.focused_message.is_some() {
+                    app_state.stop_navigating();
+                    continue;
+                }
                 if app_state.waiting_for_backend {
-                    app_state.waiting_for_backend = false;
-                    tx.send(Action::BackendAbort())?;
+                    let partial_text = app_state.interrupt_backend_response();
+                    tx.send(Action::BackendAbort(partial_text))?;
+                    app_state.save_session().await?;
                 } else if !app_state.exit_warning {
                     app_state.add_message(Message::new(
                         Author::Oatmeal,
@@ -188,9 +372,6 @@ async fn start_loop<B: Backend>(
                 }
             }
             Event::KeyboardCTRLO() => {
-                if app_state.waiting_for_backend {
-                    continue;
-                }
                 app_state.exit_warning = false;
                 textarea.insert_newline();
             }
@@ -204,23 +385,80 @@ async fn start_loop<B: Backend>(
                     })
                     .last();
                 if let Some(message) = last_message.cloned() {
+                    if app_state.is_waiting() {
+                        app_state.prompt_queue.push(message.text);
+                        continue;
+                    }
                     send_user_message!(&message.text);
                 }
             }
+            Event::KeyboardCTRLV() => {
+                if app_state.diff_preview.is_some() || app_state.model_picker.is_some() {
+                    continue;
+                }
+
+                app_state.exit_warning = false;
+                if app_state.is_waiting() {
+                    app_state.prompt_queue.push("/paste".to_string());
+                    continue;
+                }
+
+                // Unlike typing /paste, this keeps whatever is in the prompt
+                // box.
+                app_state.handle_slash_commands("/paste", &tx)?;
+            }
             Event::KeyboardEnter() => {
-                if app_state.waiting_for_backend {
+                if app_state.diff_preview.is_some() {
+                    app_state.close_diff_preview(DiffPreviewDecision::Accept, &tx)?;
+                    continue;
+                }
+
+                if let Some(model_picker) = app_state.model_picker.take() {
+                    if let Some(entry) = model_picker.selected() {
+                        send_user_message!(&format!("/model {}", entry.value));
+                    }
+                    continue;
+                }
+
+                if app_state.focused_message.is_some() {
                     continue;
                 }
+
                 let input_str = &textarea.lines().join("\n");
                 if input_str.is_empty() {
                     continue;
                 }
+
+                if let Some(command) = SlashCommand::parse(input_str) {
+                    if command.is_queue() {
+                        textarea = TextArea::default();
+                        if let Some(prompt) = app_state.handle_queue_command(&command) {
+                            textarea.insert_str(prompt);
+                        }
+                        continue;
+                    }
+                    if command.is_quit() && app_state.waiting_for_backend {
+                        break;
+                    }
+                }
+
+                if app_state.is_waiting() {
+                    app_state.prompt_queue.push(input_str.to_string());
+                    textarea = TextArea::default();
+                    continue;
+                }
                 send_user_message!(input_str);
             }
             Event::KeyboardPaste(text) => {
-                if app_state.waiting_for_backend {
+                if app_state.diff_preview.is_some() {
                     continue;
                 }
+
+                if let Some(model_picker) = app_state.model_picker.as_mut() {
+                    model_picker.push_str(&text);
+                    continue;
+                }
+
                 app_state.exit_warning = false;
                 textarea.set_yank_text(text.replace('\r', "\n"));
                 textarea.paste();
@@ -229,9 +467,33 @@ async fn start_loop<B: Backend>(
                 continue;
             }
             Event::UIScrollDown() => {
+                if let Some(diff_preview) = app_state.diff_preview.as_mut() {
+                    diff_preview.down();
+                    continue;
+                }
+                if let Some(model_picker) = app_state.model_picker.as_mut() {
+                    model_picker.down();
+                    continue;
+                }
+                if app_state.focused_message.is_some() {
+                    app_state.move_focus(1);
+                    continue;
+                }
                 app_state.scroll.down();
             }
             Event::UIScrollUp() => {
+                if let Some(diff_preview) = app_state.diff_preview.as_mut() {
+                    diff_preview.up();
+                    continue;
+                }
+                if let Some(model_picker) = app_state.model_picker.as_mut() {
+                    model_picker.up();
+                    continue;
+                }
+                if app_state.focused_message.is_some() {
+                    app_state.move_focus(-1);
+                    continue;
+                }
                 app_state.scroll.up();
             }
             Event::UIScrollPageDown() => {
@@ -241,6 +503,12 @@ async fn start_loop<B: Backend>(
                 app_state.scroll.up_page();
             }
         }
+
+        if !app_state.is_waiting() {
+            if let Some(prompt) = app_state.prompt_queue.next() {
+                send_user_message!(&prompt);
+            }
+        }
     }
 
     return Ok(());
@@ -262,6 +530,7 @@ pub fn destruct_terminal_for_panic() {
 }
 
 pub async fn start(
+    model_cache: ModelCache,
     tx: mpsc::UnboundedSender<Action>,
     rx: mpsc::UnboundedReceiver<Event>,
 ) -> Result<()> {
@@ -294,6 +563,7 @@ pub async fn start(
         theme_file: Config::get(ConfigKey::ThemeFile),
         session_id,
         sessions_service: Sessions::default(),
+        model_cache,
     };
 
     start_loop(&mut terminal, app_state_pros, tx, rx).await?;
@@ -16,6 +16,7 @@ use strum::EnumVariantNames;
 use strum::IntoEnumIterator;
 use tokio::fs;
 
+use super::ModelAliases;
 use crate::domain::models::BackendName;
 use crate::domain::models::EditorName;
 
@@ -26,15 +27,29 @@ static CONFIG: Lazy<DashMap<String, String>> = Lazy::new(DashMap::new);
 pub enum ConfigKey {
     Backend,
     BackendHealthCheckTimeout,
+    BackendResponseTimeout,
     Editor,
+    EditorServerAddress,
+    TmuxPane,
+    ClipboardProvider,
     Model,
+    FallbackModels,
     ConfigFile,
     LangChainURL,
+    LangChainInputKey,
+    LangChainHistoryKey,
     OllamaURL,
     OpenAiToken,
     OpenAiURL,
+    AzureOpenAiResource,
+    AzureOpenAiToken,
+    AzureOpenAiAPIVersion,
     ClaudeToken,
     GeminiToken,
+    LlamaCppURL,
+    LlamaCppGrammarFile,
+    LlamaCppJsonSchemaFile,
+    LlamaCppNProbs,
     SessionID,
     Theme,
     ThemeFile,
@@ -69,7 +84,7 @@ impl Config {
         let default_backend = BackendName::Ollama.to_string();
         let default_editor = EditorName::Clipboard.to_string();
 
-        let mut config_path = dirs::cache_dir().unwrap().join("oatmeal/config.toml");
+        let config_path;
 
         #[cfg(target_os = "macos")]
         {
@@ -77,32 +92,48 @@ impl Config {
                 path::PathBuf::from(env::var("HOME").unwrap()).join(".config/oatmeal/config.toml");
         }
 
-        #[cfg(target_os = "windows")]
-        {
-            config_path = dirs::cache_dir().unwrap().join("oatmeal/config.toml");
-        }
-
         #[cfg(target_os = "linux")]
         {
-            config_path = dirs::cache_dir().unwrap().join("oatmeal/config.toml");
-            if !config_path.exists() {
+            let cache_path = dirs::cache_dir().unwrap().join("oatmeal/config.toml");
+            if cache_path.exists() {
+                config_path = cache_path;
+            } else {
                 config_path = dirs::config_local_dir()
                     .unwrap()
                     .join("oatmeal/config.toml");
             }
         }
 
+        #[cfg(not(any(target_os = "macos", target_os = "linux")))]
+        {
+            config_path = dirs::cache_dir().unwrap().join("oatmeal/config.toml");
+        }
+
         let res = match key {
             ConfigKey::Backend => &default_backend,
             ConfigKey::BackendHealthCheckTimeout => "1000",
+            ConfigKey::BackendResponseTimeout => "30000",
             ConfigKey::Editor => &default_editor,
+            ConfigKey::EditorServerAddress => "127.0.0.1:0",
+            ConfigKey::TmuxPane => "{last}",
+            ConfigKey::ClipboardProvider => "auto",
             ConfigKey::Model => "",
+            ConfigKey::FallbackModels => "",
             ConfigKey::LangChainURL => "http://localhost:8000",
+            ConfigKey::LangChainInputKey => "",
+            ConfigKey::LangChainHistoryKey => "",
             ConfigKey::OllamaURL => "http://localhost:11434",
             ConfigKey::OpenAiToken => "",
             ConfigKey::OpenAiURL => "https://api.openai.com",
+            ConfigKey::AzureOpenAiResource => "",
+            ConfigKey::AzureOpenAiToken => "",
+            ConfigKey::AzureOpenAiAPIVersion => "2024-02-01",
             ConfigKey::ClaudeToken => "",
             ConfigKey::GeminiToken => "",
+            ConfigKey::LlamaCppURL => "http://localhost:8080",
+            ConfigKey::LlamaCppGrammarFile => "",
+            ConfigKey::LlamaCppJsonSchemaFile => "",
+            ConfigKey::LlamaCppNProbs => "0",
             ConfigKey::Theme => "base16-onedark",
             ConfigKey::ThemeFile => "",
 
@@ -129,10 +160,12 @@ impl Config {
             }
         }
 
+        let mut model_aliases = vec![];
         let config_path = path::PathBuf::from(config_file);
         if config_path.exists() {
             let toml_str = fs::read_to_string(config_path).await?;
             let doc = toml_str.parse::<toml_edit::Document>()?;
+            model_aliases = ModelAliases::parse(&doc)?;
 
             for key in ConfigKey::iter() {
                 if let Some(val) = doc.get(&key.to_string()) {
@@ -163,6 +196,13 @@ impl Config {
                             bail!(format!("config.toml has an invalid value for key '{key}': {val_str}\nPossible values are: {}", possible_values.join(", ")));
                         }
                         Config::set(key, val_str);
+                    } else if let Some(val_arr) = val.as_array() {
+                        let val_list = val_arr
+                            .iter()
+                            .filter_map(|e| return e.as_str())
+                            .collect::<Vec<&str>>()
+                            .join(",");
+                        Config::set(key, &val_list);
                     }
                 }
             }
@@ -179,6 +219,10 @@ impl Config {
             }
         }
 
+        ModelAliases::set(model_aliases);
+        ModelAliases::resolve_config();
+        ModelAliases::fallbacks()?;
+
         tracing::debug!(
             username = Config::get(ConfigKey::Username),
             backend = Config::get(ConfigKey::Backend),
@@ -244,6 +288,16 @@ impl Config {
             .collect::<Vec<String>>()
             .join("\n\n");
 
-        return toml_str;
+        let models_example = [
+            "# Aliases for backend and model pairs, usable with `--model` and `/model`. Params are",
+            "# sent as defaults with every request to that model, using the backend's own names.",
+            "# [models]",
+            "# code = \"codellama:latest\"",
+            "# fast = { backend = \"ollama\", model = \"llama3:8b\", params = { temperature = 0.2 } }",
+            "# smart = { backend = \"gemini\", model = \"models/gemini-1.5-pro-latest\" }",
+        ]
+        .join("\n");
+
+        return format!("{toml_str}\n\n{models_example}");
     }
 }
@@ -1,3 +1,5 @@
 mod config;
+mod model_aliases;
 
 pub use config::*;
+pub use model_aliases::*;
@@ -0,0 +1,275 @@
+#[cfg(test)]
+#[path = "model_aliases_test.rs"]
+mod tests;
+
+use anyhow::bail;
+use anyhow::Result;
+use dashmap::DashMap;
+use itertools::Itertools;
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use serde_json::Map;
+use serde_json::Value;
+
+use super::Config;
+use super::ConfigKey;
+use crate::domain::models::BackendName;
+
+static MODEL_ALIASES: Lazy<DashMap<String, ModelAlias>> = Lazy::new(DashMap::new);
+
+/// An entry from the `[models]` config table, mapping a short alias to a
+/// backend and model along with default parameters for its requests.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ModelAlias {
+    pub name: String,
+    pub backend: Option<BackendName>,
+    pub model: String,
+    pub params: Map<String, Value>,
+}
+
+impl ModelAlias {
+    pub fn format(&self) -> String {
+        let mut text = format!("- {}: {}", self.name, self.model);
+        if let Some(backend) = &self.backend {
+            text = format!("- {}: {} ({backend})", self.name, self.model);
+        }
+
+        if !self.params.is_empty() {
+            let params = self
+                .params
+                .iter()
+                .map(|(key, value)| return format!("{key} = {value}"))
+                .join(", ");
+            text += &format!(" [{params}]");
+        }
+
+        return text;
+    }
+}
+
+fn toml_to_json(value: &toml_edit::Value) -> Value {
+    return match value {
+        toml_edit::Value::String(val) => Value::from(val.value().to_string()),
+        toml_edit::Value::Integer(val) => Value::from(*val.value()),
+        toml_edit::Value::Float(val) => Value::from(*val.value()),
+        toml_edit::Value::Boolean(val) => Value::from(*val.value()),
+        toml_edit::Value::Datetime(val) => Value::from(val.value().to_string()),
+        toml_edit::Value::Array(arr) => Value::Array(arr.iter().map(toml_to_json).collect()),
+        toml_edit::Value::InlineTable(table) => Value::Object(
+            table
+                .iter()
+                .map(|(key, val)| return (key.to_string(), toml_to_json(val)))
+                .collect(),
+        ),
+    };
+}
+
+fn parse_alias(name: &str, item: &toml_edit::Item) -> Result<ModelAlias> {
+    let mut alias = ModelAlias {
+        name: name.to_string(),
+        ..ModelAlias::default()
+    };
+
+    if let Some(model) = item.as_str() {
+        alias.model = model.to_string();
+        return Ok(alias);
+    }
+
+    let table = match item.as_table_like() {
+        Some(table) => table,
+        None => bail!(format!(
+            "config.toml has an invalid value for model alias '{name}'. Expected a model name or a table."
+        )),
+    };
+
+    match table.get("model").and_then(|e| return e.as_str()) {
+        Some(model) => alias.model = model.to_string(),
+        None => bail!(format!(
+            "config.toml is missing a model for model alias '{name}'"
+        )),
+    }
+
+    if let Some(backend) = table.get("backend").and_then(|e| return e.as_str()) {
+        match BackendName::parse(backend.to_string()) {
+            Some(backend_name) => alias.backend = Some(backend_name),
+            None => bail!(format!(
+                "config.toml has an invalid backend for model alias '{name}': {backend}"
+            )),
+        }
+    }
+
+    if let Some(params) = table.get("params") {
+        let params_table = match params.as_table_like() {
+            Some(params_table) => params_table,
+            None => bail!(format!(
+                "config.toml has invalid params for model alias '{name}'. Expected a table."
+            )),
+        };
+
+        for (key, val) in params_table.iter() {
+            if let Some(val) = val.as_value() {
+                alias.params.insert(key.to_string(), toml_to_json(val));
+            }
+        }
+    }
+
+    return Ok(alias);
+}
+
+pub struct ModelAliases {}
+
+impl ModelAliases {
+    /// Reads aliases from the `[models]` table of a config file.
+    pub fn parse(doc: &toml_edit::Document) -> Result<Vec<ModelAlias>> {
+        let mut aliases = vec![];
+        if let Some(models) = doc.get("models") {
+            let table = match models.as_table_like() {
+                Some(table) => table,
+                None => bail!("config.toml has an invalid value for 'models'. Expected a table."),
+            };
+
+            for (name, item) in table.iter() {
+                aliases.push(parse_alias(name, item)?);
+            }
+        }
+
+        return Ok(aliases);
+    }
+
+    pub fn set(aliases: Vec<ModelAlias>) {
+        MODEL_ALIASES.clear();
+        for alias in aliases {
+            MODEL_ALIASES.insert(alias.name.to_string(), alias);
+        }
+    }
+
+    pub fn get(name: &str) -> Option<ModelAlias> {
+        return MODEL_ALIASES.get(name).map(|e| return e.value().clone());
+    }
+
+    pub fn list() -> Vec<ModelAlias> {
+        return MODEL_ALIASES
+            .iter()
+            .map(|e| return e.value().clone())
+            .sorted_by(|a, b| return a.name.cmp(&b.name))
+            .collect();
+    }
+
+    /// Replaces an alias set as the model in config with the backend and model
+    /// it points to.
+    pub fn resolve_config() {
+        if let Some(alias) = ModelAliases::get(&Config::get(ConfigKey::Model)) {
+            if let Some(backend) = alias.backend {
+                Config::set(ConfigKey::Backend, &backend.to_string());
+            }
+            Config::set(ConfigKey::Model, &alias.model);
+        }
+    }
+
+    /// Models to retry a prompt on when the active backend fails, in order,
+    /// from the `fallback-models` config.
+    pub fn fallbacks() -> Result<Vec<ModelAlias>> {
+        return parse_fallbacks(
+            &Config::get(ConfigKey::FallbackModels),
+            &ModelAliases::list(),
+        );
+    }
+
+    /// Serializes a backend request, merging in default parameters for the
+    /// active model. Parameters are placed inside the `nested_key` object when
+    /// the backend expects them there, such as Ollama's `options`.
+    pub fn apply_params<T: Serialize>(req: &T, nested_key: Option<&str>) -> Result<Value> {
+        let params = find_params(
+            &ModelAliases::list(),
+            &Config::get(ConfigKey::Backend),
+            &Config::get(ConfigKey::Model),
+        );
+
+        return merge_params(serde_json::to_value(req)?, params, nested_key);
+    }
+}
+
+/// Parses a comma separated fallback list. Each entry is a model alias,
+/// `BACKEND:MODEL`, or a model name on the active backend.
+fn parse_fallbacks(text: &str, aliases: &[ModelAlias]) -> Result<Vec<ModelAlias>> {
+    let mut fallbacks = vec![];
+    for entry in text.split(',').map(|e| return e.trim()) {
+        if entry.is_empty() {
+            continue;
+        }
+
+        if let Some(alias) = aliases.iter().find(|alias| return alias.name == entry) {
+            fallbacks.push(alias.clone());
+            continue;
+        }
+
+        let mut fallback = ModelAlias {
+            name: entry.to_string(),
+            model: entry.to_string(),
+            ..ModelAlias::default()
+        };
+        if let Some((backend, model)) = entry.split_once(':') {
+            if let Some(backend_name) = BackendName::parse(backend.to_string()) {
+                if model.is_empty() {
+                    bail!(format!(
+                        "Fallback model '{entry}' is missing a model name after the backend"
+                    ));
+                }
+                fallback.backend = Some(backend_name);
+                fallback.model = model.to_string();
+            }
+        }
+
+        fallbacks.push(fallback);
+    }
+
+    return Ok(fallbacks);
+}
+
+/// Default parameters for a backend and model, taken from the first alias
+/// pointing at them.
+fn find_params(aliases: &[ModelAlias], backend: &str, model: &str) -> Map<String, Value> {
+    return aliases
+        .iter()
+        .find(|alias| {
+            return alias.model == model
+                && alias
+                    .backend
+                    .as_ref()
+                    .map(|e| return e.to_string() == backend)
+                    .unwrap_or(true);
+        })
+        .map(|alias| return alias.params.clone())
+        .unwrap_or_default();
+}
+
+fn merge_params(
+    mut body: Value,
+    params: Map<String, Value>,
+    nested_key: Option<&str>,
+) -> Result<Value> {
+    if params.is_empty() {
+        return Ok(body);
+    }
+
+    let mut target = match body.as_object_mut() {
+        Some(target) => target,
+        None => bail!("Model params can only be added to JSON object requests"),
+    };
+
+    if let Some(key) = nested_key {
+        let entry = target
+            .entry(key)
+            .or_insert_with(|| return Value::Object(Map::new()));
+        if !entry.is_object() {
+            *entry = Value::Object(Map::new());
+        }
+        target = entry.as_object_mut().unwrap();
+    }
+
+    for (key, val) in params {
+        target.insert(key, val);
+    }
+
+    return Ok(body);
+}
@@ -0,0 +1,171 @@
+use anyhow::Result;
+use serde_json::json;
+
+use super::find_params;
+use super::merge_params;
+use super::parse_fallbacks;
+use super::ModelAliases;
+use crate::domain::models::BackendName;
+
+fn parse(toml_str: &str) -> Result<Vec<super::ModelAlias>> {
+    let doc = toml_str.parse::<toml_edit::Document>()?;
+    return ModelAliases::parse(&doc);
+}
+
+#[test]
+fn it_parses_aliases() -> Result<()> {
+    let aliases = parse(
+        r#"
+backend = "ollama"
+
+[models]
+code = "codellama:latest"
+fast = { backend = "ollama", model = "llama3:8b", params = { temperature = 0.2, stop = ["\n"] } }
+
+[models.smart]
+backend = "gemini"
+model = "models/gemini-1.5-pro-latest"
+"#,
+    )?;
+
+    assert_eq!(aliases.len(), 3);
+
+    assert_eq!(aliases[0].name, "code");
+    assert_eq!(aliases[0].model, "codellama:latest");
+    assert_eq!(aliases[0].backend, None);
+
+    assert_eq!(aliases[1].name, "fast");
+    assert_eq!(aliases[1].backend, Some(BackendName::Ollama));
+    assert_eq!(aliases[1].params.get("temperature"), Some(&json!(0.2)));
+    assert_eq!(aliases[1].params.get("stop"), Some(&json!(["\n"])));
+
+    assert_eq!(aliases[2].name, "smart");
+    assert_eq!(aliases[2].backend, Some(BackendName::Gemini));
+    assert_eq!(aliases[2].model, "models/gemini-1.5-pro-latest");
+
+    return Ok(());
+}
+
+#[test]
+fn it_fails_to_parse_alias_with_invalid_backend() {
+    let res = parse(
+        r#"
+[models]
+fast = { backend = "not-a-real-backend", model = "llama3:8b" }
+"#,
+    );
+
+    assert!(res.is_err());
+}
+
+#[test]
+fn it_fails_to_parse_alias_without_model() {
+    let res = parse(
+        r#"
+[models]
+fast = { backend = "ollama" }
+"#,
+    );
+
+    assert!(res.is_err());
+}
+
+#[test]
+fn it_formats_alias() -> Result<()> {
+    let aliases = parse(
+        r#"
+[models]
+code = "codellama:latest"
+fast = { backend = "ollama", model = "llama3:8b", params = { temperature = 0.2 } }
+"#,
+    )?;
+
+    assert_eq!(aliases[0].format(), "- code: codellama:latest");
+    assert_eq!(
+        aliases[1].format(),
+        "- fast: llama3:8b (ollama) [temperature = 0.2]"
+    );
+
+    return Ok(());
+}
+
+#[test]
+fn it_finds_params_for_backend_and_model() -> Result<()> {
+    let aliases = parse(
+        r#"
+[models]
+fast = { backend = "ollama", model = "llama3:8b", params = { temperature = 0.2 } }
+"#,
+    )?;
+
+    let params = find_params(&aliases, "ollama", "llama3:8b");
+    assert_eq!(params.get("temperature"), Some(&json!(0.2)));
+
+    assert!(find_params(&aliases, "openai", "llama3:8b").is_empty());
+    assert!(find_params(&aliases, "ollama", "llama3:70b").is_empty());
+
+    return Ok(());
+}
+
+#[test]
+fn it_merges_params() -> Result<()> {
+    let aliases = parse(
+        r#"
+[models]
+fast = { model = "llama3:8b", params = { temperature = 0.2 } }
+"#,
+    )?;
+    let params = aliases[0].params.clone();
+
+    let body = merge_params(json!({ "model": "llama3:8b" }), params.clone(), None)?;
+    assert_eq!(body, json!({ "model": "llama3:8b", "temperature": 0.2 }));
+
+    let body = merge_params(
+        json!({ "model": "llama3:8b", "options": null }),
+        params,
+        Some("options"),
+    )?;
+    assert_eq!(
+        body,
+        json!({ "model": "llama3:8b", "options": { "temperature": 0.2 } })
+    );
+
+    return Ok(());
+}
+
+#[test]
+fn it_parses_fallbacks() -> Result<()> {
+    let aliases = parse(
+        r#"
+[models]
+fast = { backend = "ollama", model = "llama3:8b" }
+"#,
+    )?;
+
+    let fallbacks = parse_fallbacks(
+        "claude:claude-3-opus-20240229, fast,ollama:codellama:latest,mistral:7b,",
+        &aliases,
+    )?;
+
+    assert_eq!(fallbacks.len(), 4);
+
+    assert_eq!(fallbacks[0].backend, Some(BackendName::Claude));
+    assert_eq!(fallbacks[0].model, "claude-3-opus-20240229");
+
+    assert_eq!(fallbacks[1], aliases[0]);
+
+    assert_eq!(fallbacks[2].backend, Some(BackendName::Ollama));
+    assert_eq!(fallbacks[2].model, "codellama:latest");
+
+    assert_eq!(fallbacks[3].backend, None);
+    assert_eq!(fallbacks[3].model, "mistral:7b");
+
+    assert!(parse_fallbacks("", &aliases)?.is_empty());
+
+    return Ok(());
+}
+
+#[test]
+fn it_fails_to_parse_fallback_without_model() {
+    assert!(parse_fallbacks("openai:", &[]).is_err());
+}
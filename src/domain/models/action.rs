@@ -1,11 +1,20 @@
 use super::AcceptType;
 use super::BackendPrompt;
 use super::EditorContext;
+use super::FilePatch;
 use super::Message;
 
 pub enum Action {
     AcceptCodeBlock(Option<EditorContext>, String, AcceptType),
-    BackendAbort(),
+    /// Accepted after the user confirmed writing outside of the working
+    /// directory with the file editor.
+    AcceptCodeBlockOutsideRoot(EditorContext, String, AcceptType),
+    ApplyPatches(Vec<FilePatch>),
+    BackendAbort(String),
     BackendRequest(BackendPrompt),
+    ClipboardPaste(),
     CopyMessages(Vec<Message>),
+    EditorContextAdd(),
+    EditorContextRefresh(),
+    TmuxListPanes(),
 }
@@ -16,7 +16,7 @@ impl ToString for Author {
         match self {
             Author::User => return Config::get(ConfigKey::Username),
             Author::Oatmeal => return String::from("Oatmeal"),
-            Author::Model => return Config::get(ConfigKey::Model),
+            Author::Model => return String::from("Model"),
         }
     }
 }
@@ -2,6 +2,8 @@
 #[path = "backend_test.rs"]
 mod tests;
 
+use std::fmt;
+
 use anyhow::Result;
 use async_trait::async_trait;
 use strum::EnumIter;
@@ -21,6 +23,8 @@ pub enum BackendName {
     OpenAI,
     Claude,
     Gemini,
+    LlamaCpp,
+    AzureOpenAI,
 }
 
 impl BackendName {
@@ -29,6 +33,7 @@ impl BackendName {
     }
 }
 
+#[derive(Clone)]
 pub struct BackendPrompt {
     pub text: String,
     pub backend_context: String,
@@ -42,18 +47,28 @@ impl BackendPrompt {
         };
     }
 
-    pub fn append_chat_context(&mut self, editor_context: &Option<EditorContext>) {
-        if let Some(context) = editor_context {
+    /// Appends editor contexts to the prompt. When several contexts are
+    /// shared, each code snippet is labelled with the file it came from.
+    pub fn append_chat_context(&mut self, editor_contexts: &[EditorContext]) {
+        if let Some(context) = editor_contexts.last() {
             let lang = &context.language;
-            let code = &context.code;
 
             let system_prompt =
                 format!(". The coding language is {lang}. Add language to any code blocks.");
             self.text += &system_prompt;
 
-            if !code.is_empty() {
-                let code_prompt = format!(" The code is the following:\n{code}");
-                self.text += &code_prompt;
+            for context in editor_contexts.iter() {
+                let code = &context.code;
+                if code.is_empty() {
+                    continue;
+                }
+
+                if editor_contexts.len() == 1 {
+                    self.text += &format!(" The code is the following:\n{code}");
+                } else {
+                    let summary = context.summary();
+                    self.text += &format!("\n\nThe code from {summary} is the following:\n{code}");
+                }
             }
         } else {
             self.text += ". Add language to any code blocks."
@@ -61,6 +76,80 @@ impl BackendPrompt {
     }
 }
 
+/// Describes which features a backend supports so they can be enabled or
+/// refused up front, rather than assuming the lowest common denominator.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BackendCapabilities {
+    /// Accepts a system prompt separate from the user's messages.
+    pub system_prompt: bool,
+    /// Accepts images alongside prompts.
+    pub images: bool,
+    /// Supports tool (function) calling.
+    pub tool_calls: bool,
+    /// Streams completions as they're generated rather than all at once.
+    pub streaming: bool,
+    /// Keeps chat history between prompts through the backend context.
+    pub stateful_context: bool,
+    /// Maximum context length in tokens, when the backend reports it.
+    pub max_context_length: Option<usize>,
+}
+
+impl BackendCapabilities {
+    pub fn format(&self) -> String {
+        let yes_no = |supported: bool| {
+            if supported {
+                return "yes";
+            }
+            return "no";
+        };
+
+        let max_context_length = self
+            .max_context_length
+            .map(|length| return format!("{length} tokens"))
+            .unwrap_or_else(|| return "unknown".to_string());
+
+        return [
+            format!("- System prompts: {}", yes_no(self.system_prompt)),
+            format!("- Images: {}", yes_no(self.images)),
+            format!("- Tool calls: {}", yes_no(self.tool_calls)),
+            format!("- Streaming: {}", yes_no(self.streaming)),
+            format!("- Chat history: {}", yes_no(self.stateful_context)),
+            format!("- Max context length: {max_context_length}"),
+        ]
+        .join("\n");
+    }
+}
+
+/// A completion request the backend answered with an error status. Kept
+/// apart from other errors so fallbacks can tell a failing server from a
+/// rejected request, like one with a bad token or an unknown model.
+#[derive(Debug)]
+pub struct BackendStatusError {
+    pub message: String,
+    pub status: u16,
+}
+
+impl BackendStatusError {
+    pub fn new(message: &str, status: u16) -> BackendStatusError {
+        return BackendStatusError {
+            message: message.to_string(),
+            status,
+        };
+    }
+
+    pub fn is_server_error(&self) -> bool {
+        return self.status >= 500;
+    }
+}
+
+impl fmt::Display for BackendStatusError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return write!(f, "{}", self.message);
+    }
+}
+
+impl std::error::Error for BackendStatusError {}
+
 #[derive(Clone)]
 pub struct BackendResponse {
     pub author: Author,
@@ -74,12 +163,22 @@ pub trait Backend {
     /// Returns the name of the backend
     fn name(&self) -> BackendName;
 
+    /// Base URL of the server the backend talks to, which keeps cached model
+    /// lists apart for backends pointed at different servers.
+    fn url(&self) -> String;
+
     /// Used at startup to verify all configurations are available to work with
     /// the backend.
     async fn health_check(&self) -> Result<()>;
 
-    /// Called when using the `/modellist` slash commands to provide all
-    /// available models for the backend.
+    /// Features supported by the backend. Called at startup and when using
+    /// the `/modellist` slash command, so backends that probe a server for
+    /// details should time out quickly and fall back to sensible defaults when
+    /// it's unreachable.
+    async fn capabilities(&self) -> BackendCapabilities;
+
+    /// Provides all available models for the backend. Results are cached by
+    /// `ModelCache`, and refreshed with `/modellist --refresh`.
     async fn list_models<'a>(&'a self) -> Result<Vec<String>>;
 
     /// Requests completions from the backend. Completion results may be
@@ -96,6 +195,20 @@ pub trait Backend {
         prompt: BackendPrompt,
         tx: &'a mpsc::UnboundedSender<Event>,
     ) -> Result<()>;
+
+    /// Called when a completion is cancelled part way through streaming.
+    /// Returns a new context containing the prompt and the partial response
+    /// so the model knows what it had already said on the next prompt.
+    ///
+    /// Backends whose context can't be rebuilt from text return `None`, in
+    /// which case the previous context is kept as is.
+    fn interrupted_context(
+        &self,
+        _prompt: &BackendPrompt,
+        _partial_text: &str,
+    ) -> Result<Option<String>> {
+        return Ok(None);
+    }
 }
 
 pub type BackendBox = Box<dyn Backend + Send + Sync>;
@@ -1,10 +1,11 @@
 use super::super::EditorContext;
+use super::BackendCapabilities;
 use super::BackendPrompt;
 
 #[test]
 fn it_adds_default_system_prompt() {
     let mut prompt = BackendPrompt::new("Hello world".to_string(), "".to_string());
-    prompt.append_chat_context(&None);
+    prompt.append_chat_context(&[]);
 
     insta::assert_snapshot!(prompt.text, @"Hello world. Add language to any code blocks.");
 }
@@ -12,13 +13,13 @@ fn it_adds_default_system_prompt() {
 #[test]
 fn it_adds_language_system_prompt() {
     let mut prompt = BackendPrompt::new("Hello world".to_string(), "".to_string());
-    prompt.append_chat_context(&Some(EditorContext {
+    prompt.append_chat_context(&[EditorContext {
         file_path: "./test.rs".to_string(),
         language: "rust".to_string(),
         code: "".to_string(),
         start_line: 0,
         end_line: None,
-    }));
+    }]);
 
     insta::assert_snapshot!(prompt.text, @"Hello world. The coding language is rust. Add language to any code blocks.");
 }
@@ -26,16 +27,68 @@ fn it_adds_language_system_prompt() {
 #[test]
 fn it_adds_language_and_code_system_prompt() {
     let mut prompt = BackendPrompt::new("Hello world".to_string(), "".to_string());
-    prompt.append_chat_context(&Some(EditorContext {
+    prompt.append_chat_context(&[EditorContext {
         file_path: "./test.rs".to_string(),
         language: "rust".to_string(),
         code: "println!(\"Test!\")".to_string(),
         start_line: 0,
         end_line: None,
-    }));
+    }]);
 
     insta::assert_snapshot!(prompt.text, @r###"
     Hello world. The coding language is rust. Add language to any code blocks. The code is the following:
     println!("Test!")
     "###);
 }
+
+#[test]
+fn it_adds_multiple_contexts_system_prompt() {
+    let mut prompt = BackendPrompt::new("Hello world".to_string(), "".to_string());
+    prompt.append_chat_context(&[
+        EditorContext {
+            file_path: "./lib.rs".to_string(),
+            language: "rust".to_string(),
+            code: "pub mod test;".to_string(),
+            start_line: 1,
+            end_line: Some(1),
+        },
+        EditorContext {
+            file_path: "./test.rs".to_string(),
+            language: "rust".to_string(),
+            code: "println!(\"Test!\")".to_string(),
+            start_line: 4,
+            end_line: Some(6),
+        },
+    ]);
+
+    insta::assert_snapshot!(prompt.text, @r###"
+    Hello world. The coding language is rust. Add language to any code blocks.
+
+    The code from ./lib.rs (line 1) is the following:
+    pub mod test;
+
+    The code from ./test.rs (lines 4-6) is the following:
+    println!("Test!")
+    "###);
+}
+
+#[test]
+fn it_formats_capabilities() {
+    let capabilities = BackendCapabilities {
+        system_prompt: true,
+        images: false,
+        tool_calls: false,
+        streaming: true,
+        stateful_context: true,
+        max_context_length: Some(4096),
+    };
+
+    insta::assert_snapshot!(capabilities.format(), @r###"
+    - System prompts: yes
+    - Images: no
+    - Tool calls: no
+    - Streaming: yes
+    - Chat history: yes
+    - Max context length: 4096 tokens
+    "###);
+}
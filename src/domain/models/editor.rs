@@ -9,12 +9,17 @@ use async_trait::async_trait;
 use strum::EnumIter;
 use strum::EnumVariantNames;
 use strum::IntoEnumIterator;
+use tokio::sync::mpsc;
 
 #[derive(Clone, Debug, PartialEq, Eq, EnumIter, EnumVariantNames, strum::Display)]
 #[strum(serialize_all = "lowercase")]
 pub enum EditorName {
     Neovim,
     Clipboard,
+    Emacs,
+    JsonRpc,
+    File,
+    Tmux,
     None,
 }
 
@@ -24,7 +29,7 @@ impl EditorName {
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum AcceptType {
     /// Append in editor where the cursor was last.
     Append,
@@ -51,6 +56,22 @@ pub struct EditorContext {
 }
 
 impl EditorContext {
+    /// File path and selected lines, such as `src/main.rs (lines 4-6)`.
+    pub fn summary(&self) -> String {
+        let file_path = &self.file_path;
+        if self.code.is_empty() {
+            return file_path.to_string();
+        }
+
+        match self.end_line {
+            Some(end_line) if end_line != self.start_line => {
+                return format!("{file_path} (lines {}-{end_line})", self.start_line);
+            }
+            Some(_) => return format!("{file_path} (line {})", self.start_line),
+            None => return file_path.to_string(),
+        }
+    }
+
     pub fn format(&self) -> String {
         let file_path = &self.file_path;
         let language = &self.language;
@@ -100,6 +121,14 @@ pub trait Editor {
         codeblock: String,
         accept_type: AcceptType,
     ) -> Result<()>;
+
+    /// Returns a channel that receives a message whenever the context in the
+    /// editor changes, for editors that can push updates. Editors that can't
+    /// return `None`.
+    #[allow(clippy::implicit_return)]
+    async fn subscribe_context(&self) -> Result<Option<mpsc::UnboundedReceiver<()>>> {
+        return Ok(None);
+    }
 }
 
 pub type EditorBox = Box<dyn Editor + Send + Sync>;
@@ -44,3 +44,42 @@ fn it_renders_with_code() {
     ```
     "###);
 }
+
+#[test]
+fn it_summarizes_selected_lines() {
+    let context = EditorContext {
+        file_path: "file.rs".to_string(),
+        language: "rust".to_string(),
+        code: "let x = 5;\nlet y = 6;".to_string(),
+        start_line: 4,
+        end_line: Some(5),
+    };
+
+    insta::assert_snapshot!(context.summary(), @"file.rs (lines 4-5)");
+}
+
+#[test]
+fn it_summarizes_a_single_line() {
+    let context = EditorContext {
+        file_path: "file.rs".to_string(),
+        language: "rust".to_string(),
+        code: "let x = 5;".to_string(),
+        start_line: 4,
+        end_line: Some(4),
+    };
+
+    insta::assert_snapshot!(context.summary(), @"file.rs (line 4)");
+}
+
+#[test]
+fn it_summarizes_with_no_selection() {
+    let context = EditorContext {
+        file_path: "file.rs".to_string(),
+        language: "rust".to_string(),
+        code: "".to_string(),
+        start_line: 4,
+        end_line: None,
+    };
+
+    insta::assert_snapshot!(context.summary(), @"file.rs");
+}
@@ -1,15 +1,27 @@
 use tui_textarea::Input;
 
+use super::BackendCapabilities;
+use super::BackendName;
 use super::BackendResponse;
+use super::EditorContext;
 use super::Message;
 
 pub enum Event {
+    BackendAborted(Option<String>),
+    BackendChanged(BackendCapabilities),
+    BackendFallback(Message, BackendCapabilities),
     BackendMessage(Message),
+    BackendModelList(BackendName, Vec<String>, BackendCapabilities),
     BackendPromptResponse(BackendResponse),
+    ClipboardPasted(String),
+    EditorContextAdded(EditorContext),
+    EditorContextChanged(EditorContext),
+    EditorContextRefreshed(EditorContext),
     KeyboardCharInput(Input),
     KeyboardCTRLC(),
     KeyboardCTRLO(),
     KeyboardCTRLR(),
+    KeyboardCTRLV(),
     KeyboardEnter(),
     KeyboardPaste(String),
     UITick(),
@@ -0,0 +1,66 @@
+#[cfg(test)]
+#[path = "markdown_test.rs"]
+mod tests;
+
+use pulldown_cmark::CodeBlockKind;
+use pulldown_cmark::Event;
+use pulldown_cmark::Options;
+use pulldown_cmark::Parser;
+use pulldown_cmark::Tag;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MarkdownCodeBlock {
+    /// Text following the opening fence, such as `rust src/main.rs`. Empty
+    /// for indented code blocks.
+    pub info: String,
+    pub code: String,
+}
+
+pub struct Markdown {}
+
+impl Markdown {
+    /// Parses CommonMark, along with the GitHub extensions models tend to
+    /// use. Chat bubbles and code block actions both go through here, so
+    /// they always agree on what's a code block.
+    pub fn parse(text: &str) -> Parser<'_, '_> {
+        let options =
+            Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS;
+        return Parser::new_ext(text, options);
+    }
+
+    pub fn codeblocks(text: &str) -> Vec<MarkdownCodeBlock> {
+        let mut codeblocks = vec![];
+        let mut current: Option<MarkdownCodeBlock> = None;
+
+        for event in Markdown::parse(text) {
+            match event {
+                Event::Start(Tag::CodeBlock(kind)) => {
+                    let info = match kind {
+                        CodeBlockKind::Fenced(info) => info.trim().to_string(),
+                        CodeBlockKind::Indented => "".to_string(),
+                    };
+                    current = Some(MarkdownCodeBlock {
+                        info,
+                        code: "".to_string(),
+                    });
+                }
+                Event::Text(text) => {
+                    if let Some(codeblock) = current.as_mut() {
+                        codeblock.code += &text;
+                    }
+                }
+                Event::End(Tag::CodeBlock(_)) => {
+                    if let Some(mut codeblock) = current.take() {
+                        if codeblock.code.ends_with('\n') {
+                            codeblock.code.pop();
+                        }
+                        codeblocks.push(codeblock);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        return codeblocks;
+    }
+}
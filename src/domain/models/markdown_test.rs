@@ -0,0 +1,54 @@
+use super::Markdown;
+use super::MarkdownCodeBlock;
+
+fn codeblock(info: &str, code: &str) -> MarkdownCodeBlock {
+    return MarkdownCodeBlock {
+        info: info.to_string(),
+        code: code.to_string(),
+    };
+}
+
+#[test]
+fn it_finds_fenced_codeblocks() {
+    let codeblocks =
+        Markdown::codeblocks("Hi!\n\n```rust src/main.rs\nfn main() {}\n```\n\n~~~\nabc123\n~~~");
+    assert_eq!(
+        codeblocks,
+        vec![
+            codeblock("rust src/main.rs", "fn main() {}"),
+            codeblock("", "abc123"),
+        ]
+    );
+}
+
+#[test]
+fn it_finds_indented_codeblocks() {
+    let codeblocks = Markdown::codeblocks("Run this:\n\n    cargo test\n    cargo build\n\nDone.");
+    assert_eq!(codeblocks, vec![codeblock("", "cargo test\ncargo build")]);
+}
+
+#[test]
+fn it_finds_nested_fences_as_one_codeblock() {
+    let codeblocks =
+        Markdown::codeblocks("````markdown\n# README\n\n```rust\nfn main() {}\n```\n````");
+    assert_eq!(
+        codeblocks,
+        vec![codeblock(
+            "markdown",
+            "# README\n\n```rust\nfn main() {}\n```"
+        )]
+    );
+}
+
+#[test]
+fn it_finds_codeblocks_in_lists() {
+    let codeblocks =
+        Markdown::codeblocks("1. Install it:\n\n   ```sh\n   cargo install oatmeal\n   ```");
+    assert_eq!(codeblocks, vec![codeblock("sh", "cargo install oatmeal")]);
+}
+
+#[test]
+fn it_finds_codeblocks_that_are_still_streaming() {
+    let codeblocks = Markdown::codeblocks("```rust\nfn main() {");
+    assert_eq!(codeblocks, vec![codeblock("rust", "fn main() {")]);
+}
@@ -5,11 +5,15 @@ use serde::Deserialize;
 use serde::Serialize;
 
 use super::Author;
+use super::EditorContext;
+use super::Markdown;
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum MessageType {
     Normal,
     Error,
+    /// Code shared from an editor with `/context`.
+    Context,
 }
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -17,6 +21,14 @@ pub struct Message {
     pub author: Author,
     pub text: String,
     mtype: MessageType,
+    #[serde(default)]
+    interrupted: bool,
+    #[serde(default)]
+    collapsed: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backend: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
 }
 
 impl Message {
@@ -25,48 +37,119 @@ impl Message {
             author: author.clone(),
             text: text.to_string().replace('\t', "  "),
             mtype: MessageType::Normal,
+            interrupted: false,
+            collapsed: false,
+            backend: None,
+            model: None,
         };
     }
 
+    /// Creates a model message attributed to the backend and model that
+    /// wrote it, so its bubble keeps the name after switching models.
+    pub fn new_from_model(backend: &str, model: &str, text: &str) -> Message {
+        let mut message = Message::new(Author::Model, text);
+        message.set_model(backend, model);
+        return message;
+    }
+
     pub fn new_with_type(author: Author, mtype: MessageType, text: &str) -> Message {
         return Message {
             author: author.clone(),
             text: text.to_string().replace('\t', "  "),
             mtype,
+            interrupted: false,
+            collapsed: false,
+            backend: None,
+            model: None,
         };
     }
 
+    /// Creates a collapsed bubble for code shared from an editor, numbered so
+    /// it can be expanded with `/context show NUMBER`.
+    pub fn new_context(number: usize, context: &EditorContext) -> Message {
+        let mut message = Message::new_with_type(
+            Author::Oatmeal,
+            MessageType::Context,
+            &format!("Context {number} - {}", context.format()),
+        );
+        message.collapsed = true;
+        return message;
+    }
+
+    pub fn set_model(&mut self, backend: &str, model: &str) {
+        self.backend = Some(backend.to_string());
+        self.model = Some(model.to_string());
+    }
+
+    /// Name shown for the author, which is the model that wrote the message
+    /// when it's known.
+    pub fn author_name(&self) -> String {
+        if let Some(model) = &self.model {
+            if !model.is_empty() {
+                return model.to_string();
+            }
+        }
+
+        return self.author.to_string();
+    }
+
     pub fn message_type(&self) -> MessageType {
         return self.mtype;
     }
 
+    /// True when the response was cancelled before the backend finished
+    /// streaming it.
+    pub fn is_interrupted(&self) -> bool {
+        return self.interrupted;
+    }
+
+    pub fn set_interrupted(&mut self) {
+        self.interrupted = true;
+    }
+
+    /// True when only the first line of the message is shown.
+    pub fn is_collapsed(&self) -> bool {
+        return self.collapsed;
+    }
+
+    pub fn set_collapsed(&mut self, collapsed: bool) {
+        self.collapsed = collapsed;
+    }
+
     pub fn append(&mut self, text: &str) {
         self.text += &text.replace('\t', "  ");
     }
 
     pub fn codeblocks(&self) -> Vec<String> {
-        let mut codeblocks: Vec<String> = vec![];
-        let mut current_codeblock: Vec<&str> = vec![];
-        let mut in_codeblock = false;
-
-        for line in self.text.split('\n') {
-            let trimmed = line.trim();
-            if trimmed.starts_with("```") {
-                if in_codeblock {
-                    codeblocks.push(current_codeblock.join("\n"));
-                    current_codeblock = vec![];
-                    in_codeblock = false
-                } else {
-                    in_codeblock = true;
-                }
-                continue;
-            }
+        return Markdown::codeblocks(&self.text)
+            .into_iter()
+            .map(|codeblock| return codeblock.code)
+            .collect();
+    }
 
-            if in_codeblock {
-                current_codeblock.push(line);
-            }
-        }
+    /// Languages following the opening fence of each code block.
+    pub fn codeblock_languages(&self) -> Vec<String> {
+        return Markdown::codeblocks(&self.text)
+            .into_iter()
+            .map(|codeblock| {
+                return codeblock
+                    .info
+                    .split_whitespace()
+                    .next()
+                    .unwrap_or_default()
+                    .to_string();
+            })
+            .collect();
+    }
 
-        return codeblocks;
+    /// File paths hinted after the language of each code block's fence, such
+    /// as ```` ```rust src/main.rs ````.
+    pub fn codeblock_file_paths(&self) -> Vec<Option<String>> {
+        return Markdown::codeblocks(&self.text)
+            .into_iter()
+            .map(|codeblock| {
+                return codeblock.info.split_whitespace().nth(1).map(str::to_string);
+            })
+            .collect();
     }
 }
@@ -1,6 +1,8 @@
+use anyhow::Result;
 use test_utils::codeblock_fixture;
 
 use super::Author;
+use super::EditorContext;
 use super::Message;
 use super::MessageType;
 
@@ -40,12 +42,57 @@ fn it_executes_new_with_type_replacing_tabs() {
     assert_eq!(msg.mtype, MessageType::Error);
 }
 
+#[test]
+fn it_executes_new_from_model() {
+    let msg = Message::new_from_model("ollama", "codellama:latest", "Hi there!");
+    assert_eq!(msg.author, Author::Model);
+    assert_eq!(msg.backend, Some("ollama".to_string()));
+    assert_eq!(msg.model, Some("codellama:latest".to_string()));
+    assert_eq!(msg.author_name(), "codellama:latest");
+}
+
+#[test]
+fn it_executes_author_name_without_model() {
+    assert_eq!(
+        Message::new(Author::Model, "Hi there!").author_name(),
+        "Model"
+    );
+    assert_eq!(
+        Message::new(Author::Oatmeal, "Hi there!").author_name(),
+        "Oatmeal"
+    );
+}
+
+#[test]
+fn it_deserializes_messages_without_model() -> Result<()> {
+    let msg: Message = serde_yaml::from_str(
+        r#"
+author: Model
+text: Hi there!
+mtype: Normal
+"#,
+    )?;
+
+    assert_eq!(msg.backend, None);
+    assert_eq!(msg.model, None);
+
+    return Ok(());
+}
+
 #[test]
 fn it_executes_message_type() {
     let msg = Message::new_with_type(Author::Oatmeal, MessageType::Error, "It broke!");
     assert_eq!(msg.message_type(), MessageType::Error);
 }
 
+#[test]
+fn it_executes_set_interrupted() {
+    let mut msg = Message::new(Author::Model, "Hi there!");
+    assert!(!msg.is_interrupted());
+    msg.set_interrupted();
+    assert!(msg.is_interrupted());
+}
+
 #[test]
 fn it_executes_append() {
     let mut msg = Message::new(Author::Oatmeal, "Hi there!");
@@ -94,3 +141,41 @@ fn it_executes_codeblocks() {
         print(i)
     "###);
 }
+
+#[test]
+fn it_executes_codeblock_file_paths() {
+    let msg = Message::new(
+        Author::Model,
+        "```rust src/main.rs\nfn main() {}\n```\n\n```rust\nlet x = 5;\n```",
+    );
+
+    assert_eq!(
+        msg.codeblock_file_paths(),
+        vec![Some("src/main.rs".to_string()), None]
+    );
+    assert_eq!(msg.codeblock_languages(), vec!["rust", "rust"]);
+    assert_eq!(msg.codeblocks(), vec!["fn main() {}", "let x = 5;"]);
+}
+
+#[test]
+fn it_executes_new_context() {
+    let context = EditorContext {
+        file_path: "file.rs".to_string(),
+        language: "rust".to_string(),
+        code: "let x = 5;".to_string(),
+        start_line: 0,
+        end_line: Some(1),
+    };
+    let msg = Message::new_context(2, &context);
+
+    assert_eq!(msg.author, Author::Oatmeal);
+    assert_eq!(msg.mtype, MessageType::Context);
+    assert!(msg.is_collapsed());
+    insta::assert_snapshot!(msg.text, @r###"
+    Context 2 - File: file.rs
+
+    ```rust
+    let x = 5;
+    ```
+    "###);
+}
@@ -3,8 +3,9 @@ mod author;
 mod backend;
 mod editor;
 mod event;
-mod loading;
+mod markdown;
 mod message;
+mod patch;
 mod session;
 mod slash_commands;
 mod textarea;
@@ -14,8 +15,9 @@ pub use author::*;
 pub use backend::*;
 pub use editor::*;
 pub use event::*;
-pub use loading::*;
+pub use markdown::*;
 pub use message::*;
+pub use patch::*;
 pub use session::*;
 pub use slash_commands::*;
 pub use textarea::*;
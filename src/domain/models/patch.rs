@@ -0,0 +1,348 @@
+#[cfg(test)]
+#[path = "patch_test.rs"]
+mod tests;
+
+use anyhow::bail;
+use anyhow::Result;
+
+const SEARCH_MARKER: &str = "<<<<<<< SEARCH";
+const DIVIDER_MARKER: &str = "=======";
+const REPLACE_MARKER: &str = ">>>>>>> REPLACE";
+
+/// Context lines that can be dropped from each end of a hunk that doesn't
+/// match as is, similar to the fuzz factor of `patch`.
+const MAX_FUZZ: usize = 2;
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PatchHunk {
+    /// Names the hunk in reports, such as `@@ -1,3 +1,4 @@`.
+    pub header: String,
+    pub search: Vec<String>,
+    pub replace: Vec<String>,
+    pub leading_context: usize,
+    pub trailing_context: usize,
+    /// 1-based line the hunk is expected to start at, when known.
+    pub line: Option<usize>,
+}
+
+impl PatchHunk {
+    fn has_changes(&self) -> bool {
+        return self.search != self.replace;
+    }
+
+    fn push_context(&mut self, line: &str) {
+        if self.search.len() == self.leading_context && self.replace.len() == self.leading_context {
+            self.leading_context += 1;
+        } else {
+            self.trailing_context += 1;
+        }
+
+        self.search.push(line.to_string());
+        self.replace.push(line.to_string());
+    }
+
+    fn push_removed(&mut self, line: &str) {
+        self.trailing_context = 0;
+        self.search.push(line.to_string());
+    }
+
+    fn push_added(&mut self, line: &str) {
+        self.trailing_context = 0;
+        self.replace.push(line.to_string());
+    }
+}
+
+pub struct PatchResult {
+    pub contents: String,
+    pub applied: usize,
+    pub failed: Vec<PatchHunk>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FilePatch {
+    pub file_path: String,
+    pub hunks: Vec<PatchHunk>,
+}
+
+fn push_hunk(
+    patches: &mut Vec<FilePatch>,
+    hunk: Option<PatchHunk>,
+    file_path: Option<&str>,
+) -> Result<()> {
+    let hunk = match hunk {
+        Some(hunk) if hunk.has_changes() => hunk,
+        _ => return Ok(()),
+    };
+
+    let file_path = match file_path {
+        Some(file_path) => file_path,
+        None => bail!(format!(
+            "Hunk {} doesn't say which file it's for. Add a `+++ b/PATH` header, or the path after the language of the code block.",
+            hunk.header
+        )),
+    };
+
+    if let Some(patch) = patches.last_mut() {
+        if patch.file_path == file_path {
+            patch.hunks.push(hunk);
+            return Ok(());
+        }
+    }
+
+    patches.push(FilePatch {
+        file_path: file_path.to_string(),
+        hunks: vec![hunk],
+    });
+
+    return Ok(());
+}
+
+/// Reads the path from a `---` or `+++` header, dropping git's `a/` and `b/`
+/// prefixes. `/dev/null` has no path.
+fn header_path(line: &str) -> Option<String> {
+    let path = line[4..].split('\t').next().unwrap_or_default().trim();
+    if path.is_empty() || path == "/dev/null" {
+        return None;
+    }
+
+    let path = path
+        .strip_prefix("a/")
+        .or_else(|| return path.strip_prefix("b/"))
+        .unwrap_or(path);
+
+    return Some(path.to_string());
+}
+
+/// Reads the old start line from a hunk header such as `@@ -12,5 +12,6 @@`.
+fn header_line(line: &str) -> Option<usize> {
+    let start = line.trim_start_matches('@').trim().strip_prefix('-')?;
+    let digits = start
+        .chars()
+        .take_while(|c| return c.is_ascii_digit())
+        .collect::<String>();
+
+    return digits.parse::<usize>().ok();
+}
+
+fn parse_unified_diff(code: &str, file_path: Option<&str>) -> Result<Vec<FilePatch>> {
+    let mut patches = vec![];
+    let mut file_path = file_path.map(str::to_string);
+    let mut hunk: Option<PatchHunk> = None;
+    let mut lines = code.lines().peekable();
+
+    while let Some(line) = lines.next() {
+        let is_file_header = line.starts_with("--- ")
+            && lines
+                .peek()
+                .map(|next| return next.starts_with("+++ "))
+                .unwrap_or(false);
+
+        if is_file_header {
+            push_hunk(&mut patches, hunk.take(), file_path.as_deref())?;
+            let old_path = header_path(line);
+            let new_path = header_path(lines.next().unwrap());
+            file_path = new_path.or(old_path).or(file_path);
+            continue;
+        }
+
+        if line.starts_with("@@") {
+            push_hunk(&mut patches, hunk.take(), file_path.as_deref())?;
+            hunk = Some(PatchHunk {
+                header: line.trim().to_string(),
+                line: header_line(line),
+                ..PatchHunk::default()
+            });
+            continue;
+        }
+
+        if line.starts_with("diff ") {
+            push_hunk(&mut patches, hunk.take(), file_path.as_deref())?;
+            continue;
+        }
+
+        if let Some(hunk) = hunk.as_mut() {
+            if let Some(removed) = line.strip_prefix('-') {
+                hunk.push_removed(removed);
+            } else if let Some(added) = line.strip_prefix('+') {
+                hunk.push_added(added);
+            } else if line.starts_with('\\') {
+                // "\ No newline at end of file"
+            } else {
+                // Models often drop the leading space of context lines.
+                hunk.push_context(line.strip_prefix(' ').unwrap_or(line));
+            }
+        }
+    }
+
+    push_hunk(&mut patches, hunk, file_path.as_deref())?;
+
+    return Ok(patches);
+}
+
+fn parse_search_replace(code: &str, file_path: Option<&str>) -> Result<Vec<FilePatch>> {
+    let mut patches = vec![];
+    let mut file_path = file_path.map(str::to_string);
+    let mut previous_line = "";
+    let mut hunk: Option<PatchHunk> = None;
+    let mut in_replace = false;
+    let mut count = 0;
+
+    for line in code.lines() {
+        let marker = line.trim_end();
+        match hunk.as_mut() {
+            None if marker == SEARCH_MARKER => {
+                count += 1;
+                // The path can be given on the line before the block.
+                if !previous_line.is_empty() && !previous_line.starts_with("```") {
+                    file_path = Some(previous_line.to_string());
+                }
+
+                hunk = Some(PatchHunk {
+                    header: format!("SEARCH/REPLACE block {count}"),
+                    ..PatchHunk::default()
+                });
+            }
+            None => {
+                previous_line = line.trim();
+            }
+            Some(_) if !in_replace && marker == DIVIDER_MARKER => {
+                in_replace = true;
+            }
+            Some(_) if in_replace && marker == REPLACE_MARKER => {
+                push_hunk(&mut patches, hunk.take(), file_path.as_deref())?;
+                in_replace = false;
+                previous_line = "";
+            }
+            Some(hunk) if in_replace => hunk.replace.push(line.to_string()),
+            Some(hunk) => hunk.search.push(line.to_string()),
+        }
+    }
+
+    if hunk.is_some() {
+        bail!(format!(
+            "SEARCH/REPLACE block {count} isn't closed with {REPLACE_MARKER}"
+        ));
+    }
+
+    return Ok(patches);
+}
+
+fn exact(line: &str) -> &str {
+    return line;
+}
+
+/// Finds where a hunk applies, preferring the match closest to `hint`. Lines
+/// are compared exactly first, then ignoring whitespace, and then with context
+/// lines dropped from each end. Returns the start, the number of lines to
+/// remove, and the lines to insert.
+fn find_hunk(
+    lines: &[String],
+    hunk: &PatchHunk,
+    hint: Option<usize>,
+) -> Option<(usize, usize, Vec<String>)> {
+    if hunk.search.is_empty() {
+        let start = hint.unwrap_or(lines.len()).min(lines.len());
+        return Some((start, 0, hunk.replace.clone()));
+    }
+
+    let matchers: [fn(&str) -> &str; 3] = [exact, str::trim_end, str::trim];
+    for fuzz in 0..=MAX_FUZZ {
+        let front = fuzz.min(hunk.leading_context);
+        let back = fuzz.min(hunk.trailing_context);
+        if fuzz > 0 && front + back == 0 {
+            break;
+        }
+
+        let search = &hunk.search[front..hunk.search.len() - back];
+        let replace = &hunk.replace[front..hunk.replace.len() - back];
+        if search.is_empty() || search.len() > lines.len() {
+            continue;
+        }
+
+        let target = hint.unwrap_or_default() + front;
+        for matcher in matchers {
+            let best = (0..=lines.len() - search.len())
+                .filter(|start| {
+                    return search
+                        .iter()
+                        .enumerate()
+                        .all(|(idx, line)| return matcher(&lines[start + idx]) == matcher(line));
+                })
+                .min_by_key(|start| return start.abs_diff(target));
+
+            if let Some(start) = best {
+                return Some((start, search.len(), replace.to_vec()));
+            }
+        }
+    }
+
+    return None;
+}
+
+impl FilePatch {
+    /// Parses a code block as a unified diff, or as SEARCH/REPLACE edit
+    /// blocks. `file_path` is used when the code block doesn't name the file
+    /// itself. Returns `None` when the code block is neither.
+    pub fn parse(
+        code: &str,
+        language: &str,
+        file_path: Option<&str>,
+    ) -> Result<Option<Vec<FilePatch>>> {
+        if code
+            .lines()
+            .any(|line| return line.trim_end() == SEARCH_MARKER)
+        {
+            return Ok(Some(parse_search_replace(code, file_path)?));
+        }
+
+        let trimmed = code.trim_start();
+        if ["diff", "patch", "udiff"].contains(&language)
+            || trimmed.starts_with("--- ")
+            || trimmed.starts_with("diff --git")
+        {
+            return Ok(Some(parse_unified_diff(code, file_path)?));
+        }
+
+        return Ok(None);
+    }
+
+    /// Applies each hunk in order to file contents, skipping hunks that don't
+    /// match.
+    pub fn apply(&self, contents: &str) -> PatchResult {
+        let mut lines = contents
+            .lines()
+            .map(str::to_string)
+            .collect::<Vec<String>>();
+        let mut offset: i64 = 0;
+        let mut applied = 0;
+        let mut failed = vec![];
+
+        for hunk in &self.hunks {
+            let hint = hunk.line.map(|line| {
+                return usize::try_from((i64::try_from(line).unwrap() - 1 + offset).max(0))
+                    .unwrap();
+            });
+
+            match find_hunk(&lines, hunk, hint) {
+                Some((start, removed, replace)) => {
+                    offset +=
+                        i64::try_from(replace.len()).unwrap() - i64::try_from(removed).unwrap();
+                    lines.splice(start..start + removed, replace);
+                    applied += 1;
+                }
+                None => failed.push(hunk.clone()),
+            }
+        }
+
+        let mut contents = lines.join("\n");
+        if !contents.is_empty() {
+            contents += "\n";
+        }
+
+        return PatchResult {
+            contents,
+            applied,
+            failed,
+        };
+    }
+}
@@ -0,0 +1,254 @@
+use anyhow::Result;
+
+use super::FilePatch;
+
+const CONTENTS: &str = r#"fn main() {
+    let x = 5;
+    let y = 6;
+    println!("{x} {y}");
+}
+
+fn other() {
+    let z = 7;
+}
+"#;
+
+fn parse(code: &str, language: &str, file_path: Option<&str>) -> Result<Vec<FilePatch>> {
+    return Ok(FilePatch::parse(code, language, file_path)?.unwrap());
+}
+
+#[test]
+fn it_ignores_other_code_blocks() -> Result<()> {
+    let res = FilePatch::parse("fn main() {}", "rust", Some("src/main.rs"))?;
+    assert!(res.is_none());
+
+    return Ok(());
+}
+
+#[test]
+fn it_parses_unified_diffs() -> Result<()> {
+    let patches = parse(
+        r#"--- a/src/main.rs
++++ b/src/main.rs
+@@ -1,4 +1,4 @@
+ fn main() {
+-    let x = 5;
++    let x = 10;
+     let y = 6;
+     println!("{x} {y}");
+@@ -8,2 +8,3 @@
+     let z = 7;
++    println!("{z}");
+ }"#,
+        "diff",
+        None,
+    )?;
+
+    assert_eq!(patches.len(), 1);
+    assert_eq!(patches[0].file_path, "src/main.rs");
+    assert_eq!(patches[0].hunks.len(), 2);
+
+    let hunk = &patches[0].hunks[0];
+    assert_eq!(hunk.header, "@@ -1,4 +1,4 @@");
+    assert_eq!(hunk.line, Some(1));
+    assert_eq!(hunk.leading_context, 1);
+    assert_eq!(hunk.trailing_context, 2);
+
+    return Ok(());
+}
+
+#[test]
+fn it_parses_diffs_for_several_files() -> Result<()> {
+    let patches = parse(
+        r#"diff --git a/src/one.rs b/src/one.rs
+--- a/src/one.rs
++++ b/src/one.rs
+@@ -1 +1 @@
+-one
++1
+diff --git a/src/two.rs b/src/two.rs
+--- /dev/null
++++ b/src/two.rs
+@@ -0,0 +1 @@
++2"#,
+        "diff",
+        None,
+    )?;
+
+    let paths = patches
+        .iter()
+        .map(|patch| return patch.file_path.to_string())
+        .collect::<Vec<String>>();
+    assert_eq!(paths, vec!["src/one.rs", "src/two.rs"]);
+
+    return Ok(());
+}
+
+#[test]
+fn it_uses_the_code_block_file_path_for_diffs() -> Result<()> {
+    let patches = parse(
+        "@@ -2 +2 @@\n-    let x = 5;\n+    let x = 10;",
+        "diff",
+        Some("src/main.rs"),
+    )?;
+    assert_eq!(patches[0].file_path, "src/main.rs");
+
+    return Ok(());
+}
+
+#[test]
+fn it_fails_diffs_without_a_file_path() {
+    let err = FilePatch::parse("@@ -2 +2 @@\n-a\n+b", "diff", None).unwrap_err();
+    insta::assert_snapshot!(err.to_string(), @"Hunk @@ -2 +2 @@ doesn't say which file it's for. Add a `+++ b/PATH` header, or the path after the language of the code block.");
+}
+
+#[test]
+fn it_parses_search_replace_blocks() -> Result<()> {
+    let patches = parse(
+        r#"src/main.rs
+<<<<<<< SEARCH
+    let x = 5;
+=======
+    let x = 10;
+>>>>>>> REPLACE
+
+<<<<<<< SEARCH
+    let z = 7;
+=======
+    let z = 70;
+>>>>>>> REPLACE"#,
+        "rust",
+        None,
+    )?;
+
+    assert_eq!(patches.len(), 1);
+    assert_eq!(patches[0].file_path, "src/main.rs");
+    assert_eq!(patches[0].hunks.len(), 2);
+    assert_eq!(patches[0].hunks[1].header, "SEARCH/REPLACE block 2");
+    assert_eq!(patches[0].hunks[1].search, vec!["    let z = 7;"]);
+    assert_eq!(patches[0].hunks[1].replace, vec!["    let z = 70;"]);
+
+    return Ok(());
+}
+
+#[test]
+fn it_fails_unclosed_search_replace_blocks() {
+    let err =
+        FilePatch::parse("<<<<<<< SEARCH\na\n=======\nb", "rust", Some("src/main.rs")).unwrap_err();
+    insta::assert_snapshot!(err.to_string(), @"SEARCH/REPLACE block 1 isn't closed with >>>>>>> REPLACE");
+}
+
+#[test]
+fn it_applies_unified_diffs() -> Result<()> {
+    let patches = parse(
+        r#"--- a/src/main.rs
++++ b/src/main.rs
+@@ -1,4 +1,4 @@
+ fn main() {
+-    let x = 5;
++    let x = 10;
+     let y = 6;
+     println!("{x} {y}");
+@@ -8,2 +8,3 @@
+     let z = 7;
++    println!("{z}");
+ }"#,
+        "diff",
+        None,
+    )?;
+
+    let res = patches[0].apply(CONTENTS);
+    assert_eq!(res.applied, 2);
+    assert!(res.failed.is_empty());
+    insta::assert_snapshot!(res.contents, @r###"
+    fn main() {
+        let x = 10;
+        let y = 6;
+        println!("{x} {y}");
+    }
+
+    fn other() {
+        let z = 7;
+        println!("{z}");
+    }
+    "###);
+
+    return Ok(());
+}
+
+#[test]
+fn it_applies_hunks_with_wrong_line_numbers_and_whitespace() -> Result<()> {
+    let patches = parse(
+        "@@ -40,3 +40,3 @@\nfn other() {\n-  let z = 7;\n+    let z = 70;\n}",
+        "diff",
+        Some("src/main.rs"),
+    )?;
+
+    let res = patches[0].apply(CONTENTS);
+    assert_eq!(res.applied, 1);
+    assert!(res.contents.contains("    let z = 70;\n}"));
+
+    return Ok(());
+}
+
+#[test]
+fn it_applies_hunks_with_mismatched_context() -> Result<()> {
+    let patches = parse(
+        "@@ -1,3 +1,3 @@\n fn main() {\n-    let y = 6;\n+    let y = 60;\n     println!(\"{y}\");",
+        "diff",
+        Some("src/main.rs"),
+    )?;
+
+    let res = patches[0].apply(CONTENTS);
+    assert_eq!(res.applied, 1);
+    assert!(res.contents.contains("    let y = 60;\n"));
+
+    return Ok(());
+}
+
+#[test]
+fn it_applies_search_replace_blocks() -> Result<()> {
+    let patches = parse(
+        "<<<<<<< SEARCH\n    let z = 7;\n=======\n    let z = 70;\n>>>>>>> REPLACE",
+        "rust",
+        Some("src/main.rs"),
+    )?;
+
+    let res = patches[0].apply(CONTENTS);
+    assert_eq!(res.applied, 1);
+    assert!(res.contents.contains("    let z = 70;\n"));
+
+    return Ok(());
+}
+
+#[test]
+fn it_appends_search_replace_blocks_with_nothing_to_search() -> Result<()> {
+    let patches = parse(
+        "<<<<<<< SEARCH\n=======\nfn new() {}\n>>>>>>> REPLACE",
+        "rust",
+        Some("src/main.rs"),
+    )?;
+
+    let res = patches[0].apply(CONTENTS);
+    assert!(res.contents.ends_with("}\nfn new() {}\n"));
+    assert_eq!(patches[0].apply("").contents, "fn new() {}\n");
+
+    return Ok(());
+}
+
+#[test]
+fn it_reports_hunks_that_dont_match() -> Result<()> {
+    let patches = parse(
+        "<<<<<<< SEARCH\n    let missing = 1;\n=======\n    let found = 1;\n>>>>>>> REPLACE\n\n<<<<<<< SEARCH\n    let x = 5;\n=======\n    let x = 10;\n>>>>>>> REPLACE",
+        "rust",
+        Some("src/main.rs"),
+    )?;
+
+    let res = patches[0].apply(CONTENTS);
+    assert_eq!(res.applied, 1);
+    assert_eq!(res.failed.len(), 1);
+    assert_eq!(res.failed[0].header, "SEARCH/REPLACE block 1");
+    assert!(res.contents.contains("    let x = 10;\n"));
+
+    return Ok(());
+}
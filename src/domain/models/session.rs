@@ -1,6 +1,11 @@
+#[cfg(test)]
+#[path = "session_test.rs"]
+mod tests;
+
 use serde::Deserialize;
 use serde::Serialize;
 
+use super::Author;
 use super::Message;
 
 #[derive(Serialize, Deserialize)]
@@ -19,3 +24,17 @@ pub struct Session {
     pub timestamp: String,
     pub state: State,
 }
+
+impl Session {
+    /// Sessions saved before messages recorded their model only stored the
+    /// last active model, so model messages missing one are attributed to it.
+    pub fn migrate(&mut self) {
+        let backend_name = self.state.backend_name.to_string();
+        let backend_model = self.state.backend_model.to_string();
+        for message in self.state.messages.iter_mut() {
+            if message.author == Author::Model && message.model.is_none() {
+                message.set_model(&backend_name, &backend_model);
+            }
+        }
+    }
+}
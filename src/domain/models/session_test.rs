@@ -0,0 +1,46 @@
+use anyhow::Result;
+
+use super::Session;
+use crate::domain::models::Author;
+
+#[test]
+fn it_migrates_messages_without_model() -> Result<()> {
+    let mut session: Session = serde_yaml::from_str(
+        r#"
+id: 7c2f1cd4-8b6d
+version: 0.9.0
+timestamp: 2023-12-01T10:00:00-05:00
+state:
+  backend_name: ollama
+  backend_model: codellama:latest
+  backend_context: ""
+  editor_language: ""
+  messages:
+    - author: User
+      text: Hi!
+      mtype: Normal
+    - author: Model
+      text: Hey there!
+      mtype: Normal
+    - author: Model
+      text: Hello from OpenAI.
+      mtype: Normal
+      backend: openai
+      model: gpt-4
+"#,
+    )?;
+
+    session.migrate();
+    let messages = session.state.messages;
+
+    assert_eq!(messages[0].author, Author::User);
+    assert_eq!(messages[0].model, None);
+
+    assert_eq!(messages[1].backend, Some("ollama".to_string()));
+    assert_eq!(messages[1].author_name(), "codellama:latest");
+
+    assert_eq!(messages[2].backend, Some("openai".to_string()));
+    assert_eq!(messages[2].author_name(), "gpt-4");
+
+    return Ok(());
+}
@@ -29,6 +29,13 @@ impl SlashCommand {
             || cmd.is_copy_code_block()
             || cmd.is_copy_chat()
             || cmd.is_help()
+            || cmd.is_continue()
+            || cmd.is_queue()
+            || cmd.is_context()
+            || cmd.is_apply()
+            || cmd.is_patch()
+            || cmd.is_pane()
+            || cmd.is_paste()
         {
             return Some(cmd);
         }
@@ -67,4 +74,32 @@ impl SlashCommand {
     pub fn is_help(&self) -> bool {
         return ["/h", "/help"].contains(&self.command.as_str());
     }
+
+    pub fn is_continue(&self) -> bool {
+        return ["/continue"].contains(&self.command.as_str());
+    }
+
+    pub fn is_queue(&self) -> bool {
+        return ["/queue"].contains(&self.command.as_str());
+    }
+
+    pub fn is_context(&self) -> bool {
+        return ["/ctx", "/context"].contains(&self.command.as_str());
+    }
+
+    pub fn is_apply(&self) -> bool {
+        return ["/apply"].contains(&self.command.as_str());
+    }
+
+    pub fn is_patch(&self) -> bool {
+        return ["/patch"].contains(&self.command.as_str());
+    }
+
+    pub fn is_pane(&self) -> bool {
+        return ["/pane"].contains(&self.command.as_str());
+    }
+
+    pub fn is_paste(&self) -> bool {
+        return ["/paste"].contains(&self.command.as_str());
+    }
 }
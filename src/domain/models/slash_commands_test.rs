@@ -165,3 +165,80 @@ fn it_is_not_copy_code() {
     let cmd = SlashCommand::parse("/copy").unwrap();
     assert!(!cmd.is_copy_code_block());
 }
+
+#[test]
+fn it_is_continue() {
+    let cmd = SlashCommand::parse("/continue").unwrap();
+    assert!(cmd.is_continue());
+}
+#[test]
+fn it_is_not_continue() {
+    let cmd = SlashCommand::parse("/ml").unwrap();
+    assert!(!cmd.is_continue());
+}
+
+#[test]
+fn it_is_queue() {
+    let cmd = SlashCommand::parse("/queue edit 1").unwrap();
+    assert!(cmd.is_queue());
+}
+#[test]
+fn it_is_not_queue() {
+    let cmd = SlashCommand::parse("/q").unwrap();
+    assert!(!cmd.is_queue());
+}
+
+#[test]
+fn it_is_context() {
+    let cmd = SlashCommand::parse("/context add").unwrap();
+    assert!(cmd.is_context());
+}
+#[test]
+fn it_is_not_context() {
+    let cmd = SlashCommand::parse("/copy").unwrap();
+    assert!(!cmd.is_context());
+}
+
+#[test]
+fn it_is_apply() {
+    let cmd = SlashCommand::parse("/apply src/main.rs 1").unwrap();
+    assert!(cmd.is_apply());
+}
+#[test]
+fn it_is_not_apply() {
+    let cmd = SlashCommand::parse("/append 1").unwrap();
+    assert!(!cmd.is_apply());
+}
+
+#[test]
+fn it_is_patch() {
+    let cmd = SlashCommand::parse("/patch 2").unwrap();
+    assert!(cmd.is_patch());
+}
+#[test]
+fn it_is_not_patch() {
+    let cmd = SlashCommand::parse("/apply src/main.rs").unwrap();
+    assert!(!cmd.is_patch());
+}
+
+#[test]
+fn it_is_pane() {
+    let cmd = SlashCommand::parse("/pane %3").unwrap();
+    assert!(cmd.is_pane());
+}
+#[test]
+fn it_is_not_pane() {
+    let cmd = SlashCommand::parse("/patch 1").unwrap();
+    assert!(!cmd.is_pane());
+}
+
+#[test]
+fn it_is_paste() {
+    let cmd = SlashCommand::parse("/paste").unwrap();
+    assert!(cmd.is_paste());
+}
+#[test]
+fn it_is_not_paste() {
+    let cmd = SlashCommand::parse("/pane").unwrap();
+    assert!(!cmd.is_paste());
+}
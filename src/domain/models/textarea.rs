@@ -8,14 +8,16 @@ pub struct TextArea {}
 impl<'a> TextArea {
     pub fn default() -> tui_textarea::TextArea<'a> {
         let mut textarea = tui_textarea::TextArea::default();
-        textarea.set_block(
-            Block::default()
-                .borders(Borders::ALL)
-                .border_type(BorderType::Double)
-                .title("Enter prompt")
-                .padding(Padding::new(1, 1, 0, 0)),
-        );
+        textarea.set_block(TextArea::block("Enter prompt"));
 
         return textarea;
     }
+
+    pub fn block(title: &str) -> Block<'a> {
+        return Block::default()
+            .borders(Borders::ALL)
+            .border_type(BorderType::Double)
+            .title(title.to_string())
+            .padding(Padding::new(1, 1, 0, 0));
+    }
 }
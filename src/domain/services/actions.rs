@@ -1,33 +1,52 @@
+use std::fmt;
 use std::sync::Arc;
+use std::time::Duration;
 
 use anyhow::Result;
 use tokio::sync::mpsc;
 use tokio::task::JoinHandle;
+use tokio::time;
 
 use super::clipboard::ClipboardService;
+use super::ModelCache;
 use crate::configuration::Config;
 use crate::configuration::ConfigKey;
+use crate::configuration::ModelAliases;
 use crate::domain::models::AcceptType;
 use crate::domain::models::Action;
 use crate::domain::models::Author;
 use crate::domain::models::BackendBox;
+use crate::domain::models::BackendName;
 use crate::domain::models::BackendPrompt;
+use crate::domain::models::BackendStatusError;
 use crate::domain::models::EditorContext;
 use crate::domain::models::EditorName;
 use crate::domain::models::Event;
+use crate::domain::models::FilePatch;
 use crate::domain::models::Message;
 use crate::domain::models::MessageType;
 use crate::domain::models::SlashCommand;
+use crate::infrastructure::backends::BackendManager;
+use crate::infrastructure::editors::file::FileEditor;
+use crate::infrastructure::editors::tmux::Tmux;
 use crate::infrastructure::editors::EditorManager;
 
+const EDITOR_CONTEXT_DEBOUNCE_MS: u64 = 250;
+
 pub fn help_text() -> String {
     let text = r#"
 COMMANDS:
-- /modellist (/ml) - Lists all available models from the backend.
-- /model (/model) [MODEL_NAME,MODEL_INDEX] - Sets the specified model as the active model. You can pass either the model name, or the index from `/modellist`.
+- /modellist (/ml) [--refresh?] - Opens a picker of available models and aliases, along with the features the backend supports. Type to filter, and hit Enter to switch models. Model lists are cached for an hour, `--refresh` fetches them from the backend again.
+- /model (/model) [MODEL_NAME,MODEL_INDEX,MODEL_ALIAS] - Sets the specified model as the active model. You can pass either the model name, its index in the alphabetically sorted model list, or an alias from the `[models]` config table.
 - /append (/a) [CODE_BLOCK_NUMBER?] - Appends code blocks to an editor. See Code Actions for more details.
 - /replace (/r) [CODE_BLOCK_NUMBER?] - Replaces selections with code blocks in an editor. See Code Actions for more details.
 - /copy (/c) [CODE_BLOCK_NUMBER?] - Copies the entire chat history to your clipboard. When a `CODE_BLOCK_NUMBER` is used, only the specified copy blocks are copied to clipboard. See Code Actions for more details.
+- /continue - Asks the model to resume a response that was interrupted with CTRL+C.
+- /queue [edit,cancel] [QUEUE_NUMBER?] - Prompts submitted while waiting on a response are queued and sent in order. `edit` moves a queued prompt back in to the prompt box, and `cancel` removes it. Defaults to the last queued prompt.
+- /queue clear - Removes all queued prompts.
+- /context (/ctx) [refresh,add,clear,show,hide] [CONTEXT_NUMBER?] - Shares your current editor selection mid-conversation. `add` shares it alongside previous contexts, `refresh` replaces them, and `clear` stops sharing them. Contexts are shown collapsed, `show` expands them and lists what's shared, and `hide` collapses them again.
+- /pane [TARGET?] - With the tmux editor, captures tmux pane `TARGET` (such as `%3` or `1.2`) as context, and sends accepted code blocks to it. Lists the panes to choose from when no `TARGET` is given.
+- /paste - Attaches the text in your clipboard to your next prompt, shown collapsed like editor contexts. Tabs and long logs are kept as is. Only text is supported.
 - /quit /exit (/q) - Exit Oatmeal.
 - /help (/h) - Provides this help menu.
 
@@ -36,15 +55,30 @@ HOTKEYS:
 - Down arrow - Scroll down.
 - CTRL+U - Page up.
 - CTRL+D - Page down.
-- CTRL+C - Interrupt waiting for prompt response if in progress, otherwise exit.
+- CTRL+C - Interrupt waiting for prompt response if in progress, otherwise exit. Interrupted responses are kept in the chat, and queued prompts continue to be sent.
 - CTRL+O - Insert a line break at the cursor position.
 - CTRL+R - Resubmit your last message to the backend.
+- CTRL+V - Attach the text in your clipboard to your next prompt, the same as /paste.
+- Esc - Pick a message to act on. See Navigate Mode for more details.
+
+NAVIGATE MODE:
+Esc focuses the latest message, highlighting its bubble, so you can act on one message at a time. Anything in the prompt box is kept.
+
+- Up/Down arrows, k/j - Move to the previous or next message. g and G jump to the first and last.
+- y - Copy the message to your clipboard.
+- 1-9 - Copy that code block of the message, counting from the message's first code block.
+- r - Ask the message again. For a response, the prompt it answered is asked again.
+- e - Open the message in `$EDITOR`. Once saved, the edited text is put in the prompt box.
+- d - Delete the message from the chat and its session. Models that keep chat history still remember it.
+- Esc, q, or CTRL+C - Stop navigating.
 
 CODE ACTIONS:
 When working with models that provide code, and using an editor integration, Oatmeal has the capabilities to read selected code from an editor, and submit model provided code back in to an editor. Each code block provided by a model is indexed with a (NUMBER) at the beginning of the block to make it easily identifiable.
 
 - /append (/a) [CODE_BLOCK_NUMBER?] will append one-to-many model provided code blocks to the open file in your editor.
 - /replace (/r) [CODE_BLOCK_NUMBER?] - will replace selected code in your editor with one-to-many model provided code blocks.
+- /apply PATH[:START_LINE-END_LINE] [CODE_BLOCK_NUMBER?] - With the file editor, replaces a file, or a range of lines in it, with one-to-many model provided code blocks.
+- /patch [CODE_BLOCK_NUMBER?] - Applies code blocks written as unified diffs, or as SEARCH/REPLACE edit blocks, to files in the working directory. Hunks that don't match the file are reported, and the rest are still applied.
 - /copy (/c) [CODE_BLOCK_NUMBER?] - Copies the entire chat history to your clipboard. When a `CODE_BLOCK_NUMBER` is used it will append one-to-many model provided code blocks to your clipboard, no matter the editor integration.
 
 The `CODE_BLOCK_NUMBER` allows you to select several code blocks to send back to your editor at once. The parameter can be set as follows:
@@ -52,37 +86,39 @@ The `CODE_BLOCK_NUMBER` allows you to select several code blocks to send back to
 - `1,3,5` - Selects code blocks 1, 3, and 5.
 - `2..5`- Selects an inclusive range of code blocks between 2 and 5.
 - None - Selects the last provided code block.
+
+When an editor integration has selected code, /append and /replace first show a diff of the changes. Use j/k to move between hunks, Space to accept or reject a hunk, y or Enter to send the accepted hunks to your editor, and n or Esc to discard them.
         "#;
 
     return text.trim().to_string();
 }
 
-async fn model_list(backend: &BackendBox, tx: &mpsc::UnboundedSender<Event>) -> Result<()> {
-    let mut models = backend.list_models().await?;
-    models.sort();
+async fn model_list(
+    backend: &BackendBox,
+    model_cache: &ModelCache,
+    tx: &mpsc::UnboundedSender<Event>,
+    text: &str,
+) -> Result<()> {
+    let refresh = text.split(' ').any(|arg| return arg == "--refresh");
+    let models = model_cache.list_models(backend, refresh).await?;
 
-    let res = models
-        .iter()
-        .enumerate()
-        .map(|(idx, model)| {
-            let n = idx + 1;
-            return format!("- ({n}) {model}");
-        })
-        .collect::<Vec<String>>();
-
-    tx.send(Event::BackendMessage(Message::new(
-        Author::Oatmeal,
-        res.join("\n").as_str(),
-    )))?;
+    tx.send(Event::BackendModelList(
+        backend.name(),
+        models,
+        backend.capabilities().await,
+    ))?;
 
     return Ok(());
 }
 
+/// Sets the active model. Returns a new backend when the model is an alias
+/// for a model on a different backend.
 async fn model_set(
     backend: &BackendBox,
+    model_cache: &ModelCache,
     tx: &mpsc::UnboundedSender<Event>,
     text: &str,
-) -> Result<()> {
+) -> Result<Option<BackendBox>> {
     let mut model_name = text.split(' ').last().unwrap().to_string();
     if SlashCommand::parse(&model_name).is_some() {
         let msg = Message::new_with_type(
@@ -91,27 +127,55 @@ async fn model_set(
             "You must specify a model name with `/model` or `/m`. Run `/help` more details.",
         );
         tx.send(Event::BackendMessage(msg))?;
-        return Ok(());
+        return Ok(None);
     }
 
-    let mut models = backend.list_models().await?;
-    models.sort();
+    let mut new_backend: Option<BackendBox> = None;
+    let alias = ModelAliases::get(&model_name);
+    if let Some(alias) = &alias {
+        model_name = alias.model.to_string();
+        if let Some(alias_backend) = alias.backend.clone() {
+            if alias_backend != backend.name() {
+                let switched_backend = BackendManager::get(alias_backend.clone())?;
+                if let Err(err) = switched_backend.health_check().await {
+                    let msg = Message::new_with_type(
+                        Author::Oatmeal,
+                        MessageType::Error,
+                        &format!("Backend {alias_backend} for model alias {} isn't available.\n\nError: {err}", alias.name),
+                    );
+                    tx.send(Event::BackendMessage(msg))?;
+                    return Ok(None);
+                }
+                new_backend = Some(switched_backend);
+            }
+        }
+    }
 
-    if let Ok(idx) = model_name.parse::<usize>() {
-        if idx < 1 || idx > models.len() {
-            let msg = Message::new_with_type(
-                Author::Oatmeal,
-                MessageType::Error,
-                &format!("{idx} is not a valid index from the model list."),
-            );
-            tx.send(Event::BackendMessage(msg))?;
-            return Ok(());
+    let target_backend = new_backend.as_ref().unwrap_or(backend);
+    let mut models = model_cache.list_models(target_backend, false).await?;
+    let is_index = alias.is_none() && model_name.parse::<usize>().is_ok();
+    if !is_index && !models.contains(&model_name) {
+        // The model may have been pulled since the list was cached.
+        models = model_cache.list_models(target_backend, true).await?;
+    }
+
+    if alias.is_none() {
+        if let Ok(idx) = model_name.parse::<usize>() {
+            if idx < 1 || idx > models.len() {
+                let msg = Message::new_with_type(
+                    Author::Oatmeal,
+                    MessageType::Error,
+                    &format!("{idx} is not a valid index from the model list."),
+                );
+                tx.send(Event::BackendMessage(msg))?;
+                return Ok(None);
+            }
+            model_name = models[idx - 1].to_string();
         }
-        model_name = models[idx - 1].to_string();
     }
 
     if !models.contains(&model_name) {
-        let backend_name = Config::get(ConfigKey::Backend);
+        let backend_name = target_backend.name();
         let msg = Message::new_with_type(
             Author::Oatmeal,
             MessageType::Error,
@@ -120,34 +184,47 @@ async fn model_set(
             ),
         );
         tx.send(Event::BackendMessage(msg))?;
-        return Ok(());
+        return Ok(None);
     }
 
+    if let Some(switched_backend) = &new_backend {
+        Config::set(ConfigKey::Backend, &switched_backend.name().to_string());
+        tx.send(Event::BackendChanged(switched_backend.capabilities().await))?;
+    }
     Config::set(ConfigKey::Model, &model_name);
 
-    tx.send(Event::BackendMessage(Message::new(
-        Author::Model,
+    tx.send(Event::BackendMessage(Message::new_from_model(
+        &Config::get(ConfigKey::Backend),
+        &model_name,
         &format!("{model_name} has entered the chat."),
     )))?;
 
-    return Ok(());
+    return Ok(new_backend);
 }
 
 async fn accept_codeblock(
     context: Option<EditorContext>,
     codeblock: String,
     accept_type: AcceptType,
+    allow_outside_root: bool,
     tx: &mpsc::UnboundedSender<Event>,
 ) -> Result<()> {
     let editor_name = EditorName::parse(Config::get(ConfigKey::Editor)).unwrap();
-    let editor = EditorManager::get(editor_name.clone())?;
+    let mut editor = EditorManager::get(editor_name.clone())?;
+    if allow_outside_root && editor_name == EditorName::File {
+        editor = Box::new(FileEditor::new()?.allowing_outside_root());
+    }
     let mut context_mut = context;
 
-    if editor_name == EditorName::Clipboard || editor_name == EditorName::None {
+    if editor_name == EditorName::Clipboard
+        || editor_name == EditorName::Tmux
+        || editor_name == EditorName::None
+    {
         context_mut = Some(EditorContext::default());
     }
 
     if let Some(editor_context) = context_mut {
+        let file_path = editor_context.file_path.to_string();
         let res = editor
             .send_codeblock(editor_context, codeblock, accept_type)
             .await;
@@ -158,6 +235,19 @@ async fn accept_codeblock(
                 MessageType::Error,
                 &format!("Failed to commuicate with editor:\n\n{err}"),
             )))?;
+        } else if editor_name == EditorName::File {
+            tx.send(Event::BackendMessage(Message::new(
+                Author::Oatmeal,
+                &format!("Wrote code blocks to {file_path}."),
+            )))?;
+        } else if editor_name == EditorName::Tmux {
+            tx.send(Event::BackendMessage(Message::new(
+                Author::Oatmeal,
+                &format!(
+                    "Pasted code blocks in to tmux pane {}. Press enter in the pane to run them.",
+                    Config::get(ConfigKey::TmuxPane)
+                ),
+            )))?;
         }
     }
 
@@ -171,13 +261,100 @@ async fn accept_codeblock(
     return Ok(());
 }
 
+/// Applies patches to files relative to the working directory, and reports
+/// which files were patched along with any hunks that didn't match.
+fn apply_patches(patches: Vec<FilePatch>, tx: &mpsc::UnboundedSender<Event>) -> Result<()> {
+    let editor = match FileEditor::new() {
+        Ok(editor) => editor,
+        Err(err) => {
+            tx.send(Event::BackendMessage(Message::new_with_type(
+                Author::Oatmeal,
+                MessageType::Error,
+                &err.to_string(),
+            )))?;
+            return Ok(());
+        }
+    };
+    let mut patched = vec![];
+    let mut failures = vec![];
+
+    for patch in patches {
+        let file_path = patch.file_path.to_string();
+        if editor.is_outside_root(&file_path) {
+            failures.push(format!(
+                "{file_path} is outside of the working directory, so it wasn't patched."
+            ));
+            continue;
+        }
+
+        let res = editor
+            .read(&file_path)
+            .map(|contents| return patch.apply(&contents));
+        let res = match res {
+            Ok(res) => res,
+            Err(err) => {
+                failures.push(err.to_string());
+                continue;
+            }
+        };
+
+        for hunk in res.failed.iter() {
+            let search = hunk
+                .search
+                .iter()
+                .map(|line| return format!("    {line}"))
+                .collect::<Vec<String>>()
+                .join("\n");
+            failures.push(format!(
+                "{file_path}: {} didn't match any lines. Expected to find:\n\n{search}",
+                hunk.header
+            ));
+        }
+
+        if res.applied == 0 {
+            continue;
+        }
+
+        if let Err(err) = editor.write(&file_path, &res.contents) {
+            failures.push(format!("Failed to write {file_path}: {err}"));
+            continue;
+        }
+
+        patched.push(format!(
+            "- {file_path} ({} of {} hunks)",
+            res.applied,
+            patch.hunks.len()
+        ));
+    }
+
+    if !patched.is_empty() {
+        tx.send(Event::BackendMessage(Message::new(
+            Author::Oatmeal,
+            &format!("Patched files:\n\n{}", patched.join("\n")),
+        )))?;
+    }
+
+    if !failures.is_empty() {
+        tx.send(Event::BackendMessage(Message::new_with_type(
+            Author::Oatmeal,
+            MessageType::Error,
+            &format!(
+                "Some changes couldn't be applied:\n\n{}",
+                failures.join("\n\n")
+            ),
+        )))?;
+    }
+
+    return Ok(());
+}
+
 fn copy_messages(messages: Vec<Message>, tx: &mpsc::UnboundedSender<Event>) -> Result<()> {
     let mut payload = messages[0].text.to_string();
     if messages.len() > 1 {
         payload = messages
             .iter()
             .map(|message| {
-                return format!("{}: {}", message.author.to_string(), message.text);
+                return format!("{}: {}", message.author_name(), message.text);
             })
             .collect::<Vec<String>>()
             .join("\n\n");
@@ -210,17 +387,293 @@ fn worker_error(err: anyhow::Error, tx: &mpsc::UnboundedSender<Event>) -> Result
     return Ok(());
 }
 
+/// A backend that didn't start answering within the response timeout.
+#[derive(Debug)]
+struct ResponseTimeout {
+    backend: BackendName,
+    timeout: Duration,
+}
+
+impl fmt::Display for ResponseTimeout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return write!(
+            f,
+            "Backend {} didn't respond within {}ms",
+            self.backend,
+            self.timeout.as_millis()
+        );
+    }
+}
+
+impl std::error::Error for ResponseTimeout {}
+
+/// Fallbacks are for backends that are down, overloaded or too slow. A
+/// request the backend rejected, like one with a bad token or an unknown
+/// model, would fail the same way again, so it's reported instead of
+/// switching the active model.
+async fn should_fall_back(backend: &BackendBox, err: &anyhow::Error) -> bool {
+    if err.downcast_ref::<ResponseTimeout>().is_some() {
+        return true;
+    }
+    if let Some(status_err) = err.downcast_ref::<BackendStatusError>() {
+        return status_err.is_server_error();
+    }
+
+    return backend.health_check().await.is_err();
+}
+
+/// Runs a completion on a backend, forwarding its responses to the UI.
+/// Returns whether anything was forwarded, since a prompt can't be retried
+/// on another backend once part of an answer has been shown.
+async fn stream_completion(
+    backend: &BackendBox,
+    prompt: BackendPrompt,
+    tx: &mpsc::UnboundedSender<Event>,
+    timeout: Option<Duration>,
+) -> (bool, Result<()>) {
+    let (attempt_tx, mut attempt_rx) = mpsc::unbounded_channel::<Event>();
+    let completion = backend.get_completion(prompt, &attempt_tx);
+    tokio::pin!(completion);
+
+    let mut streamed = false;
+    loop {
+        let first_response_timeout = timeout.filter(|_| return !streamed);
+        let timed_out = async move {
+            if let Some(duration) = first_response_timeout {
+                time::sleep(duration).await;
+            } else {
+                std::future::pending::<()>().await;
+            }
+        };
+
+        tokio::select! {
+            res = &mut completion => {
+                while let Ok(event) = attempt_rx.try_recv() {
+                    streamed = true;
+                    if let Err(err) = tx.send(event) {
+                        return (streamed, Err(err.into()));
+                    }
+                }
+                return (streamed, res);
+            }
+            Some(event) = attempt_rx.recv() => {
+                streamed = true;
+                if let Err(err) = tx.send(event) {
+                    return (streamed, Err(err.into()));
+                }
+            }
+            _ = timed_out => {
+                return (
+                    streamed,
+                    Err(ResponseTimeout {
+                        backend: backend.name(),
+                        timeout: timeout.unwrap_or_default(),
+                    }
+                    .into()),
+                );
+            }
+        }
+    }
+}
+
 async fn completions(
     backend: &BackendBox,
+    model_cache: &ModelCache,
     prompt: BackendPrompt,
     tx: &mpsc::UnboundedSender<Event>,
 ) -> Result<()> {
     if Config::get(ConfigKey::Model).is_empty() {
-        let models = backend.list_models().await?;
+        let models = model_cache.list_models(backend, false).await?;
         Config::set(ConfigKey::Model, &models[0]);
     }
 
-    backend.get_completion(prompt, tx).await?;
+    let fallbacks = ModelAliases::fallbacks()?;
+    let mut timeout = None;
+    if !fallbacks.is_empty() {
+        timeout = Some(Duration::from_millis(
+            Config::get(ConfigKey::BackendResponseTimeout).parse::<u64>()?,
+        ));
+    }
+
+    let (streamed, res) = stream_completion(backend, prompt.clone(), tx, timeout).await;
+    let mut err = match res {
+        Ok(()) => return Ok(()),
+        Err(err) => err,
+    };
+    if streamed || !should_fall_back(backend, &err).await {
+        return Err(err);
+    }
+
+    for fallback in fallbacks {
+        let failed_backend = Config::get(ConfigKey::Backend);
+        let failed_model = Config::get(ConfigKey::Model);
+        let backend_name = fallback
+            .backend
+            .clone()
+            .unwrap_or_else(|| return BackendName::parse(failed_backend.to_string()).unwrap());
+        if backend_name.to_string() == failed_backend && fallback.model == failed_model {
+            continue;
+        }
+
+        let fallback_backend = BackendManager::get(backend_name.clone())?;
+        if let Err(health_err) = fallback_backend.health_check().await {
+            tracing::warn!(
+                backend = backend_name.to_string(),
+                error = ?health_err,
+                "Fallback backend isn't available"
+            );
+            continue;
+        }
+
+        // Switching the active model makes the answering model show in its
+        // bubble, and keeps follow up prompts on a backend that works.
+        Config::set(ConfigKey::Backend, &backend_name.to_string());
+        Config::set(ConfigKey::Model, &fallback.model);
+
+        let mut text = format!(
+            "{failed_model} on {failed_backend} failed, so {} on {backend_name} is answering instead.",
+            fallback.model
+        );
+        if !prompt.backend_context.is_empty() {
+            text += " Chat history from the previous model isn't carried over.";
+        }
+        text += &format!("\n\nError: {err}");
+        tx.send(Event::BackendFallback(
+            Message::new_with_type(Author::Oatmeal, MessageType::Error, &text),
+            fallback_backend.capabilities().await,
+        ))?;
+
+        let fallback_prompt = BackendPrompt::new(prompt.text.to_string(), "".to_string());
+        let (streamed, res) =
+            stream_completion(&fallback_backend, fallback_prompt, tx, timeout).await;
+        err = match res {
+            Ok(()) => return Ok(()),
+            Err(err) => err,
+        };
+        if streamed || !should_fall_back(&fallback_backend, &err).await {
+            return Err(err);
+        }
+    }
+
+    return Err(err);
+}
+
+/// Fallbacks switch the active backend from within a completion worker, so
+/// the backend is rebuilt whenever it no longer matches the config. Returns
+/// true when the backend changed.
+fn sync_backend(backend: &mut Arc<BackendBox>) -> Result<bool> {
+    let backend_name = BackendName::parse(Config::get(ConfigKey::Backend)).unwrap();
+    if backend.name() == backend_name {
+        return Ok(false);
+    }
+
+    *backend = Arc::new(BackendManager::get(backend_name)?);
+    return Ok(true);
+}
+
+/// Fetches the current context from the editor for `/context add` and
+/// `/context refresh`, reporting why when there's none to share.
+async fn fetch_editor_context(tx: &mpsc::UnboundedSender<Event>) -> Result<Option<EditorContext>> {
+    let editor_name = EditorName::parse(Config::get(ConfigKey::Editor)).unwrap();
+    let editor = EditorManager::get(editor_name.clone())?;
+
+    let res = match editor.health_check().await {
+        Ok(_) => editor.get_context().await,
+        Err(err) => Err(err),
+    };
+
+    let err = match res {
+        Ok(Some(context)) => return Ok(Some(context)),
+        Ok(None) => format!("Editor {editor_name} didn't provide any context. Open a file, or select some code, and try again."),
+        Err(err) => format!("Failed to get context from editor {editor_name}:\n\n{err}"),
+    };
+
+    tx.send(Event::BackendMessage(Message::new_with_type(
+        Author::Oatmeal,
+        MessageType::Error,
+        &err,
+    )))?;
+
+    return Ok(None);
+}
+
+/// Keeps the chat's editor context up to date for editors that push changes,
+/// waiting for bursts of changes such as cursor movement to settle first.
+async fn watch_editor_context(tx: mpsc::UnboundedSender<Event>) -> Result<()> {
+    let editor = EditorManager::get(EditorName::parse(Config::get(ConfigKey::Editor)).unwrap())?;
+    if editor.health_check().await.is_err() {
+        return Ok(());
+    }
+
+    let mut changes = match editor.subscribe_context().await? {
+        Some(changes) => changes,
+        None => return Ok(()),
+    };
+
+    while changes.recv().await.is_some() {
+        time::sleep(Duration::from_millis(EDITOR_CONTEXT_DEBOUNCE_MS)).await;
+        while changes.try_recv().is_ok() {}
+
+        // No context is reported while Oatmeal's own terminal is focused, so
+        // the last known context is kept.
+        if let Some(context) = editor.get_context().await? {
+            tx.send(Event::EditorContextChanged(context))?;
+        }
+    }
+
+    return Ok(());
+}
+
+/// Lists the panes `/pane` can pick from, marking the one in use.
+async fn tmux_list_panes(tx: &mpsc::UnboundedSender<Event>) -> Result<()> {
+    let current = Config::get(ConfigKey::TmuxPane);
+    let panes = match Tmux::default().list_panes().await {
+        Ok(panes) => panes,
+        Err(err) => {
+            tx.send(Event::BackendMessage(Message::new_with_type(
+                Author::Oatmeal,
+                MessageType::Error,
+                &format!("Failed to list tmux panes:\n\n{err}"),
+            )))?;
+            return Ok(());
+        }
+    };
+
+    let list = panes
+        .iter()
+        .map(|pane| {
+            let mut line = format!("- {} ({}) {}", pane.id, pane.target, pane.command);
+            if pane.id == current || pane.target == current {
+                line += " [in use]";
+            }
+            return line;
+        })
+        .collect::<Vec<String>>()
+        .join("\n");
+
+    tx.send(Event::BackendMessage(Message::new(
+        Author::Oatmeal,
+        &format!("Using tmux pane {current}. Pick another with `/pane TARGET`:\n\n{list}"),
+    )))?;
+
+    return Ok(());
+}
+
+fn paste_clipboard(tx: &mpsc::UnboundedSender<Event>) -> Result<()> {
+    let err = match ClipboardService::get() {
+        Ok(text) if !text.trim().is_empty() => {
+            tx.send(Event::ClipboardPasted(text))?;
+            return Ok(());
+        }
+        Ok(_) => "Your clipboard is empty.".to_string(),
+        Err(err) => format!("Failed to read your clipboard:\n\n{err}"),
+    };
+
+    tx.send(Event::BackendMessage(Message::new_with_type(
+        Author::Oatmeal,
+        MessageType::Error,
+        &err,
+    )))?;
 
     return Ok(());
 }
@@ -239,15 +692,25 @@ pub struct ActionsService {}
 impl ActionsService {
     pub async fn start(
         backend: BackendBox,
+        model_cache: ModelCache,
         tx: mpsc::UnboundedSender<Event>,
         rx: &mut mpsc::UnboundedReceiver<Action>,
     ) -> Result<()> {
-        let backend_arc = Arc::new(backend);
+        let mut backend_arc = Arc::new(backend);
+        let model_cache = Arc::new(model_cache);
+
+        let watcher_tx = tx.clone();
+        tokio::spawn(async move {
+            if let Err(err) = watch_editor_context(watcher_tx).await {
+                tracing::warn!(error = ?err, "Stopped watching editor context");
+            }
+        });
 
         // Lazy default.
         let mut worker: JoinHandle<Result<()>> = tokio::spawn(async {
             return Ok(());
         });
+        let mut worker_prompt: Option<BackendPrompt> = None;
 
         loop {
             let event = rx.recv().await;
@@ -258,22 +721,66 @@ impl ActionsService {
             let worker_tx = tx.clone();
             match event.unwrap() {
                 Action::AcceptCodeBlock(context, codeblock, accept_type) => {
-                    accept_codeblock(context, codeblock, accept_type, &tx).await?;
+                    accept_codeblock(context, codeblock, accept_type, false, &tx).await?;
+                }
+                Action::AcceptCodeBlockOutsideRoot(context, codeblock, accept_type) => {
+                    accept_codeblock(Some(context), codeblock, accept_type, true, &tx).await?;
+                }
+                Action::ApplyPatches(patches) => {
+                    apply_patches(patches, &tx)?;
+                }
+                Action::ClipboardPaste() => {
+                    paste_clipboard(&tx)?;
                 }
                 Action::CopyMessages(messages) => {
                     copy_messages(messages, &tx)?;
                 }
-                Action::BackendAbort() => {
+                Action::EditorContextAdd() => {
+                    if let Some(context) = fetch_editor_context(&tx).await? {
+                        tx.send(Event::EditorContextAdded(context))?;
+                    }
+                }
+                Action::EditorContextRefresh() => {
+                    if let Some(context) = fetch_editor_context(&tx).await? {
+                        tx.send(Event::EditorContextRefreshed(context))?;
+                    }
+                }
+                Action::TmuxListPanes() => {
+                    tmux_list_panes(&tx).await?;
+                }
+                Action::BackendAbort(partial_text) => {
+                    // A finished completion already sent its context, so
+                    // only a running one has anything to interrupt.
+                    let running = !worker.is_finished();
                     worker.abort();
+
+                    // Always confirm the abort, even when there's nothing to
+                    // stop, as the UI holds queued prompts until it's told.
+                    let mut context = None;
+                    if let Some(mut prompt) = worker_prompt.take().filter(|_| return running) {
+                        if sync_backend(&mut backend_arc)? {
+                            prompt.backend_context = "".to_string();
+                        }
+                        match backend_arc.interrupted_context(&prompt, &partial_text) {
+                            Ok(res) => context = res,
+                            Err(err) => worker_error(err, &tx)?,
+                        }
+                    }
+                    tx.send(Event::BackendAborted(context))?;
                 }
                 Action::BackendRequest(prompt) => {
+                    sync_backend(&mut backend_arc)?;
                     if let Some(command) = SlashCommand::parse(&prompt.text) {
                         if command.is_model_list() {
-                            model_list(&backend_arc, &tx).await?;
+                            model_list(&backend_arc, &model_cache, &tx, &prompt.text).await?;
                             continue;
                         }
                         if command.is_model_set() {
-                            model_set(&backend_arc, &tx, &prompt.text).await?;
+                            if let Some(new_backend) =
+                                model_set(&backend_arc, &model_cache, &tx, &prompt.text).await?
+                            {
+                                backend_arc = Arc::new(new_backend);
+                            }
                             continue;
                         }
                         if command.is_help() {
@@ -282,9 +789,16 @@ impl ActionsService {
                         }
                     }
 
+                    // Only one response streams in at a time.
+                    worker.abort();
+                    worker_prompt = Some(prompt.clone());
                     let backend_worker = backend_arc.clone();
+                    let model_cache_worker = model_cache.clone();
                     worker = tokio::spawn(async move {
-                        if let Err(err) = completions(&backend_worker, prompt, &worker_tx).await {
+                        if let Err(err) =
+                            completions(&backend_worker, &model_cache_worker, prompt, &worker_tx)
+                                .await
+                        {
                             worker_error(err, &worker_tx)?;
                         }
                         return Ok(());
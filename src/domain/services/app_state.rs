@@ -5,6 +5,11 @@ use tokio::sync::mpsc;
 
 use super::BubbleList;
 use super::CodeBlocks;
+use super::DiffPreview;
+use super::DiffPreviewDecision;
+use super::ModelCache;
+use super::ModelPicker;
+use super::PromptQueue;
 use super::Scroll;
 use super::Sessions;
 use super::Themes;
@@ -14,17 +19,45 @@ use crate::domain::models::AcceptType;
 use crate::domain::models::Action;
 use crate::domain::models::Author;
 use crate::domain::models::BackendBox;
+use crate::domain::models::BackendCapabilities;
+use crate::domain::models::BackendPrompt;
 use crate::domain::models::BackendResponse;
 use crate::domain::models::EditorBox;
 use crate::domain::models::EditorContext;
+use crate::domain::models::EditorName;
 use crate::domain::models::Message;
 use crate::domain::models::MessageType;
 use crate::domain::models::SlashCommand;
+use crate::infrastructure::editors::file::FileEditor;
 
 #[cfg(test)]
 #[path = "app_state_test.rs"]
 mod tests;
 
+const CONTINUE_PROMPT: &str = "Your previous response was interrupted. Continue it from exactly where it stopped, without repeating what you have already said.";
+
+/// Creates a model message attributed to the active backend and model.
+fn model_message(text: &str) -> Message {
+    return Message::new_from_model(
+        &Config::get(ConfigKey::Backend),
+        &Config::get(ConfigKey::Model),
+        text,
+    );
+}
+
+/// Splits `/apply` targets such as `src/main.rs:4-6` or `src/main.rs:4` in to
+/// a file path and an optional inclusive line range.
+fn parse_file_target(target: &str) -> (String, Option<(i64, i64)>) {
+    if let Some((file_path, range)) = target.rsplit_once(':') {
+        let (start, end) = range.split_once('-').unwrap_or((range, range));
+        if let (Ok(start), Ok(end)) = (start.parse::<i64>(), end.parse::<i64>()) {
+            return (file_path.to_string(), Some((start, end)));
+        }
+    }
+
+    return (target.to_string(), None);
+}
+
 pub struct AppStateProps {
     pub backend: BackendBox,
     pub editor: EditorBox,
@@ -33,20 +66,44 @@ pub struct AppStateProps {
     pub theme_file: String,
     pub session_id: Option<String>,
     pub sessions_service: Sessions,
+    pub model_cache: ModelCache,
 }
 
 pub struct AppState<'a> {
+    pub backend_capabilities: BackendCapabilities,
     pub backend_context: String,
     pub bubble_list: BubbleList<'a>,
     pub codeblocks: CodeBlocks,
+    /// Changes waiting to be reviewed before they're sent to the editor.
+    pub diff_preview: Option<DiffPreview>,
+    /// Latest context from the editor, which code blocks are sent back to.
     pub editor_context: Option<EditorContext>,
+    /// Contexts shared with the model in this conversation.
+    pub editor_contexts: Vec<EditorContext>,
+    pub editor_name: EditorName,
     pub exit_warning: bool,
+    /// Index of the message picked in navigate mode, or `None` when not
+    /// navigating.
+    pub focused_message: Option<usize>,
     pub last_known_height: usize,
     pub last_known_width: usize,
     pub messages: Vec<Message>,
+    pub model_picker: Option<ModelPicker>,
+    /// Shown in place of the navigate mode help until the next key press,
+    /// such as why an action was refused. Adding a message instead would cut
+    /// off a response that's still streaming in.
+    pub navigate_notice: Option<String>,
+    /// Contexts shared since the last prompt, which haven't been sent to the
+    /// model yet.
+    pub pending_editor_contexts: Vec<EditorContext>,
+    pub prompt_queue: PromptQueue,
     pub scroll: Scroll,
     pub session_id: String,
     pub sessions_service: Sessions,
+    /// A response was interrupted, and the backend hasn't confirmed it
+    /// stopped yet. Queued prompts hold off until it does, so they're sent
+    /// with the interrupted response's context.
+    pub waiting_for_abort: bool,
     pub waiting_for_backend: bool,
 }
 
@@ -64,17 +121,27 @@ impl<'a> AppState<'a> {
         let theme = Themes::get(&props.theme_name, &props.theme_file)?;
 
         let mut app_state = AppState {
+            backend_capabilities: props.backend.capabilities().await,
             backend_context: "".to_string(),
             bubble_list: BubbleList::new(theme),
             codeblocks: CodeBlocks::default(),
+            diff_preview: None,
             editor_context: None,
+            editor_contexts: vec![],
+            editor_name: props.editor.name(),
             exit_warning: false,
+            focused_message: None,
             last_known_height: 0,
             last_known_width: 0,
             messages: vec![],
+            model_picker: None,
+            navigate_notice: None,
+            pending_editor_contexts: vec![],
+            prompt_queue: PromptQueue::default(),
             scroll: Scroll::default(),
             session_id: Sessions::create_id(),
             sessions_service: props.sessions_service,
+            waiting_for_abort: false,
             waiting_for_backend: false,
         };
 
@@ -88,7 +155,11 @@ impl<'a> AppState<'a> {
                     &format!("Hey, it looks like backend {backend_name} isn't running, I can't connect to it. You should double check that before we start talking, otherwise I may crash.\n\nError: {err}"),
                 ));
         } else {
-            let models = props.backend.list_models().await?;
+            let mut models = props.model_cache.list_models(&props.backend, false).await?;
+            if !model_name.is_empty() && !models.contains(&model_name) {
+                models = props.model_cache.list_models(&props.backend, true).await?;
+            }
+
             if model_name.is_empty() {
                 model_name = models[0].to_string();
                 // TODO refactor this out later.
@@ -106,10 +177,9 @@ impl<'a> AppState<'a> {
 
         // Fallback to the default intro message when there's no editor context.
         if app_state.add_editor_context(props.editor).await.is_err() && !model_name.is_empty() {
-            app_state.messages.push(Message::new(
-                Author::Model,
-                "Hey there! What can I do for you?",
-            ));
+            app_state
+                .messages
+                .push(model_message("Hey there! What can I do for you?"));
         }
 
         return Ok(app_state);
@@ -121,17 +191,27 @@ impl<'a> AppState<'a> {
         let theme = Themes::get(&props.theme_name, &props.theme_file)?;
 
         let mut app_state = AppState {
+            backend_capabilities: props.backend.capabilities().await,
             backend_context: session.state.backend_context,
             bubble_list: BubbleList::new(theme),
             codeblocks: CodeBlocks::default(),
+            diff_preview: None,
             editor_context: None,
+            editor_contexts: vec![],
+            editor_name: props.editor.name(),
             exit_warning: false,
+            focused_message: None,
             last_known_height: 0,
             last_known_width: 0,
             messages: session.state.messages,
+            model_picker: None,
+            navigate_notice: None,
+            pending_editor_contexts: vec![],
+            prompt_queue: PromptQueue::default(),
             scroll: Scroll::default(),
             session_id,
             sessions_service: props.sessions_service,
+            waiting_for_abort: false,
             waiting_for_backend: false,
         };
 
@@ -162,14 +242,12 @@ impl<'a> AppState<'a> {
 
         if let Some(editor_context) = editor.get_context().await? {
             let formatted = editor_context.format();
+            self.editor_contexts = vec![editor_context.clone()];
             self.editor_context = Some(editor_context);
-            self.messages.push(Message::new(
-                Author::Model,
-                &format!(
-                    "Hey there! Let's talk about the following: \n\n{}",
-                    formatted
-                ),
-            ));
+            self.messages.push(model_message(&format!(
+                "Hey there! Let's talk about the following: \n\n{}",
+                formatted
+            )));
 
             return Ok(());
         } else {
@@ -179,13 +257,15 @@ impl<'a> AppState<'a> {
 
     pub fn handle_backend_response(&mut self, msg: BackendResponse) {
         let last_message = self.messages.last_mut().unwrap();
-        if last_message.author != Author::User {
+        if last_message.author == msg.author {
             last_message.append(&msg.text);
+        } else if msg.author == Author::Model {
+            self.messages.push(model_message(&msg.text));
         } else {
             self.messages.push(Message::new(msg.author, &msg.text));
         }
 
-        self.sync_dependants();
+        self.sync_last_message();
 
         if msg.done {
             self.waiting_for_backend = false;
@@ -193,7 +273,7 @@ impl<'a> AppState<'a> {
                 self.backend_context = ctx;
             }
 
-            if self.backend_context.is_empty() {
+            if self.backend_context.is_empty() && self.backend_capabilities.stateful_context {
                 self.add_message(Message::new_with_type(
                     Author::Oatmeal,
                     MessageType::Error,
@@ -206,6 +286,52 @@ impl<'a> AppState<'a> {
         }
     }
 
+    /// Marks the response currently being streamed as interrupted, keeping
+    /// whatever text has arrived so far. Returns the partial text so it can be
+    /// folded in to the backend context.
+    pub fn interrupt_backend_response(&mut self) -> String {
+        self.waiting_for_abort = true;
+        self.waiting_for_backend = false;
+
+        let awaiting_completion = match self.messages.last() {
+            Some(message) if message.author == Author::Model => true,
+            Some(message) if message.author == Author::User => {
+                SlashCommand::parse(&message.text).is_none()
+            }
+            _ => false,
+        };
+        if !awaiting_completion {
+            return "".to_string();
+        }
+
+        if self.messages.last().unwrap().author == Author::User {
+            self.messages.push(model_message(""));
+        }
+
+        let last_message = self.messages.last_mut().unwrap();
+        last_message.set_interrupted();
+        let partial_text = last_message.text.to_string();
+
+        self.codeblocks.replace_from_messages(&self.messages);
+        self.sync_last_message();
+
+        return partial_text;
+    }
+
+    /// The backend confirmed an interrupted response stopped, passing along
+    /// the context with the partial response folded in, if it has one.
+    pub fn handle_backend_aborted(&mut self, context: Option<String>) {
+        self.waiting_for_abort = false;
+        if let Some(ctx) = context {
+            self.backend_context = ctx;
+        }
+    }
+
+    /// Whether prompts should be queued rather than sent.
+    pub fn is_waiting(&self) -> bool {
+        return self.waiting_for_backend || self.waiting_for_abort;
+    }
+
     pub fn handle_slash_commands(
         &mut self,
         input_str: &str,
@@ -253,9 +379,37 @@ impl<'a> AppState<'a> {
                     accept_type = AcceptType::Replace;
                 }
 
+                let codeblocks = codeblocks_res.unwrap();
+                if self.editor_name == EditorName::File {
+                    match self.codeblocks.file_path_from_args(&command.args)? {
+                        Some(file_path) => {
+                            self.open_file_preview(&file_path, None, &codeblocks, accept_type);
+                        }
+                        None => {
+                            self.add_message(Message::new_with_type(
+                                Author::Oatmeal,
+                                MessageType::Error,
+                                "The code blocks don't say which file they're for, such as ```rust src/main.rs. Use `/apply PATH [CODE_BLOCK_NUMBER?]` to choose a file instead.",
+                            ));
+                        }
+                    }
+
+                    return Ok((should_break, should_continue));
+                }
+
+                if let Some(context) = self.editor_context.clone() {
+                    if self.editor_name != EditorName::Clipboard
+                        && self.editor_name != EditorName::Tmux
+                        && self.editor_name != EditorName::None
+                    {
+                        self.open_diff_preview(context, &codeblocks, accept_type);
+                        return Ok((should_break, should_continue));
+                    }
+                }
+
                 tx.send(Action::AcceptCodeBlock(
                     self.editor_context.clone(),
-                    codeblocks_res.unwrap(),
+                    codeblocks,
                     accept_type,
                 ))?;
             }
@@ -266,6 +420,82 @@ impl<'a> AppState<'a> {
                 self.waiting_for_backend = true;
             }
 
+            if command.is_continue() {
+                should_continue = true;
+                if !self.backend_capabilities.stateful_context {
+                    let backend_name = Config::get(ConfigKey::Backend);
+                    self.add_message(Message::new_with_type(
+                        Author::Oatmeal,
+                        MessageType::Error,
+                        &format!("Backend {backend_name} doesn't keep chat history, so the model can't see what it already said. Resubmit your prompt with CTRL+R instead."),
+                    ));
+
+                    return Ok((should_break, should_continue));
+                }
+
+                let was_interrupted = self
+                    .messages
+                    .iter()
+                    .rev()
+                    .find(|message| return message.author == Author::Model)
+                    .map(|message| return message.is_interrupted())
+                    .unwrap_or(false);
+
+                if !was_interrupted {
+                    self.add_message(Message::new_with_type(
+                        Author::Oatmeal,
+                        MessageType::Error,
+                        "There's no interrupted response to continue.",
+                    ));
+
+                    return Ok((should_break, should_continue));
+                }
+
+                tx.send(Action::BackendRequest(BackendPrompt::new(
+                    CONTINUE_PROMPT.to_string(),
+                    self.backend_context.clone(),
+                )))?;
+                self.waiting_for_backend = true;
+            }
+
+            if command.is_apply() {
+                should_continue = true;
+                self.handle_apply_command(&command);
+            }
+
+            if command.is_patch() {
+                should_continue = true;
+                match self.codeblocks.patches_from_args(&command.args) {
+                    Ok(patches) => {
+                        tx.send(Action::ApplyPatches(patches))?;
+                        self.waiting_for_backend = true;
+                    }
+                    Err(err) => {
+                        self.add_message(Message::new_with_type(
+                            Author::Oatmeal,
+                            MessageType::Error,
+                            &format!("There was an error trying to parse your command:\n\n{err}"),
+                        ));
+                    }
+                }
+            }
+
+            if command.is_context() {
+                should_continue = true;
+                self.handle_context_command(&command, tx)?;
+            }
+
+            if command.is_paste() {
+                should_continue = true;
+                tx.send(Action::ClipboardPaste())?;
+                self.waiting_for_backend = true;
+            }
+
+            if command.is_pane() {
+                should_continue = true;
+                self.handle_pane_command(&command, tx)?;
+            }
+
             // Reset backend context on model switch.
             if command.is_model_set() {
                 self.backend_context = "".to_string();
@@ -275,22 +505,551 @@ impl<'a> AppState<'a> {
         return Ok((should_break, should_continue));
     }
 
+    fn handle_context_command(
+        &mut self,
+        command: &SlashCommand,
+        tx: &mpsc::UnboundedSender<Action>,
+    ) -> Result<()> {
+        let action = command.args.first().map(|e| return e.as_str());
+        let number = command.args.get(1).map(|e| return e.as_str());
+
+        match action {
+            Some("refresh") => {
+                tx.send(Action::EditorContextRefresh())?;
+                self.waiting_for_backend = true;
+            }
+            Some("add") => {
+                tx.send(Action::EditorContextAdd())?;
+                self.waiting_for_backend = true;
+            }
+            Some("clear") => {
+                self.editor_contexts.clear();
+                self.pending_editor_contexts.clear();
+                self.add_message(Message::new(
+                    Author::Oatmeal,
+                    "Cleared the editor context. New prompts won't include code from your editor until you use `/context add`.",
+                ));
+            }
+            Some("show") => {
+                if let Err(err) = self.set_contexts_collapsed(number, false) {
+                    self.add_message(Message::new_with_type(
+                        Author::Oatmeal,
+                        MessageType::Error,
+                        &err.to_string(),
+                    ));
+                } else if number.is_none() {
+                    self.add_message(Message::new(Author::Oatmeal, &self.contexts_summary()));
+                }
+            }
+            Some("hide") => {
+                if let Err(err) = self.set_contexts_collapsed(number, true) {
+                    self.add_message(Message::new_with_type(
+                        Author::Oatmeal,
+                        MessageType::Error,
+                        &err.to_string(),
+                    ));
+                }
+            }
+            _ => {
+                self.add_message(Message::new_with_type(
+                    Author::Oatmeal,
+                    MessageType::Error,
+                    "Usage: /context refresh, /context add, /context clear, /context show N?, /context hide N?",
+                ));
+            }
+        }
+
+        return Ok(());
+    }
+
+    fn handle_pane_command(
+        &mut self,
+        command: &SlashCommand,
+        tx: &mpsc::UnboundedSender<Action>,
+    ) -> Result<()> {
+        if self.editor_name != EditorName::Tmux {
+            self.add_message(Message::new_with_type(
+                Author::Oatmeal,
+                MessageType::Error,
+                "/pane picks which tmux pane to share, which needs the tmux editor. Start Oatmeal with `--editor tmux` from within tmux to use it.",
+            ));
+
+            return Ok(());
+        }
+
+        // Capture the new pane straight away, which also reports when it
+        // doesn't exist.
+        if let Some(target) = command.args.first() {
+            Config::set(ConfigKey::TmuxPane, target);
+            tx.send(Action::EditorContextRefresh())?;
+        } else {
+            tx.send(Action::TmuxListPanes())?;
+        }
+        self.waiting_for_backend = true;
+
+        return Ok(());
+    }
+
+    /// Expands or collapses context bubble `number`, or all of them when no
+    /// number is given.
+    fn set_contexts_collapsed(&mut self, number: Option<&str>, collapsed: bool) -> Result<()> {
+        let index = match number {
+            Some(number) => match number.parse::<usize>() {
+                Ok(number) if number > 0 => Some(number - 1),
+                _ => return Err(anyhow!("Context {number} is not valid")),
+            },
+            None => None,
+        };
+
+        let mut contexts = self
+            .messages
+            .iter_mut()
+            .filter(|message| return message.message_type() == MessageType::Context)
+            .enumerate()
+            .filter(|(idx, _)| return index.is_none() || index == Some(*idx))
+            .peekable();
+
+        if contexts.peek().is_none() {
+            if let Some(number) = number {
+                return Err(anyhow!("Context {number} is not valid"));
+            }
+        }
+
+        for (_, message) in contexts {
+            message.set_collapsed(collapsed);
+        }
+        self.sync_dependants();
+
+        return Ok(());
+    }
+
+    fn contexts_summary(&self) -> String {
+        if self.editor_contexts.is_empty() {
+            return "No editor context is being shared. Use `/context add` to share your current selection.".to_string();
+        }
+
+        let contexts = self
+            .editor_contexts
+            .iter()
+            .map(|context| return format!("- {}", context.summary()))
+            .collect::<Vec<String>>()
+            .join("\n");
+
+        return format!("Editor context shared with the model:\n\n{contexts}");
+    }
+
+    /// Handles `/apply PATH[:START_LINE-END_LINE] [CODE_BLOCK_NUMBER?]`, which
+    /// replaces a file, or a range of lines in it, with code blocks.
+    fn handle_apply_command(&mut self, command: &SlashCommand) {
+        if self.editor_name != EditorName::File {
+            self.add_message(Message::new_with_type(
+                Author::Oatmeal,
+                MessageType::Error,
+                "/apply writes code blocks straight to files, which needs the file editor. Start Oatmeal with `--editor file` to use it.",
+            ));
+            return;
+        }
+
+        let target = match command.args.first().filter(|e| return !e.is_empty()) {
+            Some(target) => target,
+            None => {
+                self.add_message(Message::new_with_type(
+                    Author::Oatmeal,
+                    MessageType::Error,
+                    "Usage: /apply PATH[:START_LINE-END_LINE] [CODE_BLOCK_NUMBER?]",
+                ));
+                return;
+            }
+        };
+
+        let codeblocks = match self.codeblocks.blocks_from_args(&command.args[1..]) {
+            Ok(codeblocks) => codeblocks,
+            Err(err) => {
+                self.add_message(Message::new_with_type(
+                    Author::Oatmeal,
+                    MessageType::Error,
+                    &format!(
+                        "There was an error trying to parse your command:\n\n{:?}",
+                        err
+                    ),
+                ));
+                return;
+            }
+        };
+
+        let (file_path, lines) = parse_file_target(target);
+        self.open_file_preview(&file_path, lines, &codeblocks, AcceptType::Replace);
+    }
+
+    /// Previews writing code blocks to a file with the file editor, warning
+    /// when the file is outside of the working directory.
+    fn open_file_preview(
+        &mut self,
+        file_path: &str,
+        lines: Option<(i64, i64)>,
+        codeblocks: &str,
+        accept_type: AcceptType,
+    ) {
+        if codeblocks.is_empty() {
+            self.add_message(Message::new_with_type(
+                Author::Oatmeal,
+                MessageType::Error,
+                "There are no code blocks to write.",
+            ));
+            return;
+        }
+
+        let editor = match FileEditor::new() {
+            Ok(editor) => editor,
+            Err(err) => {
+                self.add_message(Message::new_with_type(
+                    Author::Oatmeal,
+                    MessageType::Error,
+                    &err.to_string(),
+                ));
+                return;
+            }
+        };
+        let context = match editor.context(file_path, lines) {
+            Ok(context) => context,
+            Err(err) => {
+                self.add_message(Message::new_with_type(
+                    Author::Oatmeal,
+                    MessageType::Error,
+                    &err.to_string(),
+                ));
+                return;
+            }
+        };
+
+        self.open_diff_preview(context, codeblocks, accept_type);
+        if editor.is_outside_root(file_path) {
+            if let Some(preview) = self.diff_preview.as_mut() {
+                preview.warning = Some(format!(
+                    "{file_path} is outside of the working directory. Only accept if you're sure it should be written to."
+                ));
+            }
+        }
+    }
+
+    fn open_diff_preview(
+        &mut self,
+        context: EditorContext,
+        codeblocks: &str,
+        accept_type: AcceptType,
+    ) {
+        let preview = DiffPreview::new(context, codeblocks, accept_type, self.bubble_list.theme());
+        if preview.hunks.is_empty() {
+            self.add_message(Message::new(
+                Author::Oatmeal,
+                "The code blocks match the code in your editor, there's nothing to change.",
+            ));
+            return;
+        }
+
+        self.diff_preview = Some(preview);
+    }
+
+    /// Closes the diff preview, sending the accepted hunks to the editor.
+    pub fn close_diff_preview(
+        &mut self,
+        decision: DiffPreviewDecision,
+        tx: &mpsc::UnboundedSender<Action>,
+    ) -> Result<()> {
+        let preview = match self.diff_preview.take() {
+            Some(preview) => preview,
+            None => return Ok(()),
+        };
+
+        if decision == DiffPreviewDecision::Accept {
+            if let Some((context, code, accept_type)) = preview.accepted() {
+                // Previews only warn about files outside of the working
+                // directory, so accepting one confirms writing to it.
+                if preview.warning.is_some() {
+                    tx.send(Action::AcceptCodeBlockOutsideRoot(
+                        context,
+                        code,
+                        accept_type,
+                    ))?;
+                    return Ok(());
+                }
+
+                tx.send(Action::AcceptCodeBlock(Some(context), code, accept_type))?;
+                return Ok(());
+            }
+        }
+
+        self.add_message(Message::new(
+            Author::Oatmeal,
+            "Discarded the changes, nothing was sent to your editor.",
+        ));
+
+        return Ok(());
+    }
+
+    /// Shares a context fetched with `/context add` or `/context refresh` in
+    /// the chat, and includes it in the next prompt. Refreshing replaces all
+    /// previously shared contexts.
+    pub fn share_editor_context(&mut self, context: EditorContext, replace: bool) {
+        if replace {
+            self.editor_contexts.clear();
+            self.pending_editor_contexts.clear();
+        }
+
+        self.editor_context = Some(context.clone());
+        self.share_context(context);
+    }
+
+    /// Shares text read from the clipboard with `/paste` like any other
+    /// context, without it becoming the target for code blocks.
+    pub fn share_clipboard(&mut self, text: &str) {
+        let code = text.replace("\r\n", "\n").trim_end().to_string();
+        let context = EditorContext {
+            file_path: "Clipboard".to_string(),
+            language: "text".to_string(),
+            end_line: Some(i64::try_from(code.lines().count()).unwrap()),
+            code,
+            start_line: 1,
+        };
+
+        self.share_context(context);
+    }
+
+    /// Shows a context collapsed in the chat, and includes it in the next
+    /// prompt.
+    fn share_context(&mut self, context: EditorContext) {
+        let number = self
+            .messages
+            .iter()
+            .filter(|message| return message.message_type() == MessageType::Context)
+            .count()
+            + 1;
+
+        self.waiting_for_backend = false;
+        self.editor_contexts.push(context.clone());
+        self.pending_editor_contexts.push(context.clone());
+        self.add_message(Message::new_context(number, &context));
+    }
+
+    /// Contexts to send with the next prompt. That's every shared context at
+    /// the start of a conversation, and only newly shared ones after.
+    pub fn take_prompt_contexts(&mut self) -> Vec<EditorContext> {
+        let pending = std::mem::take(&mut self.pending_editor_contexts);
+        if self.backend_context.is_empty() {
+            return self.editor_contexts.clone();
+        }
+
+        return pending;
+    }
+
+    /// Handles `/queue` commands, which manage queued prompts without being
+    /// added to the chat. Returns a prompt to load back in to the text area
+    /// when editing.
+    pub fn handle_queue_command(&mut self, command: &SlashCommand) -> Option<String> {
+        let action = command.args.first().map(|e| return e.as_str());
+        let position = command.args.get(1).map(|e| return e.as_str());
+
+        let res = match action {
+            Some("edit") => self.prompt_queue.take(position).map(Some),
+            Some("cancel") => self.prompt_queue.take(position).map(|_| return None),
+            Some("clear") => {
+                self.prompt_queue.clear();
+                Ok(None)
+            }
+            _ => Err(anyhow!(
+                "Usage: /queue edit N, /queue cancel N, /queue clear"
+            )),
+        };
+
+        match res {
+            Ok(prompt) => return prompt,
+            Err(err) => {
+                self.prompt_queue.notice = Some(err.to_string());
+                return None;
+            }
+        }
+    }
+
+    /// Starts navigate mode, focusing the latest message.
+    pub fn start_navigating(&mut self) {
+        if self.messages.is_empty() {
+            return;
+        }
+
+        self.focus_message(self.messages.len() - 1);
+    }
+
+    pub fn stop_navigating(&mut self) {
+        self.focused_message = None;
+        self.navigate_notice = None;
+        self.bubble_list.set_focused(&self.messages, None);
+    }
+
+    /// Moves focus by `offset` messages, stopping at the first and last.
+    pub fn move_focus(&mut self, offset: isize) {
+        if let Some(idx) = self.focused_message {
+            let last = self.messages.len().saturating_sub(1);
+            self.focus_message(idx.saturating_add_signed(offset).min(last));
+        }
+    }
+
+    fn focus_message(&mut self, idx: usize) {
+        self.focused_message = Some(idx);
+        self.bubble_list.set_focused(&self.messages, Some(idx));
+        if let Some((start, end)) = self.bubble_list.line_range(idx) {
+            self.scroll.show(start, end);
+        }
+    }
+
+    /// Copying reports back with a message of its own, which would cut off a
+    /// response that's still streaming in.
+    fn check_not_waiting(&mut self) -> bool {
+        if self.waiting_for_backend {
+            self.navigate_notice = Some(
+                "Wait for the response to finish first, or interrupt it with CTRL+C".to_string(),
+            );
+            return false;
+        }
+
+        return true;
+    }
+
+    pub fn copy_focused_message(&mut self, tx: &mpsc::UnboundedSender<Action>) -> Result<()> {
+        let message = match self.focused_message {
+            Some(idx) => self.messages[idx].clone(),
+            None => return Ok(()),
+        };
+        if !self.check_not_waiting() {
+            return Ok(());
+        }
+
+        tx.send(Action::CopyMessages(vec![message]))?;
+        self.waiting_for_backend = true;
+
+        return Ok(());
+    }
+
+    /// Copies code block `number` of the focused message, counting from 1
+    /// within the message.
+    pub fn copy_focused_codeblock(
+        &mut self,
+        number: usize,
+        tx: &mpsc::UnboundedSender<Action>,
+    ) -> Result<()> {
+        let codeblocks = match self.focused_message {
+            Some(idx) => self.messages[idx].codeblocks(),
+            None => return Ok(()),
+        };
+        if !self.check_not_waiting() {
+            return Ok(());
+        }
+
+        match number
+            .checked_sub(1)
+            .and_then(|idx| return codeblocks.get(idx))
+        {
+            Some(code) => {
+                tx.send(Action::CopyMessages(vec![Message::new(
+                    Author::Model,
+                    code,
+                )]))?;
+                self.waiting_for_backend = true;
+            }
+            None => {
+                self.add_message(Message::new_with_type(
+                    Author::Oatmeal,
+                    MessageType::Error,
+                    &format!("The selected message doesn't have a code block {number}."),
+                ));
+            }
+        }
+
+        return Ok(());
+    }
+
+    /// Leaves navigate mode, returning the prompt to ask again. That's the
+    /// focused message itself when it's a prompt, otherwise the prompt it
+    /// answered.
+    pub fn reask_focused_message(&mut self) -> Option<String> {
+        let idx = self.focused_message?;
+        let prompt = self.messages[..=idx]
+            .iter()
+            .rev()
+            .find(|message| {
+                return message.author == Author::User
+                    && SlashCommand::parse(&message.text).is_none();
+            })
+            .map(|message| return message.text.to_string());
+
+        if prompt.is_some() {
+            self.stop_navigating();
+            self.scroll.last();
+        }
+
+        return prompt;
+    }
+
+    /// Removes the focused message from the chat and its session, moving
+    /// focus to the next one. Models that keep chat history still remember
+    /// it.
+    pub fn delete_focused_message(&mut self) {
+        let idx = match self.focused_message {
+            Some(idx) => idx,
+            None => return,
+        };
+        if self.waiting_for_backend && idx == self.messages.len() - 1 {
+            self.navigate_notice = Some(
+                "The response is still streaming in. Interrupt it with CTRL+C before deleting it"
+                    .to_string(),
+            );
+            return;
+        }
+
+        self.messages.remove(idx);
+        self.bubble_list.remove(idx);
+        self.codeblocks.replace_from_messages(&self.messages);
+        self.sync_dependants();
+
+        if self.messages.is_empty() {
+            self.stop_navigating();
+            return;
+        }
+        self.focus_message(idx.min(self.messages.len() - 1));
+    }
+
     pub fn set_rect(&mut self, rect: Rect) {
-        self.last_known_width = rect.width.into();
-        self.last_known_height = rect.height.into();
+        let width = rect.width.into();
+        let height = rect.height.into();
+        if self.last_known_width == width && self.last_known_height == height {
+            return;
+        }
+
+        self.last_known_width = width;
+        self.last_known_height = height;
         self.sync_dependants();
     }
 
     pub fn add_message(&mut self, message: Message) {
         self.messages.push(message);
-        self.sync_dependants();
+        self.sync_last_message();
         self.scroll.last();
     }
 
     fn sync_dependants(&mut self) {
         self.bubble_list
             .set_messages(&self.messages, self.last_known_width);
+        self.sync_scroll();
+    }
+
+    /// Like `sync_dependants`, but only for when messages were added or the
+    /// last one changed, such as on every chunk of a streamed response.
+    fn sync_last_message(&mut self) {
+        self.bubble_list
+            .set_last_message(&self.messages, self.last_known_width);
+        self.sync_scroll();
+    }
 
+    fn sync_scroll(&mut self) {
         let scrollbar_at_bottom = self.scroll.is_position_at_last();
         self.scroll
             .set_state(self.bubble_list.len(), self.last_known_height);
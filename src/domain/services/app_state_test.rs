@@ -1,21 +1,30 @@
 use anyhow::bail;
 use anyhow::Result;
+use ratatui::prelude::Rect;
 use test_utils::codeblock_fixture;
 use test_utils::insta_snapshot;
 use tokio::sync::mpsc;
 
 use super::AppState;
+use crate::configuration::Config;
+use crate::configuration::ConfigKey;
 use crate::domain::models::AcceptType;
 use crate::domain::models::Action;
 use crate::domain::models::Author;
+use crate::domain::models::BackendCapabilities;
 use crate::domain::models::BackendName;
 use crate::domain::models::BackendResponse;
+use crate::domain::models::EditorContext;
 use crate::domain::models::EditorName;
 use crate::domain::models::Message;
 use crate::domain::models::MessageType;
+use crate::domain::models::SlashCommand;
 use crate::domain::services::AppStateProps;
 use crate::domain::services::BubbleList;
 use crate::domain::services::CodeBlocks;
+use crate::domain::services::DiffPreviewDecision;
+use crate::domain::services::ModelCache;
+use crate::domain::services::PromptQueue;
 use crate::domain::services::Scroll;
 use crate::domain::services::Sessions;
 use crate::domain::services::Themes;
@@ -26,17 +35,30 @@ impl Default for AppState<'static> {
     fn default() -> AppState<'static> {
         let theme = Themes::get("base16-onedark", "").unwrap();
         return AppState {
+            backend_capabilities: BackendCapabilities {
+                stateful_context: true,
+                ..BackendCapabilities::default()
+            },
             backend_context: "".to_string(),
             bubble_list: BubbleList::new(theme),
             codeblocks: CodeBlocks::default(),
+            diff_preview: None,
             editor_context: None,
+            editor_contexts: vec![],
+            editor_name: EditorName::Clipboard,
             exit_warning: false,
+            focused_message: None,
             last_known_height: 300,
             last_known_width: 100,
             messages: vec![],
+            model_picker: None,
+            navigate_notice: None,
+            pending_editor_contexts: vec![],
+            prompt_queue: PromptQueue::default(),
             session_id: "test".to_string(),
             scroll: Scroll::default(),
             sessions_service: Sessions::default(),
+            waiting_for_abort: false,
             waiting_for_backend: false,
         };
     }
@@ -86,6 +108,75 @@ mod handle_slash_commands {
         return Ok(());
     }
 
+    #[test]
+    fn it_previews_code_blocks_before_sending_to_editors() -> Result<()> {
+        let (tx, mut rx) = mpsc::unbounded_channel::<Action>();
+        let mut app_state = AppState {
+            editor_context: Some(EditorContext {
+                file_path: "src/main.rs".to_string(),
+                language: "rust".to_string(),
+                code: "fn main() {}".to_string(),
+                start_line: 1,
+                end_line: Some(1),
+            }),
+            editor_name: EditorName::Neovim,
+            ..AppState::default()
+        };
+        app_state
+            .codeblocks
+            .replace_from_messages(&[Message::new(Author::Model, codeblock_fixture())]);
+
+        let (should_break, should_continue) = app_state.handle_slash_commands("/replace 1", &tx)?;
+
+        assert!(!should_break);
+        assert!(should_continue);
+        assert!(app_state.diff_preview.is_some());
+        assert!(rx.try_recv().is_err());
+
+        app_state.close_diff_preview(DiffPreviewDecision::Accept, &tx)?;
+        assert!(app_state.diff_preview.is_none());
+
+        let event = rx.blocking_recv().unwrap();
+        match event {
+            Action::AcceptCodeBlock(context, codeblock, accept_type) => {
+                assert_eq!(context.unwrap().file_path, "src/main.rs");
+                assert_eq!(accept_type, AcceptType::Replace);
+                assert!(!codeblock.contains("fn main() {}"));
+            }
+            _ => bail!("Wrong enum"),
+        }
+
+        return Ok(());
+    }
+
+    #[test]
+    fn it_discards_rejected_code_block_previews() -> Result<()> {
+        let (tx, mut rx) = mpsc::unbounded_channel::<Action>();
+        let mut app_state = AppState {
+            editor_context: Some(EditorContext {
+                file_path: "src/main.rs".to_string(),
+                language: "rust".to_string(),
+                code: "fn main() {}".to_string(),
+                start_line: 1,
+                end_line: Some(1),
+            }),
+            editor_name: EditorName::Neovim,
+            ..AppState::default()
+        };
+        app_state
+            .codeblocks
+            .replace_from_messages(&[Message::new(Author::Model, codeblock_fixture())]);
+
+        app_state.handle_slash_commands("/append 1", &tx)?;
+        app_state.close_diff_preview(DiffPreviewDecision::Reject, &tx)?;
+
+        assert!(app_state.diff_preview.is_none());
+        assert!(rx.try_recv().is_err());
+        insta::assert_snapshot!(app_state.messages.last().unwrap().text, @"Discarded the changes, nothing was sent to your editor.");
+
+        return Ok(());
+    }
+
     #[test]
     fn it_replaces_code_block() -> Result<()> {
         let (tx, mut rx) = mpsc::unbounded_channel::<Action>();
@@ -191,6 +282,75 @@ mod handle_slash_commands {
         return Ok(());
     }
 
+    #[test]
+    fn it_continues_interrupted_response() -> Result<()> {
+        let (tx, mut rx) = mpsc::unbounded_channel::<Action>();
+        let mut app_state = AppState {
+            backend_context: "icanrememberthingsnow".to_string(),
+            ..AppState::default()
+        };
+        let mut message = Message::new(Author::Model, "Working on");
+        message.set_interrupted();
+        app_state.add_message(message);
+
+        let (should_break, should_continue) = app_state.handle_slash_commands("/continue", &tx)?;
+
+        assert!(!should_break);
+        assert!(should_continue);
+        assert!(app_state.waiting_for_backend);
+
+        let event = rx.blocking_recv().unwrap();
+        match event {
+            Action::BackendRequest(prompt) => {
+                assert_eq!(prompt.backend_context, "icanrememberthingsnow");
+                assert!(SlashCommand::parse(&prompt.text).is_none());
+            }
+            _ => bail!("Wrong enum"),
+        }
+
+        return Ok(());
+    }
+
+    #[test]
+    fn it_returns_error_message_on_continue_without_interruption() -> Result<()> {
+        let (tx, _rx) = mpsc::unbounded_channel::<Action>();
+        let mut app_state = AppState::default();
+        app_state.add_message(Message::new(Author::Model, "All done!"));
+
+        let (should_break, should_continue) = app_state.handle_slash_commands("/continue", &tx)?;
+        let last_message = app_state.messages.last().unwrap();
+
+        assert!(!should_break);
+        assert!(should_continue);
+        assert!(!app_state.waiting_for_backend);
+        assert_eq!(last_message.message_type(), MessageType::Error);
+
+        return Ok(());
+    }
+
+    #[test]
+    fn it_returns_error_message_on_continue_without_stateful_context() -> Result<()> {
+        let (tx, mut rx) = mpsc::unbounded_channel::<Action>();
+        let mut app_state = AppState {
+            backend_capabilities: BackendCapabilities::default(),
+            ..AppState::default()
+        };
+        let mut message = Message::new(Author::Model, "Working on");
+        message.set_interrupted();
+        app_state.add_message(message);
+
+        let (should_break, should_continue) = app_state.handle_slash_commands("/continue", &tx)?;
+        let last_message = app_state.messages.last().unwrap();
+
+        assert!(!should_break);
+        assert!(should_continue);
+        assert!(!app_state.waiting_for_backend);
+        assert_eq!(last_message.message_type(), MessageType::Error);
+        assert!(rx.try_recv().is_err());
+
+        return Ok(());
+    }
+
     #[test]
     fn it_handles_model_set() -> Result<()> {
         let (tx, _rx) = mpsc::unbounded_channel::<Action>();
@@ -208,45 +368,943 @@ mod handle_slash_commands {
     }
 }
 
-mod handle_backend_response {
+mod interrupt_backend_response {
     use super::*;
 
     #[test]
-    fn it_handles_new_backend_response() {
+    fn it_keeps_partial_response() {
         let mut app_state = AppState::default();
         app_state
             .messages
             .push(Message::new(Author::User, "Do something for me!"));
-        let backend_response = BackendResponse {
-            author: Author::Model,
-            text: "All done!".to_string(),
-            done: true,
-            context: Some("icanrememberthingsnow".to_string()),
-        };
-        app_state.handle_backend_response(backend_response);
+        app_state
+            .messages
+            .push(Message::new(Author::Model, "Working on it"));
+        app_state.waiting_for_backend = true;
 
+        let partial_text = app_state.interrupt_backend_response();
+
+        assert_eq!(partial_text, "Working on it");
+        assert!(!app_state.waiting_for_backend);
         assert_eq!(app_state.messages.len(), 2);
+        assert!(app_state.messages.last().unwrap().is_interrupted());
     }
 
     #[test]
-    fn it_handles_bad_backend_response() {
+    fn it_adds_empty_response_when_nothing_was_streamed() {
         let mut app_state = AppState::default();
         app_state
             .messages
             .push(Message::new(Author::User, "Do something for me!"));
-        let backend_response = BackendResponse {
-            author: Author::Model,
-            text: "All done!".to_string(),
-            done: true,
-            context: Some("".to_string()),
+        app_state.waiting_for_backend = true;
+
+        let partial_text = app_state.interrupt_backend_response();
+        let last_message = app_state.messages.last().unwrap();
+
+        assert!(partial_text.is_empty());
+        assert_eq!(app_state.messages.len(), 2);
+        assert_eq!(last_message.author, Author::Model);
+        assert!(last_message.is_interrupted());
+    }
+
+    #[test]
+    fn it_ignores_slash_commands() {
+        let mut app_state = AppState::default();
+        app_state.messages.push(Message::new(Author::User, "/copy"));
+        app_state.waiting_for_backend = true;
+
+        app_state.interrupt_backend_response();
+
+        assert!(!app_state.waiting_for_backend);
+        assert_eq!(app_state.messages.len(), 1);
+    }
+
+    #[test]
+    fn it_holds_off_queued_prompts_until_the_abort_is_confirmed() {
+        let mut app_state = AppState {
+            backend_context: "before".to_string(),
+            waiting_for_backend: true,
+            ..AppState::default()
         };
-        app_state.handle_backend_response(backend_response);
+        app_state
+            .messages
+            .push(Message::new(Author::User, "Do something for me!"));
 
-        assert_eq!(app_state.messages.len(), 3);
+        app_state.interrupt_backend_response();
+        assert!(app_state.waiting_for_abort);
+        assert!(app_state.is_waiting());
+
+        app_state.handle_backend_aborted(Some("interrupted".to_string()));
+        assert!(!app_state.waiting_for_abort);
+        assert!(!app_state.is_waiting());
+        assert_eq!(app_state.backend_context, "interrupted");
+    }
+
+    #[test]
+    fn it_keeps_context_when_the_abort_has_none() {
+        let mut app_state = AppState {
+            backend_context: "before".to_string(),
+            waiting_for_abort: true,
+            ..AppState::default()
+        };
+
+        app_state.handle_backend_aborted(None);
+
+        assert!(!app_state.is_waiting());
+        assert_eq!(app_state.backend_context, "before");
+    }
+}
+
+mod handle_queue_command {
+    use super::*;
+
+    fn queued_app_state() -> AppState<'static> {
+        let mut app_state = AppState::default();
+        app_state.prompt_queue.push("First".to_string());
+        app_state.prompt_queue.push("Second".to_string());
+        app_state.prompt_queue.push("Third".to_string());
+        return app_state;
+    }
+
+    #[test]
+    fn it_edits_queued_prompt() {
+        let mut app_state = queued_app_state();
+        let command = SlashCommand::parse("/queue edit 2").unwrap();
+
+        let res = app_state.handle_queue_command(&command);
+
+        assert_eq!(res, Some("Second".to_string()));
+        assert_eq!(app_state.prompt_queue.len(), 2);
+        assert!(app_state.messages.is_empty());
+    }
+
+    #[test]
+    fn it_cancels_last_queued_prompt() {
+        let mut app_state = queued_app_state();
+        let command = SlashCommand::parse("/queue cancel").unwrap();
+
+        let res = app_state.handle_queue_command(&command);
+
+        assert!(res.is_none());
+        assert_eq!(app_state.prompt_queue.len(), 2);
+        assert_eq!(app_state.prompt_queue.next(), Some("First".to_string()));
+        assert_eq!(app_state.prompt_queue.next(), Some("Second".to_string()));
+    }
+
+    #[test]
+    fn it_clears_queued_prompts() {
+        let mut app_state = queued_app_state();
+        let command = SlashCommand::parse("/queue clear").unwrap();
+
+        app_state.handle_queue_command(&command);
+
+        assert!(app_state.prompt_queue.is_empty());
+    }
+
+    #[test]
+    fn it_sets_notice_on_invalid_position() {
+        let mut app_state = queued_app_state();
+        let command = SlashCommand::parse("/queue edit 10").unwrap();
+
+        let res = app_state.handle_queue_command(&command);
+
+        assert!(res.is_none());
+        assert_eq!(app_state.prompt_queue.len(), 3);
+        insta::assert_snapshot!(app_state.prompt_queue.notice.unwrap(), @"Queued prompt 10 is not valid");
+    }
+}
+
+mod handle_context_command {
+    use super::*;
+
+    fn context(file_path: &str) -> EditorContext {
+        return EditorContext {
+            file_path: file_path.to_string(),
+            language: "rust".to_string(),
+            code: "let x = 5;".to_string(),
+            start_line: 1,
+            end_line: Some(1),
+        };
+    }
+
+    #[test]
+    fn it_requests_context_on_add() -> Result<()> {
+        let (tx, mut rx) = mpsc::unbounded_channel::<Action>();
+        let mut app_state = AppState::default();
+
+        let (should_break, should_continue) =
+            app_state.handle_slash_commands("/context add", &tx)?;
+
+        assert!(!should_break);
+        assert!(should_continue);
+        assert!(app_state.waiting_for_backend);
+        match rx.blocking_recv().unwrap() {
+            Action::EditorContextAdd() => {}
+            _ => bail!("Wrong enum"),
+        }
+
+        return Ok(());
+    }
+
+    #[test]
+    fn it_requests_context_on_refresh() -> Result<()> {
+        let (tx, mut rx) = mpsc::unbounded_channel::<Action>();
+        let mut app_state = AppState::default();
+
+        app_state.handle_slash_commands("/ctx refresh", &tx)?;
+
+        match rx.blocking_recv().unwrap() {
+            Action::EditorContextRefresh() => {}
+            _ => bail!("Wrong enum"),
+        }
+
+        return Ok(());
+    }
+
+    #[test]
+    fn it_shares_added_contexts_as_collapsed_bubbles() {
+        let mut app_state = AppState {
+            waiting_for_backend: true,
+            ..AppState::default()
+        };
+
+        app_state.share_editor_context(context("a.rs"), false);
+        app_state.share_editor_context(context("b.rs"), false);
+
+        assert!(!app_state.waiting_for_backend);
+        assert_eq!(app_state.editor_contexts.len(), 2);
+        assert_eq!(app_state.editor_context.as_ref().unwrap().file_path, "b.rs");
+        assert_eq!(app_state.messages.len(), 2);
+        assert_eq!(app_state.messages[1].message_type(), MessageType::Context);
+        assert!(app_state.messages[1].is_collapsed());
+        assert!(app_state.messages[1]
+            .text
+            .starts_with("Context 2 - File: b.rs"));
+    }
+
+    #[test]
+    fn it_replaces_contexts_on_refresh() {
+        let mut app_state = AppState::default();
+
+        app_state.share_editor_context(context("a.rs"), false);
+        app_state.share_editor_context(context("b.rs"), true);
+
+        assert_eq!(app_state.editor_contexts.len(), 1);
+        assert_eq!(app_state.editor_contexts[0].file_path, "b.rs");
+        assert_eq!(app_state.pending_editor_contexts.len(), 1);
+    }
+
+    #[test]
+    fn it_sends_new_contexts_mid_conversation() {
+        let mut app_state = AppState {
+            backend_context: "icanrememberthingsnow".to_string(),
+            editor_contexts: vec![context("a.rs")],
+            ..AppState::default()
+        };
+
+        assert!(app_state.take_prompt_contexts().is_empty());
+
+        app_state.share_editor_context(context("b.rs"), false);
+        let contexts = app_state.take_prompt_contexts();
+
+        assert_eq!(contexts.len(), 1);
+        assert_eq!(contexts[0].file_path, "b.rs");
+        assert!(app_state.take_prompt_contexts().is_empty());
+    }
+
+    #[test]
+    fn it_sends_all_contexts_at_the_start_of_a_conversation() {
+        let mut app_state = AppState {
+            editor_contexts: vec![context("a.rs")],
+            ..AppState::default()
+        };
+        app_state.share_editor_context(context("b.rs"), false);
+
+        assert_eq!(app_state.take_prompt_contexts().len(), 2);
+        assert!(app_state.pending_editor_contexts.is_empty());
+    }
+
+    #[test]
+    fn it_clears_contexts() -> Result<()> {
+        let (tx, _rx) = mpsc::unbounded_channel::<Action>();
+        let mut app_state = AppState::default();
+        app_state.share_editor_context(context("a.rs"), false);
+
+        app_state.handle_slash_commands("/context clear", &tx)?;
+
+        assert!(app_state.editor_contexts.is_empty());
+        assert!(app_state.take_prompt_contexts().is_empty());
+
+        return Ok(());
+    }
+
+    #[test]
+    fn it_expands_and_collapses_contexts() -> Result<()> {
+        let (tx, _rx) = mpsc::unbounded_channel::<Action>();
+        let mut app_state = AppState::default();
+        app_state.share_editor_context(context("a.rs"), false);
+        app_state.share_editor_context(context("b.rs"), false);
+
+        app_state.handle_slash_commands("/context show 2", &tx)?;
+        assert!(app_state.messages[0].is_collapsed());
+        assert!(!app_state.messages[1].is_collapsed());
+
+        app_state.handle_slash_commands("/context show", &tx)?;
+        assert!(!app_state.messages[0].is_collapsed());
+        insta::assert_snapshot!(app_state.messages.last().unwrap().text, @r###"
+        Editor context shared with the model:
+
+        - a.rs (line 1)
+        - b.rs (line 1)
+        "###);
+
+        app_state.handle_slash_commands("/context hide", &tx)?;
+        assert!(app_state.messages[0].is_collapsed());
+        assert!(app_state.messages[1].is_collapsed());
+
+        return Ok(());
+    }
+
+    #[test]
+    fn it_returns_error_message_on_invalid_context_number() -> Result<()> {
+        let (tx, _rx) = mpsc::unbounded_channel::<Action>();
+        let mut app_state = AppState::default();
+        app_state.share_editor_context(context("a.rs"), false);
+
+        app_state.handle_slash_commands("/context show 3", &tx)?;
+        let last_message = app_state.messages.last().unwrap();
+
+        assert_eq!(last_message.message_type(), MessageType::Error);
+        insta::assert_snapshot!(last_message.text, @"Context 3 is not valid");
+
+        return Ok(());
+    }
+
+    #[test]
+    fn it_returns_usage_on_unknown_action() -> Result<()> {
+        let (tx, mut rx) = mpsc::unbounded_channel::<Action>();
+        let mut app_state = AppState::default();
+
+        let (_, should_continue) = app_state.handle_slash_commands("/context", &tx)?;
+
+        assert!(should_continue);
+        assert!(rx.try_recv().is_err());
         assert_eq!(
             app_state.messages.last().unwrap().message_type(),
             MessageType::Error
         );
+
+        return Ok(());
+    }
+}
+
+mod handle_paste_command {
+    use super::*;
+
+    #[test]
+    fn it_reads_the_clipboard() -> Result<()> {
+        let (tx, mut rx) = mpsc::unbounded_channel::<Action>();
+        let mut app_state = AppState::default();
+
+        let (_should_break, should_continue) = app_state.handle_slash_commands("/paste", &tx)?;
+
+        assert!(should_continue);
+        assert!(app_state.waiting_for_backend);
+        match rx.blocking_recv().unwrap() {
+            Action::ClipboardPaste() => {}
+            _ => bail!("Wrong enum"),
+        }
+
+        return Ok(());
+    }
+
+    #[test]
+    fn it_shares_clipboard_text_as_a_collapsed_context() {
+        let editor_context = EditorContext {
+            file_path: "src/main.rs".to_string(),
+            ..EditorContext::default()
+        };
+        let mut app_state = AppState {
+            editor_context: Some(editor_context),
+            waiting_for_backend: true,
+            ..AppState::default()
+        };
+
+        app_state.share_clipboard("error[E0308]: mismatched types\r\n\tat src/main.rs:4\r\n\r\n");
+
+        assert!(!app_state.waiting_for_backend);
+        assert_eq!(
+            app_state.editor_context.as_ref().unwrap().file_path,
+            "src/main.rs"
+        );
+
+        let contexts = app_state.take_prompt_contexts();
+        assert_eq!(contexts.len(), 1);
+        assert_eq!(contexts[0].file_path, "Clipboard");
+        assert_eq!(
+            contexts[0].code,
+            "error[E0308]: mismatched types\n\tat src/main.rs:4"
+        );
+        assert_eq!(contexts[0].end_line, Some(2));
+
+        let message = app_state.messages.last().unwrap();
+        assert_eq!(message.message_type(), MessageType::Context);
+        assert!(message.is_collapsed());
+        assert!(message.text.starts_with("Context 1 - File: Clipboard"));
+    }
+}
+
+mod handle_apply_command {
+    use super::*;
+
+    fn app_state() -> AppState<'static> {
+        let mut app_state = AppState {
+            editor_name: EditorName::File,
+            ..AppState::default()
+        };
+        app_state.codeblocks.replace_from_messages(&[Message::new(
+            Author::Model,
+            "```rust src/oatmeal_test_new_file.rs\nfn main() {}\n```\n\n```rust\nlet x = 5;\n```",
+        )]);
+
+        return app_state;
+    }
+
+    #[test]
+    fn it_requires_the_file_editor() -> Result<()> {
+        let (tx, _rx) = mpsc::unbounded_channel::<Action>();
+        let mut app_state = AppState::default();
+
+        let (_should_break, should_continue) =
+            app_state.handle_slash_commands("/apply src/main.rs", &tx)?;
+
+        assert!(should_continue);
+        assert!(app_state.diff_preview.is_none());
+        insta::assert_snapshot!(app_state.messages.last().unwrap().text, @"/apply writes code blocks straight to files, which needs the file editor. Start Oatmeal with `--editor file` to use it.");
+
+        return Ok(());
+    }
+
+    #[test]
+    fn it_requires_a_path() -> Result<()> {
+        let (tx, _rx) = mpsc::unbounded_channel::<Action>();
+        let mut app_state = app_state();
+
+        app_state.handle_slash_commands("/apply", &tx)?;
+
+        insta::assert_snapshot!(app_state.messages.last().unwrap().text, @"Usage: /apply PATH[:START_LINE-END_LINE] [CODE_BLOCK_NUMBER?]");
+
+        return Ok(());
+    }
+
+    #[test]
+    fn it_previews_writing_to_a_path() -> Result<()> {
+        let (tx, mut rx) = mpsc::unbounded_channel::<Action>();
+        let mut app_state = app_state();
+
+        app_state.handle_slash_commands("/apply src/oatmeal_test_other.rs:2-3 2", &tx)?;
+
+        let preview = app_state.diff_preview.as_ref().unwrap();
+        assert!(preview.warning.is_none());
+
+        app_state.close_diff_preview(DiffPreviewDecision::Accept, &tx)?;
+        match rx.blocking_recv().unwrap() {
+            Action::AcceptCodeBlock(context, codeblock, accept_type) => {
+                let context = context.unwrap();
+                assert_eq!(context.file_path, "src/oatmeal_test_other.rs");
+                assert_eq!(context.start_line, 2);
+                assert_eq!(codeblock, "let x = 5;");
+                assert_eq!(accept_type, AcceptType::Replace);
+            }
+            _ => bail!("Wrong enum"),
+        }
+
+        return Ok(());
+    }
+
+    #[test]
+    fn it_warns_about_paths_outside_the_working_directory() -> Result<()> {
+        let (tx, mut rx) = mpsc::unbounded_channel::<Action>();
+        let mut app_state = app_state();
+
+        app_state.handle_slash_commands("/apply ../oatmeal_test_outside.rs 2", &tx)?;
+
+        let preview = app_state.diff_preview.as_ref().unwrap();
+        insta::assert_snapshot!(preview.warning.as_ref().unwrap(), @"../oatmeal_test_outside.rs is outside of the working directory. Only accept if you're sure it should be written to.");
+
+        app_state.close_diff_preview(DiffPreviewDecision::Accept, &tx)?;
+        match rx.blocking_recv().unwrap() {
+            Action::AcceptCodeBlockOutsideRoot(context, codeblock, _accept_type) => {
+                assert_eq!(context.file_path, "../oatmeal_test_outside.rs");
+                assert_eq!(codeblock, "let x = 5;");
+            }
+            _ => bail!("Wrong enum"),
+        }
+
+        return Ok(());
+    }
+
+    #[test]
+    fn it_uses_file_paths_from_code_blocks() -> Result<()> {
+        let (tx, mut rx) = mpsc::unbounded_channel::<Action>();
+        let mut app_state = app_state();
+
+        app_state.handle_slash_commands("/append 1", &tx)?;
+        app_state.close_diff_preview(DiffPreviewDecision::Accept, &tx)?;
+
+        match rx.blocking_recv().unwrap() {
+            Action::AcceptCodeBlock(context, codeblock, accept_type) => {
+                assert_eq!(context.unwrap().file_path, "src/oatmeal_test_new_file.rs");
+                assert_eq!(codeblock, "fn main() {}");
+                assert_eq!(accept_type, AcceptType::Append);
+            }
+            _ => bail!("Wrong enum"),
+        }
+
+        return Ok(());
+    }
+
+    #[test]
+    fn it_fails_without_a_file_path_in_code_blocks() -> Result<()> {
+        let (tx, _rx) = mpsc::unbounded_channel::<Action>();
+        let mut app_state = app_state();
+
+        app_state.handle_slash_commands("/replace 2", &tx)?;
+
+        assert!(app_state.diff_preview.is_none());
+        insta::assert_snapshot!(app_state.messages.last().unwrap().text, @"The code blocks don't say which file they're for, such as ```rust src/main.rs. Use `/apply PATH [CODE_BLOCK_NUMBER?]` to choose a file instead.");
+
+        return Ok(());
+    }
+}
+
+mod handle_patch_command {
+    use super::*;
+
+    #[test]
+    fn it_sends_patches() -> Result<()> {
+        let (tx, mut rx) = mpsc::unbounded_channel::<Action>();
+        let mut app_state = AppState::default();
+        app_state.codeblocks.replace_from_messages(&[Message::new(
+            Author::Model,
+            "```diff\n--- a/src/main.rs\n+++ b/src/main.rs\n@@ -1 +1 @@\n-fn main() {}\n+fn main() { todo!() }\n```",
+        )]);
+
+        let (_should_break, should_continue) = app_state.handle_slash_commands("/patch", &tx)?;
+
+        assert!(should_continue);
+        assert!(app_state.waiting_for_backend);
+        match rx.blocking_recv().unwrap() {
+            Action::ApplyPatches(patches) => {
+                assert_eq!(patches.len(), 1);
+                assert_eq!(patches[0].file_path, "src/main.rs");
+            }
+            _ => bail!("Wrong enum"),
+        }
+
+        return Ok(());
+    }
+
+    #[test]
+    fn it_fails_on_code_blocks_that_arent_patches() -> Result<()> {
+        let (tx, _rx) = mpsc::unbounded_channel::<Action>();
+        let mut app_state = AppState::default();
+        app_state
+            .codeblocks
+            .replace_from_messages(&[Message::new(Author::Model, codeblock_fixture())]);
+
+        app_state.handle_slash_commands("/patch 1", &tx)?;
+
+        assert!(!app_state.waiting_for_backend);
+        insta::assert_snapshot!(app_state.messages.last().unwrap().text, @r###"
+        There was an error trying to parse your command:
+
+        Code block 1 isn't a diff or SEARCH/REPLACE edit block
+        "###);
+
+        return Ok(());
+    }
+
+    #[test]
+    fn it_fails_on_patches_without_changes() -> Result<()> {
+        let (tx, _rx) = mpsc::unbounded_channel::<Action>();
+        let mut app_state = AppState::default();
+        app_state.codeblocks.replace_from_messages(&[Message::new(
+            Author::Model,
+            "```diff\n--- a/src/main.rs\n+++ b/src/main.rs\n```\n\n```rust src/main.rs\n<<<<<<< SEARCH\nfn main() {}\n=======\nfn main() {}\n>>>>>>> REPLACE\n```",
+        )]);
+
+        for command in ["/patch 1", "/patch 2"] {
+            app_state.handle_slash_commands(command, &tx)?;
+
+            assert!(!app_state.waiting_for_backend);
+            assert_eq!(
+                app_state.messages.last().unwrap().text,
+                "There was an error trying to parse your command:\n\nThe code blocks don't change anything, so there's nothing to patch"
+            );
+        }
+
+        return Ok(());
+    }
+}
+
+mod handle_pane_command {
+    use super::*;
+
+    fn app_state() -> AppState<'static> {
+        return AppState {
+            editor_name: EditorName::Tmux,
+            ..AppState::default()
+        };
+    }
+
+    #[test]
+    fn it_requires_the_tmux_editor() -> Result<()> {
+        let (tx, _rx) = mpsc::unbounded_channel::<Action>();
+        let mut app_state = AppState::default();
+
+        let (_should_break, should_continue) = app_state.handle_slash_commands("/pane", &tx)?;
+
+        assert!(should_continue);
+        assert!(!app_state.waiting_for_backend);
+        insta::assert_snapshot!(app_state.messages.last().unwrap().text, @"/pane picks which tmux pane to share, which needs the tmux editor. Start Oatmeal with `--editor tmux` from within tmux to use it.");
+
+        return Ok(());
+    }
+
+    #[test]
+    fn it_lists_panes() -> Result<()> {
+        let (tx, mut rx) = mpsc::unbounded_channel::<Action>();
+        let mut app_state = app_state();
+
+        app_state.handle_slash_commands("/pane", &tx)?;
+
+        assert!(app_state.waiting_for_backend);
+        match rx.blocking_recv().unwrap() {
+            Action::TmuxListPanes() => {}
+            _ => bail!("Wrong enum"),
+        }
+
+        return Ok(());
+    }
+
+    #[test]
+    fn it_sets_the_pane_and_captures_it() -> Result<()> {
+        let (tx, mut rx) = mpsc::unbounded_channel::<Action>();
+        let mut app_state = app_state();
+
+        app_state.handle_slash_commands("/pane %3", &tx)?;
+
+        assert!(app_state.waiting_for_backend);
+        assert_eq!(Config::get(ConfigKey::TmuxPane), "%3");
+        match rx.blocking_recv().unwrap() {
+            Action::EditorContextRefresh() => {}
+            _ => bail!("Wrong enum"),
+        }
+
+        return Ok(());
+    }
+}
+
+mod handle_backend_response {
+    use super::*;
+
+    #[test]
+    fn it_handles_new_backend_response() {
+        let mut app_state = AppState::default();
+        app_state
+            .messages
+            .push(Message::new(Author::User, "Do something for me!"));
+        let backend_response = BackendResponse {
+            author: Author::Model,
+            text: "All done!".to_string(),
+            done: true,
+            context: Some("icanrememberthingsnow".to_string()),
+        };
+        app_state.handle_backend_response(backend_response);
+
+        assert_eq!(app_state.messages.len(), 2);
+    }
+
+    #[test]
+    fn it_handles_bad_backend_response() {
+        let mut app_state = AppState::default();
+        app_state
+            .messages
+            .push(Message::new(Author::User, "Do something for me!"));
+        let backend_response = BackendResponse {
+            author: Author::Model,
+            text: "All done!".to_string(),
+            done: true,
+            context: Some("".to_string()),
+        };
+        app_state.handle_backend_response(backend_response);
+
+        assert_eq!(app_state.messages.len(), 3);
+        assert_eq!(
+            app_state.messages.last().unwrap().message_type(),
+            MessageType::Error
+        );
+    }
+
+    #[test]
+    fn it_accepts_missing_context_without_stateful_context() {
+        let mut app_state = AppState {
+            backend_capabilities: BackendCapabilities::default(),
+            ..AppState::default()
+        };
+        app_state
+            .messages
+            .push(Message::new(Author::User, "Do something for me!"));
+        let backend_response = BackendResponse {
+            author: Author::Model,
+            text: "All done!".to_string(),
+            done: true,
+            context: None,
+        };
+        app_state.handle_backend_response(backend_response);
+
+        assert_eq!(app_state.messages.len(), 2);
+        assert!(!app_state.waiting_for_backend);
+    }
+
+    #[test]
+    fn it_starts_new_bubble_after_fallback_notice() {
+        let mut app_state = AppState::default();
+        app_state
+            .messages
+            .push(Message::new(Author::User, "Do something for me!"));
+        app_state.messages.push(Message::new_with_type(
+            Author::Oatmeal,
+            MessageType::Error,
+            "model-1 on ollama failed, so model-2 on openai is answering instead.",
+        ));
+        let backend_response = BackendResponse {
+            author: Author::Model,
+            text: "All done!".to_string(),
+            done: true,
+            context: Some("icanrememberthingsnow".to_string()),
+        };
+        app_state.handle_backend_response(backend_response);
+
+        assert_eq!(app_state.messages.len(), 3);
+        assert_eq!(app_state.messages.last().unwrap().text, "All done!");
+    }
+}
+
+mod navigate {
+    use super::*;
+
+    fn app_state_with_chat() -> AppState<'static> {
+        let mut app_state = AppState {
+            messages: vec![
+                Message::new(Author::User, "Write me a script"),
+                Message::new_from_model("ollama", "model-1", codeblock_fixture()),
+                Message::new(Author::User, "/model model-2"),
+                Message::new(Author::User, "Thanks!"),
+            ],
+            ..AppState::default()
+        };
+        app_state
+            .codeblocks
+            .replace_from_messages(&app_state.messages);
+        app_state.set_rect(Rect::new(0, 0, 100, 10));
+
+        return app_state;
+    }
+
+    #[test]
+    fn it_focuses_the_last_message() {
+        let mut app_state = app_state_with_chat();
+        app_state.start_navigating();
+        assert_eq!(app_state.focused_message, Some(3));
+
+        app_state.stop_navigating();
+        assert_eq!(app_state.focused_message, None);
+    }
+
+    #[test]
+    fn it_does_nothing_without_messages() {
+        let mut app_state = AppState::default();
+        app_state.start_navigating();
+        assert_eq!(app_state.focused_message, None);
+    }
+
+    #[test]
+    fn it_moves_focus_within_messages() {
+        let mut app_state = app_state_with_chat();
+        app_state.start_navigating();
+
+        app_state.move_focus(1);
+        assert_eq!(app_state.focused_message, Some(3));
+        app_state.move_focus(-2);
+        assert_eq!(app_state.focused_message, Some(1));
+        app_state.move_focus(isize::MIN);
+        assert_eq!(app_state.focused_message, Some(0));
+        app_state.move_focus(isize::MAX);
+        assert_eq!(app_state.focused_message, Some(3));
+    }
+
+    #[test]
+    fn it_scrolls_to_the_focused_message() {
+        let mut app_state = app_state_with_chat();
+        app_state.start_navigating();
+        app_state.move_focus(isize::MIN);
+        assert_eq!(app_state.scroll.position, 0);
+
+        app_state.move_focus(1);
+        let (start, _end) = app_state.bubble_list.line_range(1).unwrap();
+        assert_eq!(app_state.scroll.position, start);
+    }
+
+    #[test]
+    fn it_copies_the_focused_message() -> Result<()> {
+        let (tx, mut rx) = mpsc::unbounded_channel::<Action>();
+        let mut app_state = app_state_with_chat();
+        app_state.start_navigating();
+        app_state.copy_focused_message(&tx)?;
+
+        assert!(app_state.waiting_for_backend);
+        match rx.blocking_recv().unwrap() {
+            Action::CopyMessages(messages) => {
+                assert_eq!(messages.len(), 1);
+                assert_eq!(messages[0].text, "Thanks!");
+            }
+            _ => bail!("Wrong enum"),
+        }
+
+        return Ok(());
+    }
+
+    #[test]
+    fn it_copies_a_codeblock_of_the_focused_message() -> Result<()> {
+        let (tx, mut rx) = mpsc::unbounded_channel::<Action>();
+        let mut app_state = app_state_with_chat();
+        app_state.start_navigating();
+        app_state.move_focus(-2);
+        app_state.copy_focused_codeblock(1, &tx)?;
+
+        match rx.blocking_recv().unwrap() {
+            Action::CopyMessages(messages) => {
+                assert_eq!(messages[0].text, app_state.messages[1].codeblocks()[0]);
+            }
+            _ => bail!("Wrong enum"),
+        }
+
+        return Ok(());
+    }
+
+    #[test]
+    fn it_reports_missing_codeblocks() -> Result<()> {
+        let (tx, mut rx) = mpsc::unbounded_channel::<Action>();
+        let mut app_state = app_state_with_chat();
+        app_state.start_navigating();
+        app_state.copy_focused_codeblock(9, &tx)?;
+
+        assert!(rx.try_recv().is_err());
+        let message = app_state.messages.last().unwrap();
+        assert_eq!(message.message_type(), MessageType::Error);
+        assert_eq!(
+            message.text,
+            "The selected message doesn't have a code block 9."
+        );
+
+        return Ok(());
+    }
+
+    #[test]
+    fn it_does_not_copy_while_waiting() -> Result<()> {
+        let (tx, mut rx) = mpsc::unbounded_channel::<Action>();
+        let mut app_state = app_state_with_chat();
+        app_state.waiting_for_backend = true;
+        app_state.start_navigating();
+        app_state.copy_focused_message(&tx)?;
+
+        assert!(rx.try_recv().is_err());
+        assert_eq!(app_state.messages.len(), 4);
+        insta::assert_snapshot!(app_state.navigate_notice.as_ref().unwrap(), @"Wait for the response to finish first, or interrupt it with CTRL+C");
+
+        app_state.stop_navigating();
+        assert_eq!(app_state.navigate_notice, None);
+
+        return Ok(());
+    }
+
+    #[test]
+    fn it_reasks_a_prompt() {
+        let mut app_state = app_state_with_chat();
+        app_state.start_navigating();
+
+        assert_eq!(
+            app_state.reask_focused_message(),
+            Some("Thanks!".to_string())
+        );
+        assert_eq!(app_state.focused_message, None);
+    }
+
+    #[test]
+    fn it_reasks_the_prompt_a_response_answered() {
+        let mut app_state = app_state_with_chat();
+        app_state.start_navigating();
+        app_state.move_focus(-1);
+
+        assert_eq!(
+            app_state.reask_focused_message(),
+            Some("Write me a script".to_string())
+        );
+    }
+
+    #[test]
+    fn it_deletes_the_focused_message() -> Result<()> {
+        let mut app_state = app_state_with_chat();
+        let copy_command = SlashCommand::parse("/copy 1").unwrap();
+        assert!(!app_state
+            .codeblocks
+            .blocks_from_slash_commands(&copy_command)?
+            .is_empty());
+
+        app_state.start_navigating();
+        app_state.move_focus(-2);
+        app_state.delete_focused_message();
+
+        assert_eq!(app_state.messages.len(), 3);
+        assert_eq!(app_state.messages[1].text, "/model model-2");
+        assert_eq!(app_state.focused_message, Some(1));
+        assert_eq!(
+            app_state
+                .codeblocks
+                .blocks_from_slash_commands(&copy_command)?,
+            ""
+        );
+
+        return Ok(());
+    }
+
+    #[test]
+    fn it_stops_navigating_after_deleting_the_last_message() {
+        let mut app_state = AppState {
+            messages: vec![Message::new(Author::User, "Hello")],
+            ..AppState::default()
+        };
+        app_state.set_rect(Rect::new(0, 0, 100, 10));
+        app_state.start_navigating();
+        app_state.delete_focused_message();
+
+        assert!(app_state.messages.is_empty());
+        assert_eq!(app_state.focused_message, None);
+        assert_eq!(app_state.bubble_list.len(), 0);
+    }
+
+    #[test]
+    fn it_does_not_delete_a_response_while_it_streams() {
+        let mut app_state = app_state_with_chat();
+        app_state.waiting_for_backend = true;
+        app_state.start_navigating();
+        app_state.delete_focused_message();
+
+        assert_eq!(app_state.messages.len(), 4);
+        assert_eq!(app_state.messages[3].text, "Thanks!");
+        insta::assert_snapshot!(app_state.navigate_notice.as_ref().unwrap(), @"The response is still streaming in. Interrupt it with CTRL+C before deleting it");
     }
 }
 
@@ -268,6 +1326,7 @@ mod init {
             theme_file: "".to_string(),
             session_id: None,
             sessions_service: Sessions::new(sessions_dir.clone()),
+            model_cache: ModelCache::new(sessions_dir.join("models.yaml")),
         })
         .await?;
         app_state.save_session().await?;
@@ -284,6 +1343,7 @@ mod init {
             theme_file: "".to_string(),
             session_id: Some(session_id.to_string()),
             sessions_service: Sessions::new(sessions_dir.clone()),
+            model_cache: ModelCache::new(sessions_dir.join("models.yaml")),
         })
         .await?;
         Sessions::new(sessions_dir).delete(&session_id).await?;
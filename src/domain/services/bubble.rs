@@ -3,14 +3,15 @@
 mod tests;
 
 use ratatui::style::Color;
+use ratatui::style::Modifier;
 use ratatui::style::Style;
 use ratatui::text::Line;
 use ratatui::text::Span;
-use syntect::easy::HighlightLines;
 use syntect::highlighting::Theme;
 
-use super::Syntaxes;
-use super::SYNTAX_SET;
+use super::HighlightCache;
+use super::MarkdownRenderer;
+use super::TextWidth;
 use crate::domain::models::Author;
 use crate::domain::models::Message;
 use crate::domain::models::MessageType;
@@ -26,6 +27,7 @@ pub struct Bubble<'a> {
     message: &'a Message,
     window_max_width: usize,
     codeblock_counter: usize,
+    focused: bool,
 }
 
 pub struct BubbleConfig {
@@ -64,9 +66,17 @@ impl<'a> Bubble<'_> {
             message,
             window_max_width,
             codeblock_counter,
+            focused: false,
         };
     }
 
+    /// Highlights the bubble's border, marking the message picked in
+    /// navigate mode.
+    pub fn focused(mut self, focused: bool) -> Self {
+        self.focused = focused;
+        return self;
+    }
+
     pub fn style_config() -> BubbleConfig {
         return BubbleConfig {
             // Unicode character border + padding.
@@ -78,75 +88,36 @@ impl<'a> Bubble<'_> {
         };
     }
 
-    pub fn as_lines(&mut self, theme: &Theme) -> Vec<Line<'a>> {
-        // Lazy default
-        let mut highlight = HighlightLines::new(Syntaxes::get("text"), theme);
-        let mut in_codeblock = false;
-        let mut lines: Vec<Line> = vec![];
-
-        let max_line_length = self.get_max_line_length();
-
-        for line in self.message.text.lines() {
-            let mut spans = vec![];
-
-            if line.trim().starts_with("```") {
-                let lang = line.trim().replace("```", "");
-                let syntax = Syntaxes::get(&lang);
-                if !in_codeblock {
-                    highlight = HighlightLines::new(syntax, theme);
-                    in_codeblock = true;
-
-                    self.codeblock_counter += 1;
-                    spans = vec![
-                        Span::from(line.to_owned()),
-                        Span::styled(
-                            format!(" ({})", self.codeblock_counter),
-                            Style {
-                                fg: Some(Color::White),
-                                ..Style::default()
-                            },
-                        ),
-                    ];
-                } else {
-                    in_codeblock = false;
-                }
-            } else if in_codeblock {
-                // Highlighting doesn't work accurately unless each line is postfixed with '\n',
-                // especially when dealing with multi-line code comments.
-                let line_nl = format!("{line}\n");
-                let highlighted = highlight.highlight_line(&line_nl, &SYNTAX_SET).unwrap();
-
-                spans = highlighted
-                    .iter()
-                    .enumerate()
-                    .map(|(idx, segment)| {
-                        let (style, content) = segment;
-                        let mut text = content.to_string();
-                        if idx == highlighted.len() - 1 {
-                            text = text.trim_end().to_string();
-                        }
+    pub fn as_lines(&self, theme: &Theme) -> Vec<Line<'a>> {
+        let rendered = MarkdownRenderer::new(theme, self.codeblock_counter).render(&self.text());
+        return self.wrap_rendered(rendered);
+    }
 
-                        return Span::styled(
-                            text,
-                            Style {
-                                fg: Syntaxes::translate_colour(style.foreground),
-                                ..Style::default()
-                            },
-                        );
-                    })
-                    .collect();
-            }
+    /// Like `as_lines`, but only highlights lines of code that have changed
+    /// since the last call with the same cache. Used for the message being
+    /// streamed in, which is rendered again on every chunk.
+    pub fn as_lines_with_cache(
+        &self,
+        theme: &Theme,
+        highlight_cache: &mut HighlightCache,
+    ) -> Vec<Line<'a>> {
+        let rendered = MarkdownRenderer::new(theme, self.codeblock_counter)
+            .with_highlight_cache(highlight_cache)
+            .render(&self.text());
+        return self.wrap_rendered(rendered);
+    }
 
-            if spans.is_empty() {
-                spans = vec![Span::styled(line.to_owned(), Style::default())];
-            }
+    fn wrap_rendered(&self, rendered: Vec<Vec<Span<'static>>>) -> Vec<Line<'a>> {
+        let max_line_length = self.get_max_line_length(&rendered);
+        let mut lines: Vec<Line> = vec![];
 
+        for spans in rendered {
             let mut split_spans = vec![];
-            let mut line_char_count = 0;
+            let mut line_width = 0;
 
             for span in spans {
-                if span.content.len() + line_char_count <= max_line_length {
-                    line_char_count += span.content.len();
+                if TextWidth::of(&span.content) + line_width <= max_line_length {
+                    line_width += TextWidth::of(&span.content);
                     split_spans.push(span);
                     continue;
                 }
@@ -154,17 +125,27 @@ impl<'a> Bubble<'_> {
                 let mut word_set: Vec<&str> = vec![];
 
                 for word in span.content.split(' ') {
-                    if word.len() + line_char_count > max_line_length {
+                    let mut word = word;
+
+                    while TextWidth::of(word) + line_width > max_line_length {
+                        // Words wider than the bubble, such as long URLs or CJK text without
+                        // spaces, are broken up wherever they reach its edge.
+                        if line_width == 0 {
+                            let (head, tail) = TextWidth::split_at(word, max_line_length);
+                            word_set.push(head);
+                            word = tail;
+                        }
+
                         split_spans.push(Span::styled(word_set.join(" "), span.style));
                         lines.push(self.spans_to_line(split_spans, max_line_length));
 
                         split_spans = vec![];
                         word_set = vec![];
-                        line_char_count = 0;
+                        line_width = 0;
                     }
 
                     word_set.push(word);
-                    line_char_count += word.len() + 1;
+                    line_width += TextWidth::of(word) + 1;
                 }
 
                 split_spans.push(Span::styled(word_set.join(" "), span.style));
@@ -177,7 +158,7 @@ impl<'a> Bubble<'_> {
     }
 
     fn spans_to_line(&self, mut spans: Vec<Span<'a>>, max_line_length: usize) -> Line<'a> {
-        let line_str_len: usize = spans.iter().map(|e| return e.content.len()).sum();
+        let line_str_len: usize = spans.iter().map(|e| return TextWidth::of(&e.content)).sum();
         let fill = repeat_from_subtractions(" ", vec![max_line_length, line_str_len]);
         let formatted_line_length =
             line_str_len + fill.len() + Bubble::style_config().bubble_padding;
@@ -200,7 +181,7 @@ impl<'a> Bubble<'_> {
         return Line::from(line_spans);
     }
 
-    fn get_max_line_length(&self) -> usize {
+    fn get_max_line_length(&self, rendered: &[Vec<Span>]) -> usize {
         let style_config = Bubble::style_config();
         // Add a minimum 4% of padding on the side.
         let min_bubble_padding_length = ((self.window_max_width as f32
@@ -210,28 +191,55 @@ impl<'a> Bubble<'_> {
         // Border elements + minimum bubble padding.
         let line_border_width = style_config.border_elements_length + min_bubble_padding_length;
 
-        let mut max_line_length = self
-            .message
-            .text
-            .lines()
-            .map(|line| {
-                return line.len();
+        let mut max_line_length = rendered
+            .iter()
+            .map(|spans| {
+                return spans
+                    .iter()
+                    .map(|span| return TextWidth::of(&span.content))
+                    .sum();
             })
             .max()
-            .unwrap();
+            .unwrap_or_default();
 
         if max_line_length > (self.window_max_width - line_border_width) {
             max_line_length = self.window_max_width - line_border_width;
         }
 
-        let username = &self.message.author.to_string();
-        if max_line_length < username.len() {
-            max_line_length = username.len();
+        let username = &self.message.author_name();
+        if max_line_length < TextWidth::of(username) {
+            max_line_length = TextWidth::of(username);
         }
 
         return max_line_length;
     }
 
+    /// Message text with a marker appended when the response was cut short,
+    /// or only its first line when collapsed.
+    fn text(&self) -> String {
+        if self.message.is_collapsed() {
+            let mut lines = self.message.text.lines();
+            let first_line = lines.next().unwrap_or_default();
+            let hidden = lines.count();
+            if hidden == 0 {
+                return first_line.to_string();
+            }
+
+            return format!("{first_line} [+{hidden} lines]");
+        }
+
+        if !self.message.is_interrupted() {
+            return self.message.text.to_string();
+        }
+
+        let text = self.message.text.trim_end();
+        if text.is_empty() {
+            return "[Response interrupted]".to_string();
+        }
+
+        return format!("{text}\n\n[Response interrupted]");
+    }
+
     fn wrap_lines_in_buddle(&self, lines: Vec<Line<'a>>, max_line_length: usize) -> Vec<Line<'a>> {
         // Add 2 for the vertical bars.
         let inner_bar = ["─"].repeat(max_line_length + 2).join("");
@@ -247,10 +255,10 @@ impl<'a> Bubble<'_> {
             ],
         );
 
-        let username = &self.message.author.to_string();
+        let username = &self.message.author_name();
 
         if self.alignment == BubbleAlignment::Left {
-            let top_replace = ["─"].repeat(username.len()).join("");
+            let top_replace = ["─"].repeat(TextWidth::of(username)).join("");
             top_bar = top_bar.replace(
                 format!("{top_left_border}{top_replace}").as_str(),
                 format!("{top_left_border}{username}").as_str(),
@@ -261,7 +269,7 @@ impl<'a> Bubble<'_> {
             res.push(self.highlight_line(format!("{bottom_bar}{bar_bubble_padding}")));
             return res;
         } else {
-            let top_replace = ["─"].repeat(username.len()).join("");
+            let top_replace = ["─"].repeat(TextWidth::of(username)).join("");
             top_bar = top_bar.replace(
                 format!("{top_left_border}{top_replace}").as_str(),
                 format!("{top_left_border}{username}").as_str(),
@@ -275,7 +283,16 @@ impl<'a> Bubble<'_> {
     }
 
     fn highlight_span(&self, text: String) -> Span<'a> {
-        if self.message.message_type() == MessageType::Error {
+        if self.focused {
+            return Span::styled(
+                text,
+                Style {
+                    fg: Some(Color::Cyan),
+                    add_modifier: Modifier::BOLD,
+                    ..Style::default()
+                },
+            );
+        } else if self.message.message_type() == MessageType::Error {
             return Span::styled(
                 text,
                 Style {
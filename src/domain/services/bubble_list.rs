@@ -1,5 +1,3 @@
-use std::collections::HashMap;
-
 use ratatui::prelude::Buffer;
 use ratatui::prelude::Rect;
 use ratatui::text::Line;
@@ -7,6 +5,7 @@ use syntect::highlighting::Theme;
 
 use super::Bubble;
 use super::BubbleAlignment;
+use super::HighlightCache;
 use crate::domain::models::Author;
 use crate::domain::models::Message;
 
@@ -16,102 +15,212 @@ mod tests;
 
 struct BubbleCacheEntry<'a> {
     codeblocks_count: usize,
+    /// Number of code blocks in the messages before this one, which the
+    /// bubble's code block labels count on from.
+    codeblocks_start: usize,
+    collapsed: bool,
+    focused: bool,
+    interrupted: bool,
+    /// Line in the whole list this bubble starts on.
+    line_start: usize,
     text_len: usize,
     lines: Vec<Line<'a>>,
 }
 
+impl BubbleCacheEntry<'_> {
+    fn line_end(&self) -> usize {
+        return self.line_start + self.lines.len();
+    }
+}
+
+/// Rendered chat bubbles, one per message in order, with the line each one
+/// starts on. Only bubbles whose message changed get rendered again, and
+/// drawing a frame only touches the bubbles in view, so long sessions stay
+/// as quick as short ones.
 pub struct BubbleList<'a> {
-    cache: HashMap<usize, BubbleCacheEntry<'a>>,
+    cache: Vec<BubbleCacheEntry<'a>>,
+    /// Index of the message picked in navigate mode.
+    focused: Option<usize>,
+    /// Highlighted code of the last message, which is the one streamed in.
+    highlight_cache: HighlightCache,
+    /// Index of the message `highlight_cache` holds code for.
+    highlight_cache_idx: Option<usize>,
     line_width: usize,
-    lines_len: usize,
+    /// Number of bubbles rendered so far, so tests can check that only the
+    /// ones that changed are.
+    #[cfg(test)]
+    renders: usize,
     theme: Theme,
 }
 
 impl<'a> BubbleList<'a> {
     pub fn new(theme: Theme) -> BubbleList<'a> {
         return BubbleList {
-            cache: HashMap::new(),
+            cache: vec![],
+            focused: None,
+            highlight_cache: HighlightCache::default(),
+            highlight_cache_idx: None,
             line_width: 0,
-            lines_len: 0,
+            #[cfg(test)]
+            renders: 0,
             theme,
         };
     }
 
+    /// Checks every message for changes, such as collapsing, rendering only
+    /// those that did.
     pub fn set_messages(&mut self, messages: &[Message], line_width: usize) {
         if self.line_width != line_width {
             self.cache.clear();
+            self.highlight_cache.clear();
+            self.highlight_cache_idx = None;
             self.line_width = line_width;
         }
 
-        let mut total_codeblock_counter = 0;
-        self.lines_len = messages
-            .iter()
-            .enumerate()
-            .map(|(idx, message)| {
-                if self.cache.contains_key(&idx) {
-                    let cache_entry = self.cache.get(&idx).unwrap();
-                    if idx < (messages.len() - 1) || message.text.len() == cache_entry.text_len {
-                        total_codeblock_counter += cache_entry.codeblocks_count;
-                        return cache_entry.lines.len();
-                    }
-                }
+        self.sync_from(messages, 0);
+    }
 
-                let mut align = BubbleAlignment::Left;
-                if message.author == Author::User {
-                    align = BubbleAlignment::Right;
-                }
+    /// Only checks the last message, along with any added since the last
+    /// sync. Used while a response streams in, where nothing else changes.
+    pub fn set_last_message(&mut self, messages: &[Message], line_width: usize) {
+        if self.line_width != line_width {
+            self.set_messages(messages, line_width);
+            return;
+        }
 
-                let bubble_lines = Bubble::new(message, align, line_width, total_codeblock_counter)
-                    .as_lines(&self.theme);
-                let bubble_line_len = bubble_lines.len();
-
-                let codeblocks_count = message.codeblocks().len();
-                total_codeblock_counter += codeblocks_count;
-
-                self.cache.insert(
-                    idx,
-                    BubbleCacheEntry {
-                        codeblocks_count,
-                        text_len: message.text.len(),
-                        lines: bubble_lines,
-                    },
-                );
-
-                return bubble_line_len;
-            })
-            .sum();
+        let start = self.cache.len().min(messages.len().saturating_sub(1));
+        self.sync_from(messages, start);
     }
 
-    pub fn len(&self) -> usize {
-        return self.lines_len;
+    /// Highlights the bubble of the message at `focused`, if any.
+    pub fn set_focused(&mut self, messages: &[Message], focused: Option<usize>) {
+        self.focused = focused;
+        self.sync_from(messages, 0);
     }
 
-    pub fn render(&self, rect: Rect, buf: &mut Buffer, scroll_index: u16) {
-        let mut cache_keys: Vec<usize> = self.cache.keys().cloned().collect();
-        cache_keys.sort();
+    /// Forgets the bubble of a message that's been removed, so the bubbles
+    /// after it are matched up with their messages again.
+    pub fn remove(&mut self, idx: usize) {
+        if idx < self.cache.len() {
+            self.cache.remove(idx);
+        }
 
-        let mut line_idx = 0;
-        let mut should_break = false;
+        self.highlight_cache.clear();
+        self.highlight_cache_idx = None;
+    }
+
+    /// Lines the bubble of the message at `idx` starts and ends on.
+    pub fn line_range(&self, idx: usize) -> Option<(usize, usize)> {
+        return self
+            .cache
+            .get(idx)
+            .map(|entry| return (entry.line_start, entry.line_end()));
+    }
 
-        for cache_key in cache_keys {
-            for line in self.cache.get(&cache_key).unwrap().lines.as_slice() {
-                if line_idx < scroll_index {
-                    line_idx += 1;
+    fn sync_from(&mut self, messages: &[Message], start: usize) {
+        self.cache.truncate(messages.len());
+
+        let mut codeblocks_start = 0;
+        let mut line_start = 0;
+        if let Some(entry) = start
+            .checked_sub(1)
+            .and_then(|idx| return self.cache.get(idx))
+        {
+            codeblocks_start = entry.codeblocks_start + entry.codeblocks_count;
+            line_start = entry.line_end();
+        }
+
+        for (idx, message) in messages.iter().enumerate().skip(start) {
+            if let Some(entry) = self.cache.get_mut(idx) {
+                let unchanged = entry.codeblocks_start == codeblocks_start
+                    && entry.collapsed == message.is_collapsed()
+                    && entry.focused == (self.focused == Some(idx))
+                    && entry.interrupted == message.is_interrupted()
+                    && entry.text_len == message.text.len();
+                if unchanged {
+                    entry.line_start = line_start;
+                    codeblocks_start += entry.codeblocks_count;
+                    line_start = entry.line_end();
                     continue;
                 }
+            }
 
-                if (line_idx - scroll_index) >= rect.height {
-                    should_break = true;
-                    break;
-                }
+            let mut align = BubbleAlignment::Left;
+            if message.author == Author::User {
+                align = BubbleAlignment::Right;
+            }
 
-                buf.set_line(0, line_idx - scroll_index, line, rect.width);
-                line_idx += 1;
+            #[cfg(test)]
+            {
+                self.renders += 1;
+            }
+
+            let focused = self.focused == Some(idx);
+            let bubble =
+                Bubble::new(message, align, self.line_width, codeblocks_start).focused(focused);
+            let lines = if idx == messages.len() - 1 {
+                if self.highlight_cache_idx != Some(idx) {
+                    self.highlight_cache.clear();
+                    self.highlight_cache_idx = Some(idx);
+                }
+                bubble.as_lines_with_cache(&self.theme, &mut self.highlight_cache)
+            } else {
+                bubble.as_lines(&self.theme)
+            };
+            let entry = BubbleCacheEntry {
+                codeblocks_count: message.codeblocks().len(),
+                codeblocks_start,
+                collapsed: message.is_collapsed(),
+                focused,
+                interrupted: message.is_interrupted(),
+                line_start,
+                text_len: message.text.len(),
+                lines,
+            };
+
+            codeblocks_start += entry.codeblocks_count;
+            line_start = entry.line_end();
+            if idx < self.cache.len() {
+                self.cache[idx] = entry;
+            } else {
+                self.cache.push(entry);
             }
+        }
+    }
+
+    pub fn theme(&self) -> &Theme {
+        return &self.theme;
+    }
 
-            if should_break {
+    pub fn len(&self) -> usize {
+        return self
+            .cache
+            .last()
+            .map(|entry| return entry.line_end())
+            .unwrap_or_default();
+    }
+
+    pub fn render(&self, rect: Rect, buf: &mut Buffer, scroll_index: u16) {
+        let scroll_index = usize::from(scroll_index);
+        let height = usize::from(rect.height);
+        let first = self
+            .cache
+            .partition_point(|entry| return entry.line_end() <= scroll_index);
+
+        for entry in &self.cache[first..] {
+            if entry.line_start >= scroll_index + height {
                 break;
             }
+
+            let skip = scroll_index.saturating_sub(entry.line_start);
+            for (line_idx, line) in entry.lines.iter().enumerate().skip(skip) {
+                let y = entry.line_start + line_idx - scroll_index;
+                if y >= height {
+                    break;
+                }
+
+                buf.set_line(0, y as u16, line, rect.width);
+            }
         }
     }
 }
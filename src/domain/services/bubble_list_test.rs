@@ -1,4 +1,6 @@
 use anyhow::Result;
+use ratatui::prelude::Buffer;
+use ratatui::prelude::Rect;
 use test_utils::codeblock_fixture;
 
 use super::BubbleList;
@@ -6,6 +8,34 @@ use crate::domain::models::Author;
 use crate::domain::models::Message;
 use crate::domain::services::Themes;
 
+fn render_lines(bubble_list: &BubbleList, height: u16, scroll_index: u16) -> Vec<String> {
+    let rect = Rect::new(0, 0, 50, height);
+    let mut buf = Buffer::empty(rect);
+    bubble_list.render(rect, &mut buf, scroll_index);
+
+    return buf
+        .content
+        .chunks(rect.width.into())
+        .map(|cells| {
+            return cells
+                .iter()
+                .map(|cell| return cell.symbol.to_string())
+                .collect::<String>();
+        })
+        .collect();
+}
+
+fn session(len: usize) -> Vec<Message> {
+    return (0..len)
+        .map(|idx| {
+            if idx % 2 == 0 {
+                return Message::new(Author::User, "Can you write that in Rust?");
+            }
+            return Message::new_from_model("ollama", "model-1", codeblock_fixture());
+        })
+        .collect();
+}
+
 #[test]
 fn it_has_no_cached_lines() -> Result<()> {
     let theme = Themes::get("base16-seti", "")?;
@@ -44,3 +74,213 @@ fn it_returns_correct_length() -> Result<()> {
     assert_eq!(bubble_list.len(), 50);
     return Ok(());
 }
+
+#[test]
+fn it_rerenders_when_collapsed_changes() -> Result<()> {
+    let theme = Themes::get("base16-seti", "")?;
+    let mut messages = vec![
+        Message::new(Author::Oatmeal, codeblock_fixture()),
+        Message::new(Author::Oatmeal, "Hi there!"),
+    ];
+
+    let mut bubble_list = BubbleList::new(theme);
+    bubble_list.set_messages(&messages, 50);
+    let expanded_len = bubble_list.len();
+
+    messages[0].set_collapsed(true);
+    bubble_list.set_messages(&messages, 50);
+
+    assert_eq!(bubble_list.len(), expanded_len - 44);
+    return Ok(());
+}
+
+#[test]
+fn it_renders_the_visible_window() -> Result<()> {
+    let theme = Themes::get("base16-seti", "")?;
+    let messages = vec![
+        Message::new(Author::Oatmeal, "Hi there!"),
+        Message::new(Author::Oatmeal, codeblock_fixture()),
+        Message::new(Author::User, "Thanks!"),
+    ];
+
+    let mut bubble_list = BubbleList::new(theme);
+    bubble_list.set_messages(&messages, 50);
+    let all_lines = render_lines(&bubble_list, bubble_list.len() as u16, 0);
+
+    assert_eq!(render_lines(&bubble_list, 5, 0), all_lines[0..5]);
+    assert_eq!(render_lines(&bubble_list, 5, 2), all_lines[2..7]);
+    assert_eq!(
+        render_lines(&bubble_list, 5, 48),
+        all_lines[48..53],
+        "spans the end of one bubble and the start of the next"
+    );
+    return Ok(());
+}
+
+#[test]
+fn it_leaves_lines_past_the_end_empty() -> Result<()> {
+    let theme = Themes::get("base16-seti", "")?;
+    let messages = vec![Message::new(Author::Oatmeal, "Hi there!")];
+
+    let mut bubble_list = BubbleList::new(theme);
+    bubble_list.set_messages(&messages, 50);
+    let lines = render_lines(&bubble_list, 5, 0);
+
+    assert!(lines[1].contains("Hi there!"));
+    assert_eq!(lines[3].trim(), "");
+    return Ok(());
+}
+
+#[test]
+fn it_rerenders_the_last_message_as_it_streams() -> Result<()> {
+    let theme = Themes::get("base16-seti", "")?;
+    let mut messages = vec![
+        Message::new(Author::User, "Hello"),
+        Message::new_from_model("ollama", "model-1", "Hi"),
+    ];
+
+    let mut bubble_list = BubbleList::new(theme);
+    bubble_list.set_messages(&messages, 50);
+    assert_eq!(bubble_list.len(), 6);
+
+    messages[1].append(" there!\n\nHow are you?");
+    bubble_list.set_last_message(&messages, 50);
+    assert_eq!(bubble_list.len(), 8);
+
+    messages.push(Message::new(Author::User, "Good!"));
+    bubble_list.set_last_message(&messages, 50);
+    assert_eq!(bubble_list.cache.len(), 3);
+    assert_eq!(bubble_list.len(), 11);
+    return Ok(());
+}
+
+#[test]
+fn it_rerenders_when_interrupted() -> Result<()> {
+    let theme = Themes::get("base16-seti", "")?;
+    let mut messages = vec![Message::new_from_model("ollama", "model-1", "Hi")];
+
+    let mut bubble_list = BubbleList::new(theme);
+    bubble_list.set_messages(&messages, 50);
+    assert_eq!(bubble_list.len(), 3);
+
+    messages[0].set_interrupted();
+    bubble_list.set_last_message(&messages, 50);
+    assert_eq!(bubble_list.len(), 5);
+    return Ok(());
+}
+
+#[test]
+fn it_renumbers_codeblocks_when_earlier_messages_change() -> Result<()> {
+    let theme = Themes::get("base16-seti", "")?;
+    let mut messages = vec![
+        Message::new(Author::User, "Hello"),
+        Message::new_from_model("ollama", "model-1", "```sh\nls\n```"),
+    ];
+
+    let mut bubble_list = BubbleList::new(theme);
+    bubble_list.set_messages(&messages, 50);
+    let lines = render_lines(&bubble_list, bubble_list.len() as u16, 0);
+    assert!(lines.iter().any(|line| return line.contains("```sh (1)")));
+
+    messages[0].append("\n\n```sh\npwd\n```");
+    bubble_list.set_messages(&messages, 50);
+    let lines = render_lines(&bubble_list, bubble_list.len() as u16, 0);
+    assert!(lines.iter().any(|line| return line.contains("```sh (1)")));
+    assert!(lines.iter().any(|line| return line.contains("```sh (2)")));
+    return Ok(());
+}
+
+#[test]
+fn it_only_renders_the_streamed_bubble_regardless_of_session_length() -> Result<()> {
+    let theme = Themes::get("base16-seti", "")?;
+    let chunks = 20;
+
+    for session_len in [10, 500] {
+        let mut messages = session(session_len);
+        messages.push(Message::new_from_model("ollama", "model-1", ""));
+
+        let mut bubble_list = BubbleList::new(theme.clone());
+        bubble_list.set_messages(&messages, 50);
+        assert_eq!(bubble_list.renders, session_len + 1);
+
+        for _ in 0..chunks {
+            messages.last_mut().unwrap().append("streamed ");
+            bubble_list.set_last_message(&messages, 50);
+        }
+        assert_eq!(bubble_list.renders, session_len + 1 + chunks);
+
+        // Nothing changed, so checking every message renders nothing.
+        bubble_list.set_messages(&messages, 50);
+        assert_eq!(bubble_list.renders, session_len + 1 + chunks);
+    }
+
+    return Ok(());
+}
+
+#[test]
+fn it_highlights_streamed_code_the_same_as_all_at_once() -> Result<()> {
+    let theme = Themes::get("base16-seti", "")?;
+    let text = codeblock_fixture();
+    let expected = vec![Message::new_from_model("ollama", "model-1", text)];
+    let mut expected_list = BubbleList::new(theme.clone());
+    expected_list.set_messages(&expected, 50);
+
+    let mut messages = vec![Message::new_from_model("ollama", "model-1", "")];
+    let mut bubble_list = BubbleList::new(theme);
+    for chunk in text.split_inclusive(' ') {
+        messages[0].append(chunk);
+        bubble_list.set_last_message(&messages, 50);
+    }
+
+    assert_eq!(
+        render_lines(&bubble_list, bubble_list.len() as u16, 0),
+        render_lines(&expected_list, expected_list.len() as u16, 0)
+    );
+    return Ok(());
+}
+
+#[test]
+fn it_focuses_a_bubble() -> Result<()> {
+    let theme = Themes::get("base16-seti", "")?;
+    let messages = vec![
+        Message::new(Author::Oatmeal, "Hi there!"),
+        Message::new(Author::User, "Hello"),
+    ];
+
+    let mut bubble_list = BubbleList::new(theme);
+    bubble_list.set_messages(&messages, 50);
+    bubble_list.set_focused(&messages, Some(1));
+
+    assert!(!bubble_list.cache[0].focused);
+    assert!(bubble_list.cache[1].focused);
+    assert_eq!(bubble_list.line_range(0), Some((0, 3)));
+    assert_eq!(bubble_list.line_range(1), Some((3, 6)));
+    assert_eq!(bubble_list.line_range(2), None);
+
+    bubble_list.set_focused(&messages, None);
+    assert!(!bubble_list.cache[1].focused);
+    return Ok(());
+}
+
+#[test]
+fn it_removes_a_bubble() -> Result<()> {
+    let theme = Themes::get("base16-seti", "")?;
+    let mut messages = vec![
+        Message::new(Author::Oatmeal, "One"),
+        Message::new(Author::Oatmeal, "Two"),
+        Message::new(Author::Oatmeal, "Six"),
+    ];
+
+    let mut bubble_list = BubbleList::new(theme);
+    bubble_list.set_messages(&messages, 50);
+
+    messages.remove(1);
+    bubble_list.remove(1);
+    bubble_list.set_messages(&messages, 50);
+
+    let lines = render_lines(&bubble_list, bubble_list.len() as u16, 0);
+    assert_eq!(lines.len(), 6);
+    assert!(lines[1].contains("One"));
+    assert!(lines[4].contains("Six"));
+    return Ok(());
+}
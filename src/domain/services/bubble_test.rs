@@ -1,4 +1,5 @@
 use anyhow::Result;
+use ratatui::style::Color;
 use test_utils::codeblock_fixture;
 use test_utils::insta_snapshot;
 
@@ -7,6 +8,7 @@ use super::BubbleAlignment;
 use crate::configuration::Config;
 use crate::configuration::ConfigKey;
 use crate::domain::models::Author;
+use crate::domain::models::EditorContext;
 use crate::domain::models::Message;
 use crate::domain::services::Themes;
 
@@ -15,11 +17,22 @@ fn create_lines(
     alignment: BubbleAlignment,
     codeblock_count: usize,
     text: &str,
+) -> Result<String> {
+    let mut message = Message::new(author.clone(), text);
+    if author == Author::Model {
+        message = Message::new_from_model("ollama", "model-1", text);
+    }
+
+    return message_to_lines(message, alignment, codeblock_count);
+}
+
+fn message_to_lines(
+    message: Message,
+    alignment: BubbleAlignment,
+    codeblock_count: usize,
 ) -> Result<String> {
     Config::set(ConfigKey::Username, "testuser");
-    Config::set(ConfigKey::Model, "model-1");
 
-    let message = Message::new(author, text);
     let theme = Themes::get("base16-seti", "")?;
     let lines = Bubble::new(&message, alignment, 50, codeblock_count).as_lines(&theme);
     let lines_str = lines
@@ -121,6 +134,22 @@ fn it_creates_author_model_text() -> Result<()> {
     return Ok(());
 }
 
+#[test]
+fn it_creates_author_model_text_without_model() -> Result<()> {
+    let lines_str = message_to_lines(
+        Message::new(Author::Model, "Hi there!"),
+        BubbleAlignment::Left,
+        0,
+    )?;
+    insta::assert_snapshot!(lines_str, @r###"
+    ╭Model──────╮                                 
+    │ Hi there! │                                 
+    ╰───────────╯                                 
+    "###);
+
+    return Ok(());
+}
+
 #[test]
 fn it_creates_author_user_text() -> Result<()> {
     let lines_str = create_lines(Author::User, BubbleAlignment::Right, 0, "Hi there!")?;
@@ -130,3 +159,190 @@ fn it_creates_author_user_text() -> Result<()> {
 
     return Ok(());
 }
+
+#[test]
+fn it_creates_author_model_text_interrupted() -> Result<()> {
+    let mut message = Message::new_from_model("ollama", "model-1", "Hi there!");
+    message.set_interrupted();
+    let lines_str = message_to_lines(message, BubbleAlignment::Left, 0)?;
+    insta::assert_snapshot!(lines_str, @r###"
+    ╭model-1─────────────────╮                    
+    │ Hi there!              │                    
+    │                        │                    
+    │ [Response interrupted] │                    
+    ╰────────────────────────╯                    
+    "###);
+
+    return Ok(());
+}
+
+#[test]
+fn it_creates_author_model_empty_text_interrupted() -> Result<()> {
+    let mut message = Message::new_from_model("ollama", "model-1", "");
+    message.set_interrupted();
+    let lines_str = message_to_lines(message, BubbleAlignment::Left, 0)?;
+    insta::assert_snapshot!(lines_str, @r###"
+    ╭model-1─────────────────╮                    
+    │ [Response interrupted] │                    
+    ╰────────────────────────╯                    
+    "###);
+
+    return Ok(());
+}
+
+#[test]
+fn it_creates_collapsed_context() -> Result<()> {
+    let context = EditorContext {
+        file_path: "file.rs".to_string(),
+        language: "rust".to_string(),
+        code: "let x = 5;".to_string(),
+        start_line: 0,
+        end_line: Some(1),
+    };
+    let message = Message::new_context(1, &context);
+    let lines_str = message_to_lines(message, BubbleAlignment::Left, 0)?;
+    insta::assert_snapshot!(lines_str, @r###"
+    ╭Oatmeal───────────────────────────────╮      
+    │ Context 1 - File: file.rs [+4 lines] │      
+    ╰──────────────────────────────────────╯      
+    "###);
+
+    return Ok(());
+}
+
+#[test]
+fn it_creates_expanded_context() -> Result<()> {
+    let context = EditorContext {
+        file_path: "file.rs".to_string(),
+        language: "rust".to_string(),
+        code: "let x = 5;".to_string(),
+        start_line: 0,
+        end_line: Some(1),
+    };
+    let mut message = Message::new_context(1, &context);
+    message.set_collapsed(false);
+    let lines_str = message_to_lines(message, BubbleAlignment::Left, 0)?;
+    insta::assert_snapshot!(lines_str, @r###"
+    ╭Oatmeal────────────────────╮                 
+    │ Context 1 - File: file.rs │                 
+    │                           │                 
+    │ ```rust (1)               │                 
+    │ let x = 5;                │                 
+    │ ```                       │                 
+    ╰───────────────────────────╯                 
+    "###);
+
+    return Ok(());
+}
+
+#[test]
+fn it_creates_author_model_markdown() -> Result<()> {
+    let text = r#"
+## Summary
+
+The **fix** is in `src/main.rs`:
+
+1. Read the [docs](https://example.com).
+2. Run the tests.
+
+> Tests need a running ollama server, which they start themselves when there isn't one.
+"#
+    .trim();
+    let lines_str = create_lines(Author::Model, BubbleAlignment::Left, 0, text)?;
+
+    insta::assert_snapshot!(lines_str, @r###"
+    ╭model-1──────────────────────────────────────╮
+    │ Summary                                     │
+    │                                             │
+    │ The fix is in src/main.rs:                  │
+    │                                             │
+    │ 1. Read the docs (https://example.com).     │
+    │ 2. Run the tests.                           │
+    │                                             │
+    │ > Tests need a running ollama server, which │
+    │ they start themselves when there isn't one. │
+    ╰─────────────────────────────────────────────╯
+    "###);
+
+    return Ok(());
+}
+
+#[test]
+fn it_wraps_cjk_text_by_display_width() -> Result<()> {
+    let text =
+        "東京は日本の首都であり、世界で最も人口の多い都市圏の一つです。多くの観光客が訪れます。";
+    let lines_str = create_lines(Author::Model, BubbleAlignment::Left, 0, text)?;
+
+    insta::assert_snapshot!(lines_str, @r###"
+    ╭model-1──────────────────────────────────────╮
+    │ 東京は日本の首都であり、世界で最も人口の多  │
+    │ い都市圏の一つです。多くの観光客が訪れます  │
+    │ 。                                          │
+    ╰─────────────────────────────────────────────╯
+    "###);
+
+    return Ok(());
+}
+
+#[test]
+fn it_wraps_multilingual_text_by_display_width() -> Result<()> {
+    let text = "Ünïcödé café naïve résumé. Привет мир, это тест. 안녕하세요 세계 여러분 🦀 Rust 🎉 emoji 👍 done.";
+    let lines_str = create_lines(Author::User, BubbleAlignment::Right, 0, text)?;
+
+    insta::assert_snapshot!(lines_str, @r###"
+    ╭testuser─────────────────────────────────────╮
+    │ Ünïcödé café naïve résumé. Привет мир, это  │
+    │ тест. 안녕하세요 세계 여러분 🦀 Rust 🎉     │
+    │ emoji 👍 done.                              │
+    ╰─────────────────────────────────────────────╯
+    "###);
+
+    return Ok(());
+}
+
+#[test]
+fn it_keeps_box_drawing_characters_within_the_bubble() -> Result<()> {
+    let text = r#"
+The layout:
+
+```
+┌────────┬────────┐
+│ 左     │ right  │
+└────────┴────────┘
+```
+"#
+    .trim();
+    let lines_str = create_lines(Author::Model, BubbleAlignment::Left, 0, text)?;
+
+    insta::assert_snapshot!(lines_str, @r###"
+    ╭model-1──────────────╮                       
+    │ The layout:         │                       
+    │                     │                       
+    │ ``` (1)             │                       
+    │ ┌────────┬────────┐ │                       
+    │ │ 左     │ right  │ │                       
+    │ └────────┴────────┘ │                       
+    │ ```                 │                       
+    ╰─────────────────────╯                       
+    "###);
+
+    return Ok(());
+}
+
+#[test]
+fn it_highlights_the_border_when_focused() -> Result<()> {
+    let theme = Themes::get("base16-seti", "")?;
+    let message = Message::new(Author::Oatmeal, "Hi there!");
+
+    let lines = Bubble::new(&message, BubbleAlignment::Left, 50, 0)
+        .focused(true)
+        .as_lines(&theme);
+    assert_eq!(lines[0].spans[0].style.fg, Some(Color::Cyan));
+    assert_eq!(lines[1].spans[0].style.fg, Some(Color::Cyan));
+    assert_eq!(lines[1].spans[1].style.fg, None);
+
+    let lines = Bubble::new(&message, BubbleAlignment::Left, 50, 0).as_lines(&theme);
+    assert_ne!(lines[0].spans[0].style.fg, Some(Color::Cyan));
+
+    return Ok(());
+}
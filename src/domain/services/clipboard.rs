@@ -1,17 +1,84 @@
+#[cfg(test)]
+#[path = "clipboard_test.rs"]
+mod tests;
+
+use std::env;
+use std::io;
+use std::io::IsTerminal;
+use std::io::Write;
+
 use anyhow::anyhow;
+use anyhow::bail;
 use anyhow::Result;
+use base64::engine::general_purpose::STANDARD as b64;
+use base64::Engine;
 use once_cell::sync::OnceCell;
+use strum::EnumIter;
+use strum::EnumVariantNames;
+use strum::IntoEnumIterator;
 use tokio::sync::mpsc;
 
+use crate::configuration::Config;
+use crate::configuration::ConfigKey;
+
 static SENDER: OnceCell<mpsc::UnboundedSender<String>> = OnceCell::new();
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq, EnumIter, EnumVariantNames, strum::Display)]
+#[strum(serialize_all = "lowercase")]
+pub enum ClipboardProvider {
+    /// The system clipboard, falling back to OSC 52 when it's unavailable.
+    Auto,
+    /// The system clipboard.
+    System,
+    /// OSC 52 escape sequences, asking the terminal to set its clipboard.
+    Osc52,
+}
+
+impl ClipboardProvider {
+    pub fn parse(text: String) -> Option<ClipboardProvider> {
+        return ClipboardProvider::iter().find(|e| return e.to_string() == text);
+    }
+}
+
+/// Builds the OSC 52 escape sequence that sets the terminal's clipboard to
+/// `text`. Within tmux the sequence is wrapped in a passthrough sequence so
+/// it reaches the outer terminal, which needs `allow-passthrough` to be on.
+fn osc52_sequence(text: &str, tmux: bool) -> String {
+    let seq = format!("\x1b]52;c;{}\x07", b64.encode(text));
+    if !tmux {
+        return seq;
+    }
+
+    return format!("\x1bPtmux;{}\x1b\\", seq.replace('\x1b', "\x1b\x1b"));
+}
+
+fn write_osc52(text: &str) -> Result<()> {
+    let seq = osc52_sequence(text, env::var("TMUX").is_ok());
+    let mut stdout = io::stdout().lock();
+    stdout.write_all(seq.as_bytes())?;
+    stdout.flush()?;
+
+    return Ok(());
+}
+
+fn provider() -> ClipboardProvider {
+    return ClipboardProvider::parse(Config::get(ConfigKey::ClipboardProvider))
+        .unwrap_or(ClipboardProvider::Auto);
+}
+
 pub struct ClipboardService {}
 
 impl ClipboardService {
     pub async fn start() -> Result<()> {
         let (tx, mut rx) = mpsc::unbounded_channel::<String>();
         SENDER.set(tx).unwrap();
-        let mut clipboard = arboard::Clipboard::new()?;
+
+        let provider = provider();
+        let mut clipboard = match provider {
+            ClipboardProvider::Auto => arboard::Clipboard::new().ok(),
+            ClipboardProvider::System => Some(arboard::Clipboard::new()?),
+            ClipboardProvider::Osc52 => None,
+        };
 
         loop {
             let event = rx.recv().await;
@@ -19,7 +86,20 @@ impl ClipboardService {
                 continue;
             }
 
-            clipboard.set_text(event.unwrap())?;
+            let text = event.unwrap();
+            match clipboard.as_mut() {
+                Some(clipboard) => {
+                    if let Err(err) = clipboard.set_text(&text) {
+                        if provider != ClipboardProvider::Auto {
+                            return Err(err.into());
+                        }
+
+                        tracing::warn!(err = ?err, "System clipboard failed, falling back to OSC 52");
+                        write_osc52(&text)?;
+                    }
+                }
+                None => write_osc52(&text)?,
+            }
         }
     }
 
@@ -28,16 +108,48 @@ impl ClipboardService {
             return Ok(());
         }
 
-        arboard::Clipboard::new()?;
+        let provider = provider();
+        if provider != ClipboardProvider::Osc52 {
+            match arboard::Clipboard::new() {
+                Ok(_) => return Ok(()),
+                Err(err) if provider == ClipboardProvider::System => return Err(err.into()),
+                Err(err) => {
+                    tracing::info!(err = ?err, "System clipboard is unavailable, using OSC 52");
+                }
+            }
+        }
+
+        if !io::stdout().is_terminal() {
+            bail!("OSC 52 clipboard needs a terminal to write to");
+        }
+
         return Ok(());
     }
 
+    /// Reads text from the system clipboard. Terminals don't let programs
+    /// read their clipboard with OSC 52, so it's never used here.
+    pub fn get() -> Result<String> {
+        if provider() == ClipboardProvider::Osc52 {
+            bail!("The osc52 clipboard provider can't read your clipboard. Paste in to your terminal instead.");
+        }
+
+        match arboard::Clipboard::new()?.get_text() {
+            Ok(text) => return Ok(text),
+            Err(arboard::Error::ContentNotAvailable) => {
+                bail!("Your clipboard doesn't have any text. Only text can be pasted.")
+            }
+            Err(err) => return Err(err.into()),
+        }
+    }
+
     pub fn set(text: String) -> Result<()> {
         if let Some(tx) = SENDER.get() {
             tx.send(text)?;
             return Ok(());
         }
 
-        return Err(anyhow!("Clipboard service is not initialized."));
+        return Err(anyhow!(
+            "Clipboard service is not initialized. Set `clipboard-provider` to `osc52` to copy through your terminal instead."
+        ));
     }
 }
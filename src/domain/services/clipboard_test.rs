@@ -0,0 +1,35 @@
+use super::osc52_sequence;
+use super::ClipboardProvider;
+
+#[test]
+fn it_builds_osc52_sequences() {
+    assert_eq!(
+        osc52_sequence("hello world", false),
+        "\x1b]52;c;aGVsbG8gd29ybGQ=\x07"
+    );
+}
+
+#[test]
+fn it_wraps_osc52_sequences_for_tmux() {
+    assert_eq!(
+        osc52_sequence("hello world", true),
+        "\x1bPtmux;\x1b\x1b]52;c;aGVsbG8gd29ybGQ=\x07\x1b\\"
+    );
+}
+
+#[test]
+fn it_encodes_multiline_unicode_text() {
+    assert_eq!(
+        osc52_sequence("fn main() {\n    println!(\"🥣\");\n}", false),
+        "\x1b]52;c;Zm4gbWFpbigpIHsKICAgIHByaW50bG4hKCLwn6WjIik7Cn0=\x07"
+    );
+}
+
+#[test]
+fn it_parses_clipboard_providers() {
+    assert_eq!(
+        ClipboardProvider::parse("osc52".to_string()),
+        Some(ClipboardProvider::Osc52)
+    );
+    assert_eq!(ClipboardProvider::parse("xclip".to_string()), None);
+}
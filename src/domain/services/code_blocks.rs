@@ -1,6 +1,8 @@
 use anyhow::anyhow;
+use anyhow::bail;
 use anyhow::Result;
 
+use crate::domain::models::FilePatch;
 use crate::domain::models::Message;
 use crate::domain::models::SlashCommand;
 
@@ -11,6 +13,8 @@ mod tests;
 #[derive(Default)]
 pub struct CodeBlocks {
     codeblocks: Vec<String>,
+    file_paths: Vec<Option<String>>,
+    languages: Vec<String>,
 }
 
 impl CodeBlocks {
@@ -21,22 +25,99 @@ impl CodeBlocks {
                 return msg.codeblocks();
             })
             .collect();
+
+        self.file_paths = messages
+            .iter()
+            .flat_map(|msg| {
+                return msg.codeblock_file_paths();
+            })
+            .collect();
+
+        self.languages = messages
+            .iter()
+            .flat_map(|msg| {
+                return msg.codeblock_languages();
+            })
+            .collect();
     }
 
     pub fn blocks_from_slash_commands(&self, command: &SlashCommand) -> Result<String> {
+        return self.blocks_from_args(&command.args);
+    }
+
+    /// Joins the code blocks selected by `CODE_BLOCK_NUMBER` arguments.
+    pub fn blocks_from_args(&self, args: &[String]) -> Result<String> {
         if self.codeblocks.is_empty() {
             return Ok("".to_string());
         }
 
-        let args = command
-            .args
+        let res = self
+            .indexes_from_args(args)?
+            .iter()
+            .map(|idx| return self.codeblocks[*idx].to_string())
+            .collect::<Vec<String>>()
+            .join("\n\n");
+
+        return Ok(res);
+    }
+
+    /// The file path hinted in the fences of the selected code blocks, when
+    /// they all agree on one.
+    pub fn file_path_from_args(&self, args: &[String]) -> Result<Option<String>> {
+        if self.codeblocks.is_empty() {
+            return Ok(None);
+        }
+
+        let mut file_paths = self
+            .indexes_from_args(args)?
+            .into_iter()
+            .map(|idx| return self.file_paths[idx].clone())
+            .collect::<Vec<Option<String>>>();
+        file_paths.dedup();
+
+        if file_paths.len() != 1 {
+            return Ok(None);
+        }
+
+        return Ok(file_paths.remove(0));
+    }
+
+    /// Parses the selected code blocks as diffs or SEARCH/REPLACE edit blocks.
+    pub fn patches_from_args(&self, args: &[String]) -> Result<Vec<FilePatch>> {
+        if self.codeblocks.is_empty() {
+            bail!("There are no code blocks to patch files with");
+        }
+
+        let mut patches = vec![];
+        for idx in self.indexes_from_args(args)? {
+            let file_path = self.file_paths[idx].as_deref();
+            match FilePatch::parse(&self.codeblocks[idx], &self.languages[idx], file_path)? {
+                Some(file_patches) => patches.extend(file_patches),
+                None => bail!(format!(
+                    "Code block {} isn't a diff or SEARCH/REPLACE edit block",
+                    idx + 1
+                )),
+            }
+        }
+
+        // Hunks that don't change anything are dropped while parsing, which
+        // can leave nothing to apply.
+        if patches.is_empty() {
+            bail!("The code blocks don't change anything, so there's nothing to patch");
+        }
+
+        return Ok(patches);
+    }
+
+    fn indexes_from_args(&self, args: &[String]) -> Result<Vec<usize>> {
+        let args = args
             .iter()
             .map(|e| return e.trim().to_string())
             .filter(|e| return !e.is_empty())
             .collect::<Vec<String>>();
 
         if args.is_empty() {
-            return Ok(self.codeblocks.last().unwrap().to_string());
+            return Ok(vec![self.codeblocks.len() - 1]);
         }
 
         let mut indexes = vec![];
@@ -64,13 +145,7 @@ impl CodeBlocks {
             }
         }
 
-        let res = indexes
-            .iter()
-            .map(|idx| return self.codeblocks[*idx].to_string())
-            .collect::<Vec<String>>()
-            .join("\n\n");
-
-        return Ok(res);
+        return Ok(indexes);
     }
 
     fn validate_index(&self, entry: &str) -> Result<usize> {
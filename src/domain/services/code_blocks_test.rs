@@ -104,3 +104,30 @@ fn it_throws_an_error_on_invalid_index() {
     let res = from_slash_command("/a 1010101").unwrap_err().to_string();
     insta::assert_snapshot!(res, @"Code block index 1010101 is not valid");
 }
+
+#[test]
+fn it_provides_hinted_file_paths() -> Result<()> {
+    let messages = vec![Message::new(
+        Author::Model,
+        "```rust src/main.rs\nfn main() {}\n```\n\n```rust src/main.rs\nlet x = 5;\n```\n\n```rust\nlet y = 6;\n```",
+    )];
+
+    let mut codeblocks = CodeBlocks::default();
+    codeblocks.replace_from_messages(&messages);
+
+    let args = |text: &str| {
+        return text
+            .split(' ')
+            .map(|e| return e.to_string())
+            .collect::<Vec<String>>();
+    };
+
+    assert_eq!(
+        codeblocks.file_path_from_args(&args("1,2"))?,
+        Some("src/main.rs".to_string())
+    );
+    assert_eq!(codeblocks.file_path_from_args(&args("2..3"))?, None);
+    assert_eq!(codeblocks.file_path_from_args(&[])?, None);
+
+    return Ok(());
+}
@@ -0,0 +1,352 @@
+use std::collections::HashSet;
+
+use ratatui::prelude::*;
+use ratatui::widgets::Block;
+use ratatui::widgets::BorderType;
+use ratatui::widgets::Borders;
+use ratatui::widgets::Clear;
+use ratatui::widgets::Padding;
+use ratatui::widgets::Paragraph;
+use ratatui::Frame;
+use similar::udiff::UnifiedHunkHeader;
+use similar::ChangeTag;
+use similar::DiffTag;
+use similar::TextDiff;
+use syntect::easy::HighlightLines;
+use syntect::highlighting::Theme;
+use tui_textarea::Input;
+use tui_textarea::Key;
+
+use super::popup_rect;
+use super::Syntaxes;
+use super::SYNTAX_SET;
+use crate::domain::models::AcceptType;
+use crate::domain::models::EditorContext;
+
+#[cfg(test)]
+#[path = "diff_preview_test.rs"]
+mod tests;
+
+/// Lines of unchanged code shown around each hunk.
+const CONTEXT_LINES: usize = 3;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DiffLineKind {
+    Unchanged,
+    Removed,
+    Added,
+}
+
+pub struct DiffLine {
+    pub kind: DiffLineKind,
+    pub spans: Vec<Span<'static>>,
+}
+
+pub struct DiffHunk {
+    pub header: String,
+    pub lines: Vec<DiffLine>,
+    pub accepted: bool,
+    /// Old and new line offsets of each change in the hunk, used to find the
+    /// hunk a change belongs to when applying the diff.
+    changes: Vec<(usize, usize)>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum DiffPreviewDecision {
+    Accept,
+    Reject,
+}
+
+/// Ensures non-empty text ends with exactly one line break, so the last line
+/// doesn't show as changed only because a line break was added or removed.
+fn with_trailing_newline(text: &str) -> String {
+    let text = text.trim_end_matches('\n');
+    if text.is_empty() {
+        return "".to_string();
+    }
+
+    return format!("{text}\n");
+}
+
+fn highlight_lines(text: &str, language: &str, theme: &Theme) -> Vec<Vec<Span<'static>>> {
+    let mut highlight = HighlightLines::new(Syntaxes::get(language), theme);
+
+    return text
+        .lines()
+        .map(|line| {
+            // Highlighting doesn't work accurately unless each line is postfixed with '\n'.
+            let line_nl = format!("{line}\n");
+            let highlighted = highlight.highlight_line(&line_nl, &SYNTAX_SET).unwrap();
+
+            return highlighted
+                .iter()
+                .map(|(style, content)| {
+                    return Span::styled(
+                        content.trim_end_matches('\n').to_string(),
+                        Style {
+                            fg: Syntaxes::translate_colour(style.foreground),
+                            ..Style::default()
+                        },
+                    );
+                })
+                .collect();
+        })
+        .collect();
+}
+
+/// Popup showing what accepting code blocks would change in the editor, so
+/// each hunk can be accepted or rejected before anything is sent.
+pub struct DiffPreview {
+    context: EditorContext,
+    accept_type: AcceptType,
+    old: String,
+    new: String,
+    pub hunks: Vec<DiffHunk>,
+    pub selected: usize,
+    /// Shown above the diff, such as when writing outside of the working
+    /// directory.
+    pub warning: Option<String>,
+}
+
+impl DiffPreview {
+    pub fn new(
+        context: EditorContext,
+        codeblock: &str,
+        accept_type: AcceptType,
+        theme: &Theme,
+    ) -> DiffPreview {
+        let old = with_trailing_newline(&context.code);
+        let new = match accept_type {
+            AcceptType::Append => format!("{old}{}", with_trailing_newline(codeblock)),
+            AcceptType::Replace => with_trailing_newline(codeblock),
+        };
+
+        let old_lines = highlight_lines(&old, &context.language, theme);
+        let new_lines = highlight_lines(&new, &context.language, theme);
+
+        let diff = TextDiff::from_lines(&old, &new);
+        let hunks = diff
+            .grouped_ops(CONTEXT_LINES)
+            .iter()
+            .map(|group| {
+                let mut changes = vec![];
+                let mut lines = vec![];
+                for op in group {
+                    let (tag, old_range, new_range) = op.as_tag_tuple();
+                    if tag != DiffTag::Equal {
+                        changes.push((old_range.start, new_range.start));
+                    }
+
+                    for change in diff.iter_changes(op) {
+                        let (kind, spans) = match change.tag() {
+                            ChangeTag::Equal => (
+                                DiffLineKind::Unchanged,
+                                &old_lines[change.old_index().unwrap()],
+                            ),
+                            ChangeTag::Delete => (
+                                DiffLineKind::Removed,
+                                &old_lines[change.old_index().unwrap()],
+                            ),
+                            ChangeTag::Insert => {
+                                (DiffLineKind::Added, &new_lines[change.new_index().unwrap()])
+                            }
+                        };
+                        lines.push(DiffLine {
+                            kind,
+                            spans: spans.clone(),
+                        });
+                    }
+                }
+
+                return DiffHunk {
+                    header: UnifiedHunkHeader::new(group).to_string(),
+                    lines,
+                    accepted: true,
+                    changes,
+                };
+            })
+            .collect();
+
+        return DiffPreview {
+            context,
+            accept_type,
+            old,
+            new,
+            hunks,
+            selected: 0,
+            warning: None,
+        };
+    }
+
+    pub fn up(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    pub fn down(&mut self) {
+        if self.selected + 1 < self.hunks.len() {
+            self.selected += 1;
+        }
+    }
+
+    /// Flips whether the selected hunk is accepted.
+    pub fn toggle(&mut self) {
+        if let Some(hunk) = self.hunks.get_mut(self.selected) {
+            hunk.accepted = !hunk.accepted;
+        }
+    }
+
+    /// Handles a key press, returning a decision once the preview should close.
+    pub fn input(&mut self, input: Input) -> Option<DiffPreviewDecision> {
+        match input.key {
+            Key::Char('y') => return Some(DiffPreviewDecision::Accept),
+            Key::Char('n') | Key::Esc => return Some(DiffPreviewDecision::Reject),
+            Key::Char('k') | Key::Up => self.up(),
+            Key::Char('j') | Key::Down => self.down(),
+            Key::Char(' ') => self.toggle(),
+            _ => {}
+        }
+
+        return None;
+    }
+
+    /// Code with only the accepted hunks applied.
+    fn apply(&self) -> String {
+        let accepted = self
+            .hunks
+            .iter()
+            .filter(|hunk| return hunk.accepted)
+            .flat_map(|hunk| return hunk.changes.iter().copied())
+            .collect::<HashSet<(usize, usize)>>();
+
+        let diff = TextDiff::from_lines(&self.old, &self.new);
+        let mut res = String::new();
+        for op in diff.ops() {
+            let (tag, old_range, new_range) = op.as_tag_tuple();
+            if tag != DiffTag::Equal && accepted.contains(&(old_range.start, new_range.start)) {
+                res += &diff.new_slices()[new_range].concat();
+            } else {
+                res += &diff.old_slices()[old_range].concat();
+            }
+        }
+
+        return res;
+    }
+
+    /// The context, code, and accept type to send to the editor, or `None`
+    /// when every hunk was rejected.
+    pub fn accepted(&self) -> Option<(EditorContext, String, AcceptType)> {
+        if !self.hunks.iter().any(|hunk| return hunk.accepted) {
+            return None;
+        }
+
+        let res = self.apply();
+        let mut accept_type = self.accept_type;
+        let mut code = res.as_str();
+        if accept_type == AcceptType::Append {
+            // Appended lines can be diffed in amongst the existing code when
+            // they repeat it, in which case the selection is replaced instead.
+            match res.strip_prefix(&self.old) {
+                Some(appended) => code = appended,
+                None => accept_type = AcceptType::Replace,
+            }
+        }
+
+        return Some((
+            self.context.clone(),
+            code.trim_end_matches('\n').to_string(),
+            accept_type,
+        ));
+    }
+
+    fn as_lines(&self) -> (Vec<Line<'static>>, usize) {
+        let mut lines = vec![];
+        let mut selected_offset = 0;
+
+        for (idx, hunk) in self.hunks.iter().enumerate() {
+            if idx > 0 {
+                lines.push(Line::from(""));
+            }
+
+            let status = if hunk.accepted {
+                "accepted"
+            } else {
+                "rejected"
+            };
+            let mut header_style = Style::default().fg(Color::Cyan);
+            if idx == self.selected {
+                selected_offset = lines.len();
+                header_style = header_style.add_modifier(Modifier::REVERSED);
+            }
+            lines.push(Line::from(Span::styled(
+                format!("{} [{status}]", hunk.header.trim_end()),
+                header_style,
+            )));
+
+            for line in &hunk.lines {
+                let prefix = match line.kind {
+                    DiffLineKind::Unchanged => Span::from("  "),
+                    DiffLineKind::Removed => Span::styled("- ", Style::default().fg(Color::Red)),
+                    DiffLineKind::Added => Span::styled("+ ", Style::default().fg(Color::Green)),
+                };
+
+                let mut spans = vec![prefix];
+                spans.extend(line.spans.iter().cloned());
+                let mut line = Line::from(spans);
+                if !hunk.accepted {
+                    line.patch_style(Style::default().add_modifier(Modifier::DIM));
+                }
+                lines.push(line);
+            }
+        }
+
+        return (lines, selected_offset);
+    }
+
+    pub fn render(&self, frame: &mut Frame, rect: Rect) {
+        let popup = popup_rect(rect);
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_type(BorderType::Double)
+            .title(format!(
+                "Review {} to {}",
+                self.accept_type,
+                self.context.summary()
+            ))
+            .padding(Padding::new(1, 1, 0, 0));
+        let inner = block.inner(popup);
+
+        frame.render_widget(Clear, popup);
+        frame.render_widget(block, popup);
+
+        let warning_len = if self.warning.is_some() { 2 } else { 0 };
+        let layout = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(vec![
+                Constraint::Length(warning_len),
+                Constraint::Min(1),
+                Constraint::Length(1),
+            ])
+            .split(inner);
+
+        if let Some(warning) = &self.warning {
+            frame.render_widget(Paragraph::new(warning.to_string()).red().bold(), layout[0]);
+        }
+
+        // Keep the selected hunk's header in view.
+        let (lines, selected_offset) = self.as_lines();
+        let height = layout[1].height as usize;
+        let scroll = selected_offset.min(lines.len().saturating_sub(height));
+
+        frame.render_widget(
+            Paragraph::new(lines).scroll((scroll.try_into().unwrap_or(u16::MAX), 0)),
+            layout[1],
+        );
+        frame.render_widget(
+            Paragraph::new(
+                "y/Enter apply accepted hunks, n/Esc discard, j/k select hunk, Space toggle hunk",
+            )
+            .bold(),
+            layout[2],
+        );
+    }
+}
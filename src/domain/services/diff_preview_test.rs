@@ -0,0 +1,171 @@
+use anyhow::Result;
+use tui_textarea::Input;
+use tui_textarea::Key;
+
+use super::DiffLine;
+use super::DiffLineKind;
+use super::DiffPreview;
+use super::DiffPreviewDecision;
+use crate::domain::models::AcceptType;
+use crate::domain::models::EditorContext;
+use crate::domain::services::Themes;
+
+const OLD_CODE: &str = "fn one() {\n    return 1;\n}\n\nfn middle() {}\nfn middle() {}\nfn middle() {}\nfn middle() {}\nfn middle() {}\nfn middle() {}\nfn middle() {}\n\nfn two() {\n    return 2;\n}";
+
+const NEW_CODE: &str = "fn one() {\n    return 10;\n}\n\nfn middle() {}\nfn middle() {}\nfn middle() {}\nfn middle() {}\nfn middle() {}\nfn middle() {}\nfn middle() {}\n\nfn two() {\n    return 20;\n}";
+
+fn context(code: &str) -> EditorContext {
+    return EditorContext {
+        file_path: "src/main.rs".to_string(),
+        language: "rust".to_string(),
+        code: code.to_string(),
+        start_line: 1,
+        end_line: Some(15),
+    };
+}
+
+fn preview(code: &str, codeblock: &str, accept_type: AcceptType) -> Result<DiffPreview> {
+    let theme = Themes::get("base16-seti", "")?;
+    return Ok(DiffPreview::new(
+        context(code),
+        codeblock,
+        accept_type,
+        &theme,
+    ));
+}
+
+fn text(line: &DiffLine) -> String {
+    return line
+        .spans
+        .iter()
+        .map(|span| return span.content.to_string())
+        .collect();
+}
+
+fn key(preview: &mut DiffPreview, key: Key) -> Option<DiffPreviewDecision> {
+    return preview.input(Input {
+        key,
+        ..Input::default()
+    });
+}
+
+#[test]
+fn it_splits_changes_in_to_hunks() -> Result<()> {
+    let preview = preview(OLD_CODE, NEW_CODE, AcceptType::Replace)?;
+
+    assert_eq!(preview.hunks.len(), 2);
+    assert_eq!(preview.hunks[0].header, "@@ -1,5 +1,5 @@");
+    assert_eq!(preview.hunks[1].header, "@@ -11,5 +11,5 @@");
+
+    let changed = preview.hunks[0]
+        .lines
+        .iter()
+        .filter(|line| return line.kind != DiffLineKind::Unchanged)
+        .map(|line| return (line.kind, text(line)))
+        .collect::<Vec<(DiffLineKind, String)>>();
+    assert_eq!(
+        changed,
+        vec![
+            (DiffLineKind::Removed, "    return 1;".to_string()),
+            (DiffLineKind::Added, "    return 10;".to_string()),
+        ]
+    );
+
+    return Ok(());
+}
+
+#[test]
+fn it_accepts_every_hunk_by_default() -> Result<()> {
+    let preview = preview(OLD_CODE, NEW_CODE, AcceptType::Replace)?;
+
+    let (_context, code, accept_type) = preview.accepted().unwrap();
+    assert_eq!(accept_type, AcceptType::Replace);
+    assert_eq!(code, NEW_CODE);
+
+    return Ok(());
+}
+
+#[test]
+fn it_applies_only_accepted_hunks() -> Result<()> {
+    let mut preview = preview(OLD_CODE, NEW_CODE, AcceptType::Replace)?;
+    key(&mut preview, Key::Down);
+    key(&mut preview, Key::Char(' '));
+
+    assert!(preview.hunks[0].accepted);
+    assert!(!preview.hunks[1].accepted);
+
+    let (_context, code, _accept_type) = preview.accepted().unwrap();
+    assert!(code.contains("return 10;"));
+    assert!(code.contains("return 2;"));
+
+    return Ok(());
+}
+
+#[test]
+fn it_returns_none_when_every_hunk_is_rejected() -> Result<()> {
+    let mut preview = preview(OLD_CODE, NEW_CODE, AcceptType::Replace)?;
+    key(&mut preview, Key::Char(' '));
+    key(&mut preview, Key::Char('j'));
+    key(&mut preview, Key::Char(' '));
+
+    assert!(preview.accepted().is_none());
+
+    return Ok(());
+}
+
+#[test]
+fn it_shows_appended_code_as_additions() -> Result<()> {
+    let preview = preview("fn one() {}", "fn two() {}", AcceptType::Append)?;
+
+    assert_eq!(preview.hunks.len(), 1);
+    let kinds = preview.hunks[0]
+        .lines
+        .iter()
+        .map(|line| return line.kind)
+        .collect::<Vec<DiffLineKind>>();
+    assert_eq!(kinds, vec![DiffLineKind::Unchanged, DiffLineKind::Added]);
+
+    let (_context, code, accept_type) = preview.accepted().unwrap();
+    assert_eq!(accept_type, AcceptType::Append);
+    assert_eq!(code, "fn two() {}");
+
+    return Ok(());
+}
+
+#[test]
+fn it_has_no_hunks_when_code_is_unchanged() -> Result<()> {
+    let preview = preview("fn one() {}\n", "fn one() {}", AcceptType::Replace)?;
+    assert!(preview.hunks.is_empty());
+
+    return Ok(());
+}
+
+#[test]
+fn it_keeps_the_selection_in_bounds() -> Result<()> {
+    let mut preview = preview(OLD_CODE, NEW_CODE, AcceptType::Replace)?;
+    key(&mut preview, Key::Up);
+    assert_eq!(preview.selected, 0);
+
+    key(&mut preview, Key::Down);
+    key(&mut preview, Key::Down);
+    assert_eq!(preview.selected, 1);
+
+    return Ok(());
+}
+
+#[test]
+fn it_returns_decisions() -> Result<()> {
+    let mut preview = preview(OLD_CODE, NEW_CODE, AcceptType::Replace)?;
+
+    assert_eq!(key(&mut preview, Key::Char(' ')), None);
+    assert_eq!(
+        key(&mut preview, Key::Char('y')),
+        Some(DiffPreviewDecision::Accept)
+    );
+    assert_eq!(
+        key(&mut preview, Key::Esc),
+        Some(DiffPreviewDecision::Reject)
+    );
+
+    return Ok(());
+}
@@ -106,6 +106,13 @@ impl EventsService {
                     } => {
                         return Some(Event::KeyboardCTRLR());
                     }
+                    Input {
+                        key: Key::Char('v'),
+                        ctrl: true,
+                        ..
+                    } => {
+                        return Some(Event::KeyboardCTRLV());
+                    }
                     Input {
                         key: Key::Enter, ..
                     } => {
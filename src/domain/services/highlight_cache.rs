@@ -0,0 +1,137 @@
+#[cfg(test)]
+#[path = "highlight_cache_test.rs"]
+mod tests;
+
+use ratatui::style::Style;
+use ratatui::text::Span;
+use syntect::highlighting::HighlightIterator;
+use syntect::highlighting::HighlightState;
+use syntect::highlighting::Highlighter;
+use syntect::highlighting::Theme;
+use syntect::parsing::ParseState;
+use syntect::parsing::ScopeStack;
+
+use super::Syntaxes;
+use super::SYNTAX_SET;
+
+/// A highlighted line of code, along with the highlighter's state after it
+/// so highlighting can pick up from the next line.
+struct HighlightedLine {
+    text: String,
+    spans: Vec<Span<'static>>,
+    parse_state: ParseState,
+    highlight_state: HighlightState,
+}
+
+#[derive(Default)]
+struct HighlightedCodeBlock {
+    language: String,
+    theme: String,
+    lines: Vec<HighlightedLine>,
+}
+
+/// Highlighted lines of each code block in a message. While a response is
+/// streamed in only its last line or so changes between renders, so lines
+/// that are unchanged since the last render are reused rather than
+/// highlighting the whole code block again.
+#[derive(Default)]
+pub struct HighlightCache {
+    codeblocks: Vec<HighlightedCodeBlock>,
+}
+
+impl HighlightCache {
+    /// Highlights the code block at `idx` within a message, returning the
+    /// spans of each line.
+    pub fn highlight(
+        &mut self,
+        idx: usize,
+        language: &str,
+        code: &str,
+        theme: &Theme,
+    ) -> Vec<Vec<Span<'static>>> {
+        while self.codeblocks.len() <= idx {
+            self.codeblocks.push(HighlightedCodeBlock::default());
+        }
+        let theme_name = theme.name.clone().unwrap_or_default();
+        if self.codeblocks[idx].language != language || self.codeblocks[idx].theme != theme_name {
+            self.codeblocks[idx] = HighlightedCodeBlock {
+                language: language.to_string(),
+                theme: theme_name,
+                lines: vec![],
+            };
+        }
+
+        let codeblock = &mut self.codeblocks[idx];
+        let code_lines = code.lines().collect::<Vec<&str>>();
+        let unchanged = codeblock
+            .lines
+            .iter()
+            .zip(code_lines.iter())
+            .take_while(|(cached, line)| return cached.text == **line)
+            .count();
+        codeblock.lines.truncate(unchanged);
+
+        let highlighter = Highlighter::new(theme);
+        let (mut parse_state, mut highlight_state) = match codeblock.lines.last() {
+            Some(cached) => (cached.parse_state.clone(), cached.highlight_state.clone()),
+            None => (
+                ParseState::new(Syntaxes::get(language)),
+                HighlightState::new(&highlighter, ScopeStack::new()),
+            ),
+        };
+
+        for line in code_lines.iter().skip(unchanged) {
+            let spans = highlight_line(line, &highlighter, &mut parse_state, &mut highlight_state);
+            codeblock.lines.push(HighlightedLine {
+                text: line.to_string(),
+                spans,
+                parse_state: parse_state.clone(),
+                highlight_state: highlight_state.clone(),
+            });
+        }
+
+        return codeblock
+            .lines
+            .iter()
+            .map(|cached| return cached.spans.clone())
+            .collect();
+    }
+
+    pub fn clear(&mut self) {
+        self.codeblocks.clear();
+    }
+}
+
+fn highlight_line(
+    line: &str,
+    highlighter: &Highlighter,
+    parse_state: &mut ParseState,
+    highlight_state: &mut HighlightState,
+) -> Vec<Span<'static>> {
+    // Highlighting doesn't work accurately unless each line is postfixed with '\n',
+    // especially when dealing with multi-line code comments.
+    let line_nl = format!("{line}\n");
+    let ops = parse_state.parse_line(&line_nl, &SYNTAX_SET).unwrap();
+    let highlighted =
+        HighlightIterator::new(highlight_state, &ops[..], &line_nl, highlighter).collect::<Vec<_>>();
+
+    return highlighted
+        .iter()
+        .enumerate()
+        .map(|(idx, segment)| {
+            let (style, content) = segment;
+            let mut text = content.to_string();
+            if idx == highlighted.len() - 1 {
+                text = text.trim_end().to_string();
+            }
+
+            return Span::styled(
+                text,
+                Style {
+                    fg: Syntaxes::translate_colour(style.foreground),
+                    ..Style::default()
+                },
+            );
+        })
+        .collect();
+}
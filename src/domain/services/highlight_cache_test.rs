@@ -0,0 +1,110 @@
+use anyhow::Result;
+use syntect::highlighting::Color;
+use test_utils::codeblock_fixture;
+
+use super::HighlightCache;
+use crate::domain::models::Markdown;
+use crate::domain::services::Themes;
+
+fn fixture_code() -> String {
+    return Markdown::codeblocks(codeblock_fixture())[0].code.to_string();
+}
+
+#[test]
+fn it_highlights_streamed_code_the_same_as_all_at_once() -> Result<()> {
+    let theme = Themes::get("base16-seti", "")?;
+    let code = fixture_code();
+    let expected = HighlightCache::default().highlight(0, "rust", &code, &theme);
+
+    let mut cache = HighlightCache::default();
+    for (idx, _) in code.char_indices().step_by(7) {
+        cache.highlight(0, "rust", &code[..idx], &theme);
+    }
+    let streamed = cache.highlight(0, "rust", &code, &theme);
+
+    assert_eq!(streamed, expected);
+    return Ok(());
+}
+
+#[test]
+fn it_carries_state_across_cached_lines() -> Result<()> {
+    let theme = Themes::get("base16-seti", "")?;
+    let code = "/* A comment\nstill a comment */\nfn main() {}";
+    let expected = HighlightCache::default().highlight(0, "rust", code, &theme);
+
+    let mut cache = HighlightCache::default();
+    cache.highlight(0, "rust", "/* A comment\nstill", &theme);
+    let streamed = cache.highlight(0, "rust", code, &theme);
+
+    assert_eq!(streamed, expected);
+    assert_ne!(streamed[1], streamed[2]);
+    return Ok(());
+}
+
+#[test]
+fn it_rehighlights_when_earlier_lines_change() -> Result<()> {
+    let theme = Themes::get("base16-seti", "")?;
+    let code = "let a = 1;\nlet b = \"two\";";
+    let expected = HighlightCache::default().highlight(0, "rust", code, &theme);
+
+    let mut cache = HighlightCache::default();
+    cache.highlight(0, "rust", "/* let a = 1;\nlet b = \"two\";", &theme);
+    let highlighted = cache.highlight(0, "rust", code, &theme);
+
+    assert_eq!(highlighted, expected);
+    return Ok(());
+}
+
+#[test]
+fn it_rehighlights_when_the_language_changes() -> Result<()> {
+    let theme = Themes::get("base16-seti", "")?;
+    let code = "def main():\n    return 'hi'";
+    let expected = HighlightCache::default().highlight(0, "python", code, &theme);
+
+    let mut cache = HighlightCache::default();
+    cache.highlight(0, "rust", code, &theme);
+    let highlighted = cache.highlight(0, "python", code, &theme);
+
+    assert_eq!(highlighted, expected);
+    return Ok(());
+}
+
+#[test]
+fn it_rehighlights_when_the_theme_changes() -> Result<()> {
+    let seti = Themes::get("base16-seti", "")?;
+    // Spans cached for the first theme would show the wrong text colour.
+    let mut plain = seti.clone();
+    plain.name = Some("plain".to_string());
+    plain.settings.foreground = Some(Color {
+        r: 1,
+        g: 2,
+        b: 3,
+        a: 0xFF,
+    });
+    let code = "fn main() {}";
+    let expected = HighlightCache::default().highlight(0, "rust", code, &plain);
+
+    let mut cache = HighlightCache::default();
+    let previous = cache.highlight(0, "rust", code, &seti);
+    let highlighted = cache.highlight(0, "rust", code, &plain);
+
+    assert_ne!(highlighted, previous);
+    assert_eq!(highlighted, expected);
+    return Ok(());
+}
+
+#[test]
+fn it_keeps_codeblocks_apart() -> Result<()> {
+    let theme = Themes::get("base16-seti", "")?;
+    let mut cache = HighlightCache::default();
+    let first = cache.highlight(0, "rust", "fn main() {}", &theme);
+    let second = cache.highlight(1, "sh", "cargo run", &theme);
+
+    assert_eq!(cache.highlight(0, "rust", "fn main() {}", &theme), first);
+    assert_eq!(cache.highlight(1, "sh", "cargo run", &theme), second);
+    assert_eq!(cache.codeblocks.len(), 2);
+
+    cache.clear();
+    assert_eq!(cache.codeblocks.len(), 0);
+    return Ok(());
+}
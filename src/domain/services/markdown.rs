@@ -0,0 +1,486 @@
+#[cfg(test)]
+#[path = "markdown_test.rs"]
+mod tests;
+
+use std::mem;
+
+use pulldown_cmark::Alignment;
+use pulldown_cmark::CodeBlockKind;
+use pulldown_cmark::Event;
+use pulldown_cmark::HeadingLevel;
+use pulldown_cmark::Tag;
+use ratatui::style::Color;
+use ratatui::style::Modifier;
+use ratatui::style::Style;
+use ratatui::text::Span;
+use syntect::highlighting::Theme;
+
+use super::HighlightCache;
+use super::TextWidth;
+use crate::domain::models::Markdown;
+
+enum Container {
+    Quote,
+    /// A list item, holding on to its marker until its first line is written.
+    Item {
+        marker: Option<String>,
+        indent: usize,
+    },
+}
+
+#[derive(Default)]
+struct Table {
+    alignments: Vec<Alignment>,
+    rows: Vec<Vec<Vec<Span<'static>>>>,
+    row: Vec<Vec<Span<'static>>>,
+    cell: Vec<Span<'static>>,
+}
+
+fn spans_width(spans: &[Span]) -> usize {
+    return spans
+        .iter()
+        .map(|span| return TextWidth::of(&span.content))
+        .sum();
+}
+
+fn heading_style(level: HeadingLevel) -> Style {
+    let style = Style::default().add_modifier(Modifier::BOLD);
+    if level <= HeadingLevel::H2 {
+        return style.add_modifier(Modifier::UNDERLINED);
+    }
+
+    return style;
+}
+
+/// Renders Markdown in to lines of styled spans for chat bubbles, leaving
+/// wrapping to the bubble. Code blocks are syntax highlighted and numbered
+/// so they can be used with code actions such as `/append`.
+pub struct MarkdownRenderer<'a> {
+    theme: &'a Theme,
+    codeblock_counter: usize,
+    codeblock_start: usize,
+    highlight_cache: Option<&'a mut HighlightCache>,
+    lines: Vec<Vec<Span<'static>>>,
+    line: Vec<Span<'static>>,
+    styles: Vec<Style>,
+    containers: Vec<Container>,
+    /// Next number of each open list, or `None` for bullet lists.
+    lists: Vec<Option<u64>>,
+    /// Destination and text of each open link.
+    links: Vec<(String, String)>,
+    /// Info string and code of the code block being read.
+    codeblock: Option<(String, String)>,
+    table: Option<Table>,
+    needs_blank_line: bool,
+}
+
+impl<'a> MarkdownRenderer<'a> {
+    /// `codeblock_counter` is the number of code blocks in previous messages.
+    pub fn new(theme: &'a Theme, codeblock_counter: usize) -> MarkdownRenderer<'a> {
+        return MarkdownRenderer {
+            theme,
+            codeblock_counter,
+            codeblock_start: codeblock_counter,
+            highlight_cache: None,
+            lines: vec![],
+            line: vec![],
+            styles: vec![],
+            containers: vec![],
+            lists: vec![],
+            links: vec![],
+            codeblock: None,
+            table: None,
+            needs_blank_line: false,
+        };
+    }
+
+    /// Reuses code highlighted in previous renders of the same message,
+    /// rather than highlighting all of its code blocks again.
+    pub fn with_highlight_cache(
+        mut self,
+        highlight_cache: &'a mut HighlightCache,
+    ) -> MarkdownRenderer<'a> {
+        self.highlight_cache = Some(highlight_cache);
+        return self;
+    }
+
+    pub fn render(mut self, text: &str) -> Vec<Vec<Span<'static>>> {
+        for event in Markdown::parse(text) {
+            self.event(event);
+        }
+        self.end_line();
+
+        return self.lines;
+    }
+
+    fn event(&mut self, event: Event) {
+        match event {
+            Event::Start(tag) => self.start(tag),
+            Event::End(tag) => self.end(tag),
+            Event::Text(text) => {
+                if let Some((_info, code)) = self.codeblock.as_mut() {
+                    *code += &text;
+                } else {
+                    self.push_text(&text, self.style());
+                }
+            }
+            Event::Code(code) => {
+                let style = self.style().patch(Style {
+                    fg: Some(Color::Yellow),
+                    ..Style::default()
+                });
+                self.push_text(&code, style);
+            }
+            Event::Html(html) => {
+                self.push_text(html.trim_end_matches('\n'), self.style());
+            }
+            Event::FootnoteReference(name) => {
+                self.push_text(&format!("[^{name}]"), self.style());
+            }
+            Event::SoftBreak | Event::HardBreak => self.end_line(),
+            Event::Rule => {
+                self.start_block();
+                self.push_span(Span::from("---"));
+                self.end_block();
+            }
+            Event::TaskListMarker(checked) => {
+                let marker = if checked { "[x] " } else { "[ ] " };
+                self.push_span(Span::from(marker));
+            }
+        }
+    }
+
+    fn start(&mut self, tag: Tag) {
+        match tag {
+            Tag::Paragraph => self.start_block(),
+            Tag::Heading(level, _, _) => {
+                self.start_block();
+                self.styles.push(heading_style(level));
+            }
+            Tag::BlockQuote => {
+                self.start_block();
+                self.containers.push(Container::Quote);
+                self.styles
+                    .push(Style::default().add_modifier(Modifier::ITALIC));
+            }
+            Tag::CodeBlock(kind) => {
+                self.start_block();
+                let info = match kind {
+                    CodeBlockKind::Fenced(info) => info.trim().to_string(),
+                    CodeBlockKind::Indented => "".to_string(),
+                };
+                self.codeblock = Some((info, "".to_string()));
+            }
+            Tag::List(start) => {
+                if let Some(Container::Item { .. }) = self.containers.last() {
+                    self.end_line();
+                } else {
+                    self.start_block();
+                }
+                self.lists.push(start);
+            }
+            Tag::Item => {
+                self.end_line();
+                if self.needs_blank_line {
+                    self.start_block();
+                }
+
+                let marker = match self.lists.last_mut() {
+                    Some(Some(number)) => {
+                        *number += 1;
+                        format!("{}. ", *number - 1)
+                    }
+                    _ => "- ".to_string(),
+                };
+                self.containers.push(Container::Item {
+                    indent: marker.len(),
+                    marker: Some(marker),
+                });
+            }
+            Tag::FootnoteDefinition(name) => {
+                self.start_block();
+                self.push_text(&format!("[^{name}]: "), self.style());
+            }
+            Tag::Table(alignments) => {
+                self.start_block();
+                self.table = Some(Table {
+                    alignments,
+                    ..Table::default()
+                });
+            }
+            Tag::TableHead | Tag::TableRow | Tag::TableCell => {}
+            Tag::Emphasis => {
+                self.styles
+                    .push(Style::default().add_modifier(Modifier::ITALIC));
+            }
+            Tag::Strong => {
+                self.styles
+                    .push(Style::default().add_modifier(Modifier::BOLD));
+            }
+            Tag::Strikethrough => {
+                self.styles
+                    .push(Style::default().add_modifier(Modifier::CROSSED_OUT));
+            }
+            Tag::Link(_, dest, _) | Tag::Image(_, dest, _) => {
+                self.styles.push(Style {
+                    fg: Some(Color::Blue),
+                    add_modifier: Modifier::UNDERLINED,
+                    ..Style::default()
+                });
+                self.links.push((dest.to_string(), "".to_string()));
+            }
+        }
+    }
+
+    fn end(&mut self, tag: Tag) {
+        match tag {
+            Tag::Paragraph | Tag::FootnoteDefinition(_) => self.end_block(),
+            Tag::Heading(..) => {
+                self.styles.pop();
+                self.end_block();
+            }
+            Tag::BlockQuote => {
+                self.end_line();
+                self.styles.pop();
+                self.containers.pop();
+                self.needs_blank_line = true;
+            }
+            Tag::CodeBlock(_) => {
+                self.push_codeblock();
+                self.end_block();
+            }
+            Tag::List(_) => {
+                self.lists.pop();
+                if self.lists.is_empty() {
+                    self.end_block();
+                } else {
+                    self.end_line();
+                }
+            }
+            Tag::Item => {
+                self.end_line();
+                self.containers.pop();
+            }
+            Tag::Table(_) => {
+                self.push_table();
+                self.end_block();
+            }
+            Tag::TableHead | Tag::TableRow => {
+                if let Some(table) = self.table.as_mut() {
+                    let row = mem::take(&mut table.row);
+                    table.rows.push(row);
+                }
+            }
+            Tag::TableCell => {
+                if let Some(table) = self.table.as_mut() {
+                    let cell = mem::take(&mut table.cell);
+                    table.row.push(cell);
+                }
+            }
+            Tag::Emphasis | Tag::Strong | Tag::Strikethrough => {
+                self.styles.pop();
+            }
+            Tag::Link(..) | Tag::Image(..) => {
+                self.styles.pop();
+                if let Some((dest, text)) = self.links.pop() {
+                    if !dest.is_empty() && dest.trim_start_matches("mailto:") != text {
+                        self.push_span(Span::styled(
+                            format!(" ({dest})"),
+                            Style {
+                                fg: Some(Color::DarkGray),
+                                ..Style::default()
+                            },
+                        ));
+                    }
+                }
+            }
+        }
+    }
+
+    fn style(&self) -> Style {
+        return self
+            .styles
+            .iter()
+            .fold(Style::default(), |acc, style| return acc.patch(*style));
+    }
+
+    /// Spans that start a new line within block quotes and list items. List
+    /// markers are only written on the first line of an item.
+    fn prefix(&mut self) -> Vec<Span<'static>> {
+        return self
+            .containers
+            .iter_mut()
+            .map(|container| match container {
+                Container::Quote => {
+                    return Span::styled(
+                        "> ",
+                        Style {
+                            fg: Some(Color::DarkGray),
+                            ..Style::default()
+                        },
+                    );
+                }
+                Container::Item { marker, indent } => match marker.take() {
+                    Some(marker) => return Span::from(marker),
+                    None => return Span::from(" ".repeat(*indent)),
+                },
+            })
+            .collect();
+    }
+
+    fn push_span(&mut self, span: Span<'static>) {
+        if let Some(table) = self.table.as_mut() {
+            table.cell.push(span);
+            return;
+        }
+
+        if self.line.is_empty() {
+            self.line = self.prefix();
+        }
+        self.line.push(span);
+    }
+
+    fn push_text(&mut self, text: &str, style: Style) {
+        for (_dest, link_text) in self.links.iter_mut() {
+            *link_text += text;
+        }
+
+        for (idx, part) in text.split('\n').enumerate() {
+            if idx > 0 {
+                self.end_line();
+            }
+            if !part.is_empty() {
+                self.push_span(Span::styled(part.to_string(), style));
+            }
+        }
+    }
+
+    fn end_line(&mut self) {
+        if !self.line.is_empty() {
+            self.lines.push(mem::take(&mut self.line));
+        }
+    }
+
+    /// Separates blocks with a blank line.
+    fn start_block(&mut self) {
+        self.end_line();
+        if !self.needs_blank_line {
+            return;
+        }
+
+        let quotes = self
+            .containers
+            .iter()
+            .filter(|container| return matches!(container, Container::Quote))
+            .count();
+        let mut blank_line = vec![];
+        if quotes > 0 {
+            blank_line.push(Span::styled(
+                "> ".repeat(quotes).trim_end().to_string(),
+                Style {
+                    fg: Some(Color::DarkGray),
+                    ..Style::default()
+                },
+            ));
+        }
+
+        self.lines.push(blank_line);
+        self.needs_blank_line = false;
+    }
+
+    fn end_block(&mut self) {
+        self.end_line();
+        self.needs_blank_line = true;
+    }
+
+    /// Writes the code block being read with its fence and number, and
+    /// highlights its code.
+    fn push_codeblock(&mut self) {
+        let (info, code) = match self.codeblock.take() {
+            Some(codeblock) => codeblock,
+            None => return,
+        };
+
+        self.codeblock_counter += 1;
+        self.push_span(Span::from(format!("```{info}")));
+        self.push_span(Span::styled(
+            format!(" ({})", self.codeblock_counter),
+            Style {
+                fg: Some(Color::White),
+                ..Style::default()
+            },
+        ));
+        self.end_line();
+
+        let language = info.split_whitespace().next().unwrap_or_default();
+        let idx = self.codeblock_counter - self.codeblock_start - 1;
+        let highlighted = match self.highlight_cache.as_deref_mut() {
+            Some(cache) => cache.highlight(idx, language, &code, self.theme),
+            None => HighlightCache::default().highlight(idx, language, &code, self.theme),
+        };
+
+        for spans in highlighted {
+            self.push_span(Span::from(""));
+            for span in spans {
+                self.push_span(span);
+            }
+            self.end_line();
+        }
+
+        self.push_span(Span::from("```"));
+        self.end_line();
+    }
+
+    /// Writes the table being read with its columns lined up, and a rule
+    /// under the header row.
+    fn push_table(&mut self) {
+        let table = match self.table.take() {
+            Some(table) => table,
+            None => return,
+        };
+
+        let mut widths = vec![0; table.alignments.len()];
+        for row in table.rows.iter() {
+            for (idx, cell) in row.iter().enumerate() {
+                if idx < widths.len() {
+                    widths[idx] = widths[idx].max(spans_width(cell));
+                }
+            }
+        }
+
+        for (row_idx, row) in table.rows.into_iter().enumerate() {
+            let columns = row.len().min(widths.len());
+            for (idx, cell) in row.into_iter().take(columns).enumerate() {
+                if idx > 0 {
+                    self.push_span(Span::from(" | "));
+                }
+
+                let fill = widths[idx] - spans_width(&cell);
+                let (before, after) = match table.alignments[idx] {
+                    Alignment::Right => (fill, 0),
+                    Alignment::Center => (fill / 2, fill - fill / 2),
+                    Alignment::Left | Alignment::None => (0, fill),
+                };
+
+                self.push_span(Span::from(" ".repeat(before)));
+                for span in cell {
+                    self.push_span(span);
+                }
+                if idx < columns - 1 {
+                    self.push_span(Span::from(" ".repeat(after)));
+                }
+            }
+            self.end_line();
+
+            if row_idx == 0 {
+                let rule = widths
+                    .iter()
+                    .map(|width| return "-".repeat(*width))
+                    .collect::<Vec<String>>()
+                    .join("-|-");
+                self.push_span(Span::from(rule));
+                self.end_line();
+            }
+        }
+    }
+}
@@ -0,0 +1,211 @@
+use anyhow::Result;
+use ratatui::style::Modifier;
+use ratatui::text::Span;
+
+use super::MarkdownRenderer;
+use crate::domain::services::Themes;
+
+fn render_spans(text: &str) -> Result<Vec<Vec<Span<'static>>>> {
+    let theme = Themes::get("base16-seti", "")?;
+    return Ok(MarkdownRenderer::new(&theme, 0).render(text));
+}
+
+fn render(text: &str) -> Result<String> {
+    let lines = render_spans(text)?
+        .iter()
+        .map(|spans| {
+            return spans
+                .iter()
+                .map(|span| return span.content.to_string())
+                .collect::<String>();
+        })
+        .collect::<Vec<String>>();
+
+    return Ok(lines.join("\n"));
+}
+
+#[test]
+fn it_renders_headings_and_emphasis() -> Result<()> {
+    let text = "# Getting started\n\nThis is **bold**, *italic* and ~~gone~~.";
+    insta::assert_snapshot!(render(text)?, @r###"
+    Getting started
+
+    This is bold, italic and gone.
+    "###);
+
+    let lines = render_spans(text)?;
+    assert!(lines[0][0].style.add_modifier.contains(Modifier::BOLD));
+    let bold = lines[2]
+        .iter()
+        .find(|span| return span.content == "bold")
+        .unwrap();
+    assert!(bold.style.add_modifier.contains(Modifier::BOLD));
+    let italic = lines[2]
+        .iter()
+        .find(|span| return span.content == "italic")
+        .unwrap();
+    assert!(italic.style.add_modifier.contains(Modifier::ITALIC));
+
+    return Ok(());
+}
+
+#[test]
+fn it_keeps_line_breaks_within_paragraphs() -> Result<()> {
+    insta::assert_snapshot!(render("One line.\nAnother line.")?, @r###"
+    One line.
+    Another line.
+    "###);
+
+    return Ok(());
+}
+
+#[test]
+fn it_renders_inline_code_without_backticks() -> Result<()> {
+    insta::assert_snapshot!(render("Run `cargo test` first.")?, @"Run cargo test first.");
+
+    return Ok(());
+}
+
+#[test]
+fn it_renders_lists() -> Result<()> {
+    let text = r#"
+Steps:
+
+- Install it
+- Configure it
+  1. Pick a backend
+  2. Pick an editor
+- [x] Done
+"#;
+
+    insta::assert_snapshot!(render(text)?, @r###"
+    Steps:
+
+    - Install it
+    - Configure it
+      1. Pick a backend
+      2. Pick an editor
+    - [x] Done
+    "###);
+
+    return Ok(());
+}
+
+#[test]
+fn it_renders_numbered_lists_from_their_start() -> Result<()> {
+    insta::assert_snapshot!(render("3. Three\n4. Four\n\n   More about four.")?, @r###"
+    3. Three
+
+    4. Four
+
+       More about four.
+    "###);
+
+    return Ok(());
+}
+
+#[test]
+fn it_renders_block_quotes() -> Result<()> {
+    insta::assert_snapshot!(render("> Quoted\n> text.\n>\n> Again.\n\nAfter.")?, @r###"
+    > Quoted
+    > text.
+    >
+    > Again.
+
+    After.
+    "###);
+
+    return Ok(());
+}
+
+#[test]
+fn it_renders_links() -> Result<()> {
+    insta::assert_snapshot!(render("See [the docs](https://example.com/docs) or <https://example.com>.")?, @"See the docs (https://example.com/docs) or https://example.com.");
+
+    return Ok(());
+}
+
+#[test]
+fn it_renders_tables() -> Result<()> {
+    let text = r#"
+| Backend | Streams | Models |
+|:--------|:-------:|-------:|
+| ollama | yes | 12 |
+| **openai** | yes | 3 |
+"#;
+
+    insta::assert_snapshot!(render(text)?, @r###"
+    Backend | Streams | Models
+    --------|---------|-------
+    ollama  |   yes   |     12
+    openai  |   yes   |      3
+    "###);
+
+    return Ok(());
+}
+
+#[test]
+fn it_renders_numbered_codeblocks() -> Result<()> {
+    let text = "Try this:\n\n~~~rust src/main.rs\nfn main() {}\n~~~\n\n    cargo run";
+    let theme = Themes::get("base16-seti", "")?;
+    let lines = MarkdownRenderer::new(&theme, 2).render(text);
+    let text = lines
+        .iter()
+        .map(|spans| {
+            return spans
+                .iter()
+                .map(|span| return span.content.to_string())
+                .collect::<String>();
+        })
+        .collect::<Vec<String>>()
+        .join("\n");
+
+    insta::assert_snapshot!(text, @r###"
+    Try this:
+
+    ```rust src/main.rs (3)
+    fn main() {}
+    ```
+
+    ``` (4)
+    cargo run
+    ```
+    "###);
+
+    return Ok(());
+}
+
+#[test]
+fn it_renders_nested_fences_as_one_codeblock() -> Result<()> {
+    insta::assert_snapshot!(render("````markdown\n```rust\nfn main() {}\n```\n````")?, @r###"
+    ```markdown (1)
+    ```rust
+    fn main() {}
+    ```
+    ```
+    "###);
+
+    return Ok(());
+}
+
+#[test]
+fn it_renders_codeblocks_in_list_items() -> Result<()> {
+    insta::assert_snapshot!(render("1. Install it:\n\n   ```sh\n   cargo install oatmeal\n   ```\n2. Run it.")?, @r###"
+    1. Install it:
+
+       ```sh (1)
+       cargo install oatmeal
+       ```
+
+    2. Run it.
+    "###);
+
+    return Ok(());
+}
+
+#[test]
+fn it_renders_html_as_text() -> Result<()> {
+    insta::assert_snapshot!(render("Returns a Vec<String> of <b>names</b>.")?, @"Returns a Vec<String> of <b>names</b>.");
+
+    return Ok(());
+}
@@ -4,17 +4,31 @@ mod bubble;
 mod bubble_list;
 pub mod clipboard;
 mod code_blocks;
+mod diff_preview;
 pub mod events;
+mod highlight_cache;
+mod markdown;
+mod model_cache;
+mod model_picker;
+mod prompt_queue;
 mod scroll;
 mod sessions;
 mod syntaxes;
+mod text_width;
 mod themes;
 
 pub use app_state::*;
 pub use bubble::*;
 pub use bubble_list::*;
 pub use code_blocks::*;
+pub use diff_preview::*;
+pub use highlight_cache::*;
+pub use markdown::*;
+pub use model_cache::*;
+pub use model_picker::*;
+pub use prompt_queue::*;
 pub use scroll::*;
 pub use sessions::*;
 pub use syntaxes::*;
+pub use text_width::*;
 pub use themes::*;
@@ -0,0 +1,103 @@
+use std::collections::HashMap;
+use std::path;
+
+use anyhow::Result;
+use chrono::Utc;
+use serde::Deserialize;
+use serde::Serialize;
+use tokio::fs;
+
+use crate::domain::models::BackendBox;
+
+#[cfg(test)]
+#[path = "model_cache_test.rs"]
+mod tests;
+
+/// Seconds a backend's model list is reused before being fetched again.
+const MODEL_LIST_TTL_SECONDS: i64 = 60 * 60;
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+struct CachedModelList {
+    timestamp: i64,
+    models: Vec<String>,
+}
+
+/// Caches `Backend::list_models` results per backend and server on disk, so
+/// model switching and startup don't make a network call every time.
+#[derive(Clone)]
+pub struct ModelCache {
+    pub cache_file: path::PathBuf,
+    ttl_seconds: i64,
+}
+
+impl Default for ModelCache {
+    fn default() -> ModelCache {
+        let cache_file = dirs::cache_dir().unwrap().join("oatmeal/models.yaml");
+
+        return ModelCache::new(cache_file);
+    }
+}
+
+impl ModelCache {
+    pub fn new(cache_file: path::PathBuf) -> ModelCache {
+        return ModelCache {
+            cache_file,
+            ttl_seconds: MODEL_LIST_TTL_SECONDS,
+        };
+    }
+
+    async fn read(&self) -> HashMap<String, CachedModelList> {
+        if !self.cache_file.exists() {
+            return HashMap::new();
+        }
+
+        // A corrupt cache is thrown away rather than breaking model listing.
+        return match fs::read_to_string(&self.cache_file).await {
+            Ok(payload) => serde_yaml::from_str(&payload).unwrap_or_default(),
+            Err(_) => HashMap::new(),
+        };
+    }
+
+    async fn write(&self, entries: &HashMap<String, CachedModelList>) -> Result<()> {
+        if let Some(parent) = self.cache_file.parent() {
+            if !parent.exists() {
+                fs::create_dir_all(parent).await?;
+            }
+        }
+        fs::write(&self.cache_file, serde_yaml::to_string(entries)?).await?;
+
+        return Ok(());
+    }
+
+    /// Returns the sorted model list for a backend, using the cached list
+    /// unless it has expired or `refresh` is set.
+    pub async fn list_models(&self, backend: &BackendBox, refresh: bool) -> Result<Vec<String>> {
+        let key = format!("{}:{}", backend.name(), backend.url());
+        let mut entries = self.read().await;
+        let now = Utc::now().timestamp();
+
+        if !refresh {
+            if let Some(entry) = entries.get(&key) {
+                if now - entry.timestamp < self.ttl_seconds && !entry.models.is_empty() {
+                    return Ok(entry.models.clone());
+                }
+            }
+        }
+
+        let mut models = backend.list_models().await?;
+        models.sort();
+
+        entries.insert(
+            key,
+            CachedModelList {
+                timestamp: now,
+                models: models.clone(),
+            },
+        );
+        if let Err(err) = self.write(&entries).await {
+            tracing::warn!(error = ?err, "Failed to write model list cache");
+        }
+
+        return Ok(models);
+    }
+}
@@ -0,0 +1,160 @@
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use tokio::sync::mpsc;
+
+use super::ModelCache;
+use crate::domain::models::Backend;
+use crate::domain::models::BackendBox;
+use crate::domain::models::BackendCapabilities;
+use crate::domain::models::BackendName;
+use crate::domain::models::BackendPrompt;
+use crate::domain::models::Event;
+
+struct CountingBackend {
+    calls: Arc<AtomicUsize>,
+    url: String,
+}
+
+#[async_trait]
+impl Backend for CountingBackend {
+    fn name(&self) -> BackendName {
+        return BackendName::Ollama;
+    }
+
+    fn url(&self) -> String {
+        return self.url.to_string();
+    }
+
+    #[allow(clippy::implicit_return)]
+    async fn health_check(&self) -> Result<()> {
+        return Ok(());
+    }
+
+    #[allow(clippy::implicit_return)]
+    async fn capabilities(&self) -> BackendCapabilities {
+        return BackendCapabilities::default();
+    }
+
+    #[allow(clippy::implicit_return)]
+    async fn list_models(&self) -> Result<Vec<String>> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        return Ok(vec!["model-2".to_string(), "model-1".to_string()]);
+    }
+
+    #[allow(clippy::implicit_return)]
+    async fn get_completion<'a>(
+        &self,
+        _prompt: BackendPrompt,
+        _tx: &'a mpsc::UnboundedSender<Event>,
+    ) -> Result<()> {
+        return Ok(());
+    }
+}
+
+fn counting_backend(url: &str) -> (BackendBox, Arc<AtomicUsize>) {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let backend = Box::new(CountingBackend {
+        calls: calls.clone(),
+        url: url.to_string(),
+    });
+
+    return (backend, calls);
+}
+
+#[tokio::test]
+async fn it_caches_model_lists() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let cache = ModelCache::new(dir.path().join("models.yaml"));
+    let (backend, calls) = counting_backend("http://localhost:11434");
+
+    let models = cache.list_models(&backend, false).await?;
+    assert_eq!(models, vec!["model-1", "model-2"]);
+    assert!(cache.cache_file.exists());
+
+    let models = cache.list_models(&backend, false).await?;
+    assert_eq!(models, vec!["model-1", "model-2"]);
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_reads_model_lists_cached_by_another_instance() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let (backend, calls) = counting_backend("http://localhost:11434");
+
+    ModelCache::new(dir.path().join("models.yaml"))
+        .list_models(&backend, false)
+        .await?;
+    ModelCache::new(dir.path().join("models.yaml"))
+        .list_models(&backend, false)
+        .await?;
+
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_refreshes_model_lists() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let cache = ModelCache::new(dir.path().join("models.yaml"));
+    let (backend, calls) = counting_backend("http://localhost:11434");
+
+    cache.list_models(&backend, false).await?;
+    cache.list_models(&backend, true).await?;
+
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_expires_model_lists() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let mut cache = ModelCache::new(dir.path().join("models.yaml"));
+    cache.ttl_seconds = 0;
+    let (backend, calls) = counting_backend("http://localhost:11434");
+
+    cache.list_models(&backend, false).await?;
+    cache.list_models(&backend, false).await?;
+
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_ignores_corrupt_cache_files() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let cache_file = dir.path().join("models.yaml");
+    std::fs::write(&cache_file, "not: [valid")?;
+    let cache = ModelCache::new(cache_file);
+    let (backend, _calls) = counting_backend("http://localhost:11434");
+
+    let models = cache.list_models(&backend, false).await?;
+    assert_eq!(models, vec!["model-1", "model-2"]);
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_keeps_model_lists_apart_per_url() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let cache = ModelCache::new(dir.path().join("models.yaml"));
+    let (local_backend, local_calls) = counting_backend("http://localhost:11434");
+    let (remote_backend, remote_calls) = counting_backend("https://ollama.example.com");
+
+    cache.list_models(&local_backend, false).await?;
+    cache.list_models(&remote_backend, false).await?;
+    cache.list_models(&local_backend, false).await?;
+
+    assert_eq!(local_calls.load(Ordering::SeqCst), 1);
+    assert_eq!(remote_calls.load(Ordering::SeqCst), 1);
+
+    return Ok(());
+}
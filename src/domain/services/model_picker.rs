@@ -0,0 +1,254 @@
+use ratatui::prelude::*;
+use ratatui::widgets::Block;
+use ratatui::widgets::BorderType;
+use ratatui::widgets::Borders;
+use ratatui::widgets::Clear;
+use ratatui::widgets::List;
+use ratatui::widgets::ListItem;
+use ratatui::widgets::ListState;
+use ratatui::widgets::Padding;
+use ratatui::widgets::Paragraph;
+use ratatui::Frame;
+use tui_textarea::Input;
+use tui_textarea::Key;
+
+use crate::configuration::ModelAlias;
+use crate::domain::models::BackendCapabilities;
+use crate::domain::models::BackendName;
+
+#[cfg(test)]
+#[path = "model_picker_test.rs"]
+mod tests;
+
+/// A selectable row in the picker. `value` is what gets passed to `/model`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ModelPickerEntry {
+    pub label: String,
+    pub value: String,
+}
+
+fn is_word_start(chars: &[char], idx: usize) -> bool {
+    if idx == 0 {
+        return true;
+    }
+
+    return matches!(chars[idx - 1], '-' | '_' | ':' | '/' | '.' | ' ');
+}
+
+/// Scores how well `query` matches `candidate` as a case insensitive
+/// subsequence. Consecutive characters and matches at the start of words
+/// score higher. Returns `None` when not every query character is found.
+pub fn fuzzy_score(query: &str, candidate: &str) -> Option<i64> {
+    let query = query.to_lowercase().chars().collect::<Vec<char>>();
+    let chars = candidate.to_lowercase().chars().collect::<Vec<char>>();
+
+    let mut score = 0;
+    let mut query_idx = 0;
+    let mut last_match: Option<usize> = None;
+    for (idx, char) in chars.iter().enumerate() {
+        if query_idx == query.len() {
+            break;
+        }
+        if *char != query[query_idx] {
+            continue;
+        }
+
+        score += 1;
+        if last_match.is_some() && last_match == idx.checked_sub(1) {
+            score += 5;
+        }
+        if is_word_start(&chars, idx) {
+            score += 8;
+        }
+        last_match = Some(idx);
+        query_idx += 1;
+    }
+
+    if query_idx < query.len() {
+        return None;
+    }
+
+    // Prefer tighter candidates when scores are otherwise equal.
+    return Some(score * 100 - chars.len() as i64);
+}
+
+/// Centered area covering most of `rect`, for drawing popups over the chat.
+pub fn popup_rect(rect: Rect) -> Rect {
+    let width = (rect.width * 4 / 5).max(rect.width.min(40));
+    let height = (rect.height * 4 / 5).max(rect.height.min(12));
+
+    return Rect {
+        x: rect.x + (rect.width - width) / 2,
+        y: rect.y + (rect.height - height) / 2,
+        width,
+        height,
+    };
+}
+
+/// Popup listing models and aliases for the active backend, narrowed down by
+/// typing a fuzzy filter.
+pub struct ModelPicker {
+    backend_name: BackendName,
+    capabilities: BackendCapabilities,
+    entries: Vec<ModelPickerEntry>,
+    matches: Vec<usize>,
+    selected: usize,
+    pub filter: String,
+}
+
+impl ModelPicker {
+    pub fn new(
+        backend_name: BackendName,
+        models: Vec<String>,
+        aliases: Vec<ModelAlias>,
+        capabilities: BackendCapabilities,
+    ) -> ModelPicker {
+        let mut entries = aliases
+            .iter()
+            .map(|alias| {
+                return ModelPickerEntry {
+                    label: alias.format().trim_start_matches("- ").to_string(),
+                    value: alias.name.to_string(),
+                };
+            })
+            .collect::<Vec<ModelPickerEntry>>();
+
+        entries.extend(models.into_iter().map(|model| {
+            return ModelPickerEntry {
+                label: model.to_string(),
+                value: model,
+            };
+        }));
+
+        let mut picker = ModelPicker {
+            backend_name,
+            capabilities,
+            entries,
+            matches: vec![],
+            selected: 0,
+            filter: "".to_string(),
+        };
+        picker.update_matches();
+
+        return picker;
+    }
+
+    fn update_matches(&mut self) {
+        let mut scored = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, entry)| {
+                return fuzzy_score(&self.filter, &entry.label).map(|score| return (idx, score));
+            })
+            .collect::<Vec<(usize, i64)>>();
+
+        if !self.filter.is_empty() {
+            scored.sort_by(|a, b| return b.1.cmp(&a.1));
+        }
+
+        self.matches = scored.into_iter().map(|(idx, _)| return idx).collect();
+        self.selected = 0;
+    }
+
+    /// Edits the filter from a key press. Other keys are ignored.
+    pub fn input(&mut self, input: Input) {
+        match input.key {
+            Key::Char(char) => {
+                self.filter.push(char);
+            }
+            Key::Backspace => {
+                self.filter.pop();
+            }
+            _ => return,
+        }
+
+        self.update_matches();
+    }
+
+    pub fn push_str(&mut self, text: &str) {
+        self.filter += text.trim();
+        self.update_matches();
+    }
+
+    pub fn up(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    pub fn down(&mut self) {
+        if self.selected + 1 < self.matches.len() {
+            self.selected += 1;
+        }
+    }
+
+    /// Entries matching the filter, best match first.
+    pub fn visible(&self) -> Vec<&ModelPickerEntry> {
+        return self
+            .matches
+            .iter()
+            .map(|idx| return &self.entries[*idx])
+            .collect();
+    }
+
+    pub fn selected(&self) -> Option<&ModelPickerEntry> {
+        return self
+            .matches
+            .get(self.selected)
+            .map(|idx| return &self.entries[*idx]);
+    }
+
+    pub fn render(&self, frame: &mut Frame, rect: Rect) {
+        let popup = popup_rect(rect);
+        let capabilities = format!(
+            "Backend {} supports:\n{}",
+            self.backend_name,
+            self.capabilities.format()
+        );
+        let capabilities_len = (capabilities.lines().count() + 1).try_into().unwrap();
+
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_type(BorderType::Double)
+            .title("Models (type to filter, Enter to select, Esc to close)")
+            .padding(Padding::new(1, 1, 0, 0));
+        let inner = block.inner(popup);
+
+        frame.render_widget(Clear, popup);
+        frame.render_widget(block, popup);
+
+        let layout = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(vec![
+                Constraint::Length(2),
+                Constraint::Min(1),
+                Constraint::Length(capabilities_len),
+            ])
+            .split(inner);
+
+        frame.render_widget(
+            Paragraph::new(format!("> {}", self.filter)).bold(),
+            layout[0],
+        );
+
+        let items = self
+            .visible()
+            .into_iter()
+            .map(|entry| return ListItem::new(entry.label.to_string()))
+            .collect::<Vec<ListItem>>();
+
+        if items.is_empty() {
+            frame.render_widget(Paragraph::new("No matching models"), layout[1]);
+        } else {
+            let mut state = ListState::default().with_selected(Some(self.selected));
+            frame.render_stateful_widget(
+                List::new(items)
+                    .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+                    .highlight_symbol("> "),
+                layout[1],
+                &mut state,
+            );
+        }
+
+        frame.render_widget(Paragraph::new(capabilities), layout[2]);
+    }
+}
@@ -0,0 +1,138 @@
+use tui_textarea::Input;
+use tui_textarea::Key;
+
+use super::fuzzy_score;
+use super::ModelPicker;
+use crate::configuration::ModelAlias;
+use crate::domain::models::BackendCapabilities;
+use crate::domain::models::BackendName;
+
+fn picker() -> ModelPicker {
+    let models = vec![
+        "codellama:latest".to_string(),
+        "llama2:13b".to_string(),
+        "mistral:7b".to_string(),
+    ];
+    let aliases = vec![ModelAlias {
+        name: "fast".to_string(),
+        backend: Some(BackendName::Ollama),
+        model: "mistral:7b".to_string(),
+        ..ModelAlias::default()
+    }];
+
+    return ModelPicker::new(
+        BackendName::Ollama,
+        models,
+        aliases,
+        BackendCapabilities::default(),
+    );
+}
+
+fn type_filter(picker: &mut ModelPicker, text: &str) {
+    for char in text.chars() {
+        picker.input(Input {
+            key: Key::Char(char),
+            ..Input::default()
+        });
+    }
+}
+
+fn visible_values(picker: &ModelPicker) -> Vec<String> {
+    return picker
+        .visible()
+        .iter()
+        .map(|entry| return entry.value.to_string())
+        .collect();
+}
+
+#[test]
+fn it_scores_subsequences() {
+    assert!(fuzzy_score("cl", "codellama:latest").is_some());
+    assert!(fuzzy_score("CLL", "codellama:latest").is_some());
+    assert!(fuzzy_score("", "codellama:latest").is_some());
+    assert!(fuzzy_score("cla", "codellama").is_some());
+    assert!(fuzzy_score("xyz", "codellama:latest").is_none());
+    assert!(fuzzy_score("lc", "codellama").is_none());
+}
+
+#[test]
+fn it_scores_consecutive_and_word_start_matches_higher() {
+    let consecutive = fuzzy_score("lla", "llama2").unwrap();
+    let scattered = fuzzy_score("lla", "lxlxa").unwrap();
+    assert!(consecutive > scattered);
+
+    let word_start = fuzzy_score("13", "llama2:13b").unwrap();
+    let mid_word = fuzzy_score("13", "x213").unwrap();
+    assert!(word_start > mid_word);
+}
+
+#[test]
+fn it_lists_aliases_before_models() {
+    let picker = picker();
+
+    assert_eq!(
+        visible_values(&picker),
+        vec!["fast", "codellama:latest", "llama2:13b", "mistral:7b"]
+    );
+    assert_eq!(picker.visible()[0].label, "fast: mistral:7b (ollama)");
+}
+
+#[test]
+fn it_filters_and_ranks_entries() {
+    let mut picker = picker();
+    type_filter(&mut picker, "llama");
+
+    assert_eq!(
+        visible_values(&picker),
+        vec!["llama2:13b", "codellama:latest", "fast"]
+    );
+    assert_eq!(picker.selected().unwrap().value, "llama2:13b");
+}
+
+#[test]
+fn it_removes_filter_characters() {
+    let mut picker = picker();
+    type_filter(&mut picker, "mis");
+    assert_eq!(visible_values(&picker), vec!["mistral:7b", "fast"]);
+
+    picker.input(Input {
+        key: Key::Backspace,
+        ..Input::default()
+    });
+    picker.input(Input {
+        key: Key::Backspace,
+        ..Input::default()
+    });
+    picker.input(Input {
+        key: Key::Backspace,
+        ..Input::default()
+    });
+    assert_eq!(picker.filter, "");
+    assert_eq!(picker.visible().len(), 4);
+}
+
+#[test]
+fn it_moves_selection_within_bounds() {
+    let mut picker = picker();
+    picker.up();
+    assert_eq!(picker.selected().unwrap().value, "fast");
+
+    picker.down();
+    picker.down();
+    picker.down();
+    picker.down();
+    assert_eq!(picker.selected().unwrap().value, "mistral:7b");
+
+    picker.up();
+    assert_eq!(picker.selected().unwrap().value, "llama2:13b");
+}
+
+#[test]
+fn it_has_no_selection_without_matches() {
+    let mut picker = picker();
+    picker.push_str("gpt-4\n");
+
+    assert_eq!(picker.filter, "gpt-4");
+    assert!(picker.visible().is_empty());
+    assert!(picker.selected().is_none());
+}
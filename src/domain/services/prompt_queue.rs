@@ -0,0 +1,115 @@
+use std::collections::VecDeque;
+
+use anyhow::anyhow;
+use anyhow::Result;
+use ratatui::prelude::Rect;
+use ratatui::widgets::Block;
+use ratatui::widgets::BorderType;
+use ratatui::widgets::Borders;
+use ratatui::widgets::Padding;
+use ratatui::widgets::Paragraph;
+use ratatui::Frame;
+
+#[cfg(test)]
+#[path = "prompt_queue_test.rs"]
+mod tests;
+
+/// Max number of queued prompts shown before the list is cut off.
+const MAX_VISIBLE_PROMPTS: usize = 5;
+
+/// Prompts submitted while waiting on the backend, dispatched in order as
+/// each completion finishes.
+#[derive(Default)]
+pub struct PromptQueue {
+    prompts: VecDeque<String>,
+    pub notice: Option<String>,
+}
+
+impl PromptQueue {
+    pub fn push(&mut self, prompt: String) {
+        self.notice = None;
+        self.prompts.push_back(prompt);
+    }
+
+    /// Pops the oldest queued prompt to be sent to the backend.
+    pub fn next(&mut self) -> Option<String> {
+        return self.prompts.pop_front();
+    }
+
+    /// Removes a queued prompt by its 1-based position, defaulting to the most
+    /// recently queued prompt when no position is given.
+    pub fn take(&mut self, position: Option<&str>) -> Result<String> {
+        self.notice = None;
+        if self.prompts.is_empty() {
+            return Err(anyhow!("There are no queued prompts."));
+        }
+
+        let mut idx = self.prompts.len() - 1;
+        if let Some(entry) = position {
+            let parsed = entry.trim().parse::<usize>().unwrap_or(0);
+            if parsed < 1 || parsed > self.prompts.len() {
+                return Err(anyhow!(format!("Queued prompt {entry} is not valid")));
+            }
+            idx = parsed - 1;
+        }
+
+        return Ok(self.prompts.remove(idx).unwrap());
+    }
+
+    pub fn clear(&mut self) {
+        self.notice = None;
+        self.prompts.clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.prompts.is_empty() && self.notice.is_none();
+    }
+
+    pub fn len(&self) -> usize {
+        return self.prompts.len();
+    }
+
+    /// Rows required to render the queue, including borders.
+    pub fn height(&self) -> u16 {
+        if self.is_empty() {
+            return 0;
+        }
+
+        let rows = self.len().clamp(1, MAX_VISIBLE_PROMPTS);
+        return (rows + 2).try_into().unwrap();
+    }
+
+    pub fn render(&self, frame: &mut Frame, rect: Rect) {
+        let mut lines = self
+            .prompts
+            .iter()
+            .take(MAX_VISIBLE_PROMPTS)
+            .enumerate()
+            .map(|(idx, prompt)| {
+                let first_line = prompt.split('\n').next().unwrap_or_default();
+                return format!("({}) {first_line}", idx + 1);
+            })
+            .collect::<Vec<String>>();
+
+        if self.len() > MAX_VISIBLE_PROMPTS {
+            let last = lines.len() - 1;
+            lines[last] = format!("... and {} more", self.len() - last);
+        }
+
+        let title = match &self.notice {
+            Some(notice) => notice.to_string(),
+            None => "Queued prompts (/queue edit N, /queue cancel N, /queue clear)".to_string(),
+        };
+
+        frame.render_widget(
+            Paragraph::new(lines.join("\n")).block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_type(BorderType::Double)
+                    .title(title)
+                    .padding(Padding::new(1, 1, 0, 0)),
+            ),
+            rect,
+        );
+    }
+}
@@ -0,0 +1,58 @@
+use super::PromptQueue;
+
+fn queue_with(prompts: Vec<&str>) -> PromptQueue {
+    let mut queue = PromptQueue::default();
+    for prompt in prompts {
+        queue.push(prompt.to_string());
+    }
+    return queue;
+}
+
+#[test]
+fn it_is_empty_by_default() {
+    let queue = PromptQueue::default();
+    assert!(queue.is_empty());
+    assert_eq!(queue.height(), 0);
+}
+
+#[test]
+fn it_dispatches_in_order() {
+    let mut queue = queue_with(vec!["First", "Second"]);
+    assert_eq!(queue.next(), Some("First".to_string()));
+    assert_eq!(queue.next(), Some("Second".to_string()));
+    assert_eq!(queue.next(), None);
+}
+
+#[test]
+fn it_takes_last_prompt_by_default() {
+    let mut queue = queue_with(vec!["First", "Second"]);
+    assert_eq!(queue.take(None).unwrap(), "Second");
+    assert_eq!(queue.len(), 1);
+}
+
+#[test]
+fn it_takes_prompt_by_position() {
+    let mut queue = queue_with(vec!["First", "Second", "Third"]);
+    assert_eq!(queue.take(Some("1")).unwrap(), "First");
+    assert_eq!(queue.next(), Some("Second".to_string()));
+}
+
+#[test]
+fn it_fails_to_take_invalid_position() {
+    let mut queue = queue_with(vec!["First"]);
+    let err = queue.take(Some("0")).unwrap_err();
+    insta::assert_snapshot!(err.to_string(), @"Queued prompt 0 is not valid");
+}
+
+#[test]
+fn it_fails_to_take_from_empty_queue() {
+    let mut queue = PromptQueue::default();
+    let err = queue.take(None).unwrap_err();
+    insta::assert_snapshot!(err.to_string(), @"There are no queued prompts.");
+}
+
+#[test]
+fn it_caps_height() {
+    let queue = queue_with(vec!["1", "2", "3", "4", "5", "6", "7"]);
+    assert_eq!(queue.height(), 7);
+}
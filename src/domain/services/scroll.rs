@@ -56,6 +56,20 @@ impl Scroll {
         self.scrollbar_state.last();
     }
 
+    /// Scrolls just enough for lines `start` up to `end` to be in view,
+    /// favouring `start` when they don't all fit.
+    pub fn show(&mut self, start: usize, end: usize) {
+        if end > self.position + self.viewport_length {
+            self.position = end.saturating_sub(self.viewport_length);
+        }
+        if start < self.position {
+            self.position = start;
+        }
+
+        self.position = self.position.min(self.get_position_as_if_last());
+        self.scrollbar_state = self.scrollbar_state.position(self.position);
+    }
+
     pub fn set_state(&mut self, list_length: usize, viewport_length: usize) {
         self.list_length = list_length;
         self.viewport_length = viewport_length;
@@ -95,7 +95,8 @@ impl Sessions {
         }
 
         let payload = fs::read_to_string(file_path).await?;
-        let session: Session = serde_yaml::from_str(&payload)?;
+        let mut session: Session = serde_yaml::from_str(&payload)?;
+        session.migrate();
 
         return Ok(session);
     }
@@ -0,0 +1,44 @@
+#[cfg(test)]
+#[path = "text_width_test.rs"]
+mod tests;
+
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+/// Measures text by the columns it takes up in a terminal rather than its
+/// bytes, so CJK characters and emoji count as two columns and combining
+/// marks as none. Widths match how ratatui lays out spans, which keeps
+/// bubble borders lined up with what's drawn.
+pub struct TextWidth {}
+
+impl TextWidth {
+    pub fn of(text: &str) -> usize {
+        return text.width();
+    }
+
+    /// Splits text at the last grapheme that fits within `width` columns,
+    /// never breaking up a grapheme cluster. The head always takes at least
+    /// one grapheme so callers wrapping text keep making progress.
+    pub fn split_at(text: &str, width: usize) -> (&str, &str) {
+        let mut head_width = 0;
+        for (idx, grapheme) in text.grapheme_indices(true) {
+            head_width += grapheme.width();
+            if head_width > width && idx > 0 {
+                return text.split_at(idx);
+            }
+        }
+
+        return (text, "");
+    }
+
+    /// Shortens text to fit within `width` columns, ending it with `...`
+    /// when it had to be cut.
+    pub fn truncate(text: &str, width: usize) -> String {
+        if TextWidth::of(text) <= width {
+            return text.to_string();
+        }
+
+        let (head, _) = TextWidth::split_at(text, width.saturating_sub(3));
+        return format!("{head}...");
+    }
+}
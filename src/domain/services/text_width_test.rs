@@ -0,0 +1,37 @@
+use super::TextWidth;
+
+#[test]
+fn it_measures_columns_rather_than_bytes() {
+    assert_eq!(TextWidth::of("hello"), 5);
+    assert_eq!(TextWidth::of("こんにちは"), 10);
+    assert_eq!(TextWidth::of("╭──╮"), 4);
+    assert_eq!(TextWidth::of("café"), 4);
+    assert_eq!(TextWidth::of("cafe\u{301}"), 4);
+    assert_eq!(TextWidth::of("🦀"), 2);
+}
+
+#[test]
+fn it_splits_at_width() {
+    assert_eq!(TextWidth::split_at("hello world", 5), ("hello", " world"));
+    assert_eq!(TextWidth::split_at("こんにちは", 5), ("こん", "にちは"));
+    assert_eq!(TextWidth::split_at("short", 10), ("short", ""));
+}
+
+#[test]
+fn it_splits_without_breaking_graphemes() {
+    assert_eq!(TextWidth::split_at("cafe\u{301}s", 4), ("cafe\u{301}", "s"));
+    assert_eq!(TextWidth::split_at("🦀🦀", 3), ("🦀", "🦀"));
+}
+
+#[test]
+fn it_splits_off_at_least_one_grapheme() {
+    assert_eq!(TextWidth::split_at("世界", 1), ("世", "界"));
+    assert_eq!(TextWidth::split_at("", 1), ("", ""));
+}
+
+#[test]
+fn it_truncates() {
+    assert_eq!(TextWidth::truncate("hello", 5), "hello");
+    assert_eq!(TextWidth::truncate("hello world", 8), "hello...");
+    assert_eq!(TextWidth::truncate("日本語のテキスト", 9), "日本語...");
+}
@@ -0,0 +1,250 @@
+#[cfg(test)]
+#[path = "azureopenai_test.rs"]
+mod tests;
+
+use std::time::Duration;
+
+use anyhow::bail;
+use anyhow::Result;
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde::Serialize;
+use tokio::sync::mpsc;
+
+use super::openai::stream_completion;
+use super::openai::MessageRequest;
+use crate::configuration::Config;
+use crate::configuration::ConfigKey;
+use crate::configuration::ModelAliases;
+use crate::domain::models::Author;
+use crate::domain::models::Backend;
+use crate::domain::models::BackendCapabilities;
+use crate::domain::models::BackendName;
+use crate::domain::models::BackendPrompt;
+use crate::domain::models::BackendResponse;
+use crate::domain::models::BackendStatusError;
+use crate::domain::models::Event;
+
+/// Listing deployments was dropped from the data plane after this version, so
+/// it's pinned regardless of the configured API version.
+const DEPLOYMENTS_API_VERSION: &str = "2022-12-01";
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct Deployment {
+    id: String,
+    status: Option<String>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct DeploymentListResponse {
+    data: Vec<Deployment>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct CompletionRequest {
+    messages: Vec<MessageRequest>,
+    stream: bool,
+}
+
+pub struct AzureOpenAI {
+    url: String,
+    token: String,
+    api_version: String,
+    timeout: String,
+}
+
+impl Default for AzureOpenAI {
+    fn default() -> AzureOpenAI {
+        let resource = Config::get(ConfigKey::AzureOpenAiResource);
+        let mut url = "".to_string();
+        if !resource.is_empty() {
+            url = format!("https://{resource}.openai.azure.com");
+        }
+
+        return AzureOpenAI {
+            url,
+            token: Config::get(ConfigKey::AzureOpenAiToken),
+            api_version: Config::get(ConfigKey::AzureOpenAiAPIVersion),
+            timeout: Config::get(ConfigKey::BackendHealthCheckTimeout),
+        };
+    }
+}
+
+impl AzureOpenAI {
+    fn deployments_url(&self) -> String {
+        return format!(
+            "{url}/openai/deployments?api-version={DEPLOYMENTS_API_VERSION}",
+            url = self.url
+        );
+    }
+
+    fn completions_url(&self, deployment: &str) -> String {
+        return format!(
+            "{url}/openai/deployments/{deployment}/chat/completions?api-version={api_version}",
+            url = self.url,
+            api_version = self.api_version
+        );
+    }
+}
+
+#[async_trait]
+impl Backend for AzureOpenAI {
+    fn name(&self) -> BackendName {
+        return BackendName::AzureOpenAI;
+    }
+
+    fn url(&self) -> String {
+        return self.url.to_string();
+    }
+
+    #[allow(clippy::implicit_return)]
+    async fn health_check(&self) -> Result<()> {
+        if self.url.is_empty() {
+            bail!("Azure OpenAI resource is not defined");
+        }
+        if self.token.is_empty() {
+            bail!("Azure OpenAI token is not defined");
+        }
+
+        let res = reqwest::Client::new()
+            .get(self.deployments_url())
+            .header("api-key", &self.token)
+            .timeout(Duration::from_millis(self.timeout.parse::<u64>()?))
+            .send()
+            .await;
+
+        if res.is_err() {
+            tracing::error!(error = ?res.unwrap_err(), "Azure OpenAI is not reachable");
+            bail!("Azure OpenAI is not reachable");
+        }
+
+        let status = res.unwrap().status().as_u16();
+        if status == 401 || status == 403 {
+            tracing::error!(status = status, "Azure OpenAI rejected the token");
+            bail!("Azure OpenAI rejected the token");
+        }
+        if status >= 400 {
+            tracing::error!(status = status, "Azure OpenAI health check failed");
+            bail!("Azure OpenAI health check failed");
+        }
+
+        return Ok(());
+    }
+
+    #[allow(clippy::implicit_return)]
+    async fn capabilities(&self) -> BackendCapabilities {
+        return BackendCapabilities {
+            system_prompt: true,
+            images: true,
+            tool_calls: true,
+            streaming: true,
+            stateful_context: true,
+            max_context_length: None,
+        };
+    }
+
+    #[allow(clippy::implicit_return)]
+    async fn list_models(&self) -> Result<Vec<String>> {
+        let res = reqwest::Client::new()
+            .get(self.deployments_url())
+            .header("api-key", &self.token)
+            .send()
+            .await?
+            .json::<DeploymentListResponse>()
+            .await?;
+
+        let mut deployments: Vec<String> = res
+            .data
+            .iter()
+            .filter(|deployment| {
+                return deployment.status.is_none()
+                    || deployment.status == Some("succeeded".to_string());
+            })
+            .map(|deployment| {
+                return deployment.id.to_string();
+            })
+            .collect();
+
+        deployments.sort();
+
+        return Ok(deployments);
+    }
+
+    #[allow(clippy::implicit_return)]
+    async fn get_completion<'a>(
+        &self,
+        prompt: BackendPrompt,
+        tx: &'a mpsc::UnboundedSender<Event>,
+    ) -> Result<()> {
+        let mut messages: Vec<MessageRequest> = vec![];
+        if !prompt.backend_context.is_empty() {
+            messages = serde_json::from_str(&prompt.backend_context)?;
+        }
+        messages.push(MessageRequest {
+            role: "user".to_string(),
+            content: prompt.text,
+        });
+
+        let req = CompletionRequest {
+            messages: messages.clone(),
+            stream: true,
+        };
+
+        let res = reqwest::Client::new()
+            .post(self.completions_url(&Config::get(ConfigKey::Model)))
+            .header("api-key", &self.token)
+            .json(&ModelAliases::apply_params(&req, None)?)
+            .send()
+            .await?;
+
+        if !res.status().is_success() {
+            tracing::error!(
+                status = res.status().as_u16(),
+                "Failed to make completion request to Azure OpenAI"
+            );
+            return Err(BackendStatusError::new(
+                "Failed to make completion request to Azure OpenAI",
+                res.status().as_u16(),
+            )
+            .into());
+        }
+
+        let last_message = stream_completion(res, tx).await?;
+
+        messages.push(MessageRequest {
+            role: "assistant".to_string(),
+            content: last_message,
+        });
+
+        let msg = BackendResponse {
+            author: Author::Model,
+            text: "".to_string(),
+            done: true,
+            context: Some(serde_json::to_string(&messages)?),
+        };
+        tx.send(Event::BackendPromptResponse(msg))?;
+
+        return Ok(());
+    }
+
+    fn interrupted_context(
+        &self,
+        prompt: &BackendPrompt,
+        partial_text: &str,
+    ) -> Result<Option<String>> {
+        let mut messages: Vec<MessageRequest> = vec![];
+        if !prompt.backend_context.is_empty() {
+            messages = serde_json::from_str(&prompt.backend_context)?;
+        }
+        messages.push(MessageRequest {
+            role: "user".to_string(),
+            content: prompt.text.to_string(),
+        });
+        messages.push(MessageRequest {
+            role: "assistant".to_string(),
+            content: partial_text.to_string(),
+        });
+
+        return Ok(Some(serde_json::to_string(&messages)?));
+    }
+}
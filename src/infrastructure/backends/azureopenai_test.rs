@@ -0,0 +1,186 @@
+use anyhow::bail;
+use anyhow::Result;
+use mockito::Matcher;
+use test_utils::insta_snapshot;
+use tokio::sync::mpsc;
+
+use super::AzureOpenAI;
+use super::Deployment;
+use super::DeploymentListResponse;
+use super::MessageRequest;
+use crate::configuration::Config;
+use crate::configuration::ConfigKey;
+use crate::domain::models::Author;
+use crate::domain::models::Backend;
+use crate::domain::models::BackendPrompt;
+use crate::domain::models::BackendResponse;
+use crate::domain::models::Event;
+
+impl AzureOpenAI {
+    fn with_url(url: String) -> AzureOpenAI {
+        return AzureOpenAI {
+            url,
+            token: "abc".to_string(),
+            api_version: "2024-02-01".to_string(),
+            timeout: "200".to_string(),
+        };
+    }
+}
+
+fn to_res(action: Option<Event>) -> Result<BackendResponse> {
+    let act = match action.unwrap() {
+        Event::BackendPromptResponse(res) => res,
+        _ => bail!("Wrong type from recv"),
+    };
+
+    return Ok(act);
+}
+
+#[tokio::test]
+async fn it_successfully_health_checks() {
+    let mut server = mockito::Server::new();
+    let mock = server
+        .mock("GET", "/openai/deployments")
+        .match_query(Matcher::UrlEncoded(
+            "api-version".to_string(),
+            "2022-12-01".to_string(),
+        ))
+        .match_header("api-key", "abc")
+        .with_status(200)
+        .create();
+
+    let backend = AzureOpenAI::with_url(server.url());
+    let res = backend.health_check().await;
+
+    assert!(res.is_ok());
+    mock.assert();
+}
+
+#[tokio::test]
+async fn it_fails_health_checks_with_rejected_token() {
+    let mut server = mockito::Server::new();
+    let mock = server
+        .mock("GET", "/openai/deployments")
+        .match_query(Matcher::Any)
+        .with_status(401)
+        .create();
+
+    let backend = AzureOpenAI::with_url(server.url());
+    let res = backend.health_check().await;
+
+    assert!(res.is_err());
+    assert_eq!(
+        res.unwrap_err().to_string(),
+        "Azure OpenAI rejected the token"
+    );
+    mock.assert();
+}
+
+#[tokio::test]
+async fn it_fails_health_checks_without_resource() {
+    let backend = AzureOpenAI::with_url("".to_string());
+    let res = backend.health_check().await;
+
+    assert!(res.is_err());
+}
+
+#[tokio::test]
+async fn it_lists_deployments() -> Result<()> {
+    let body = serde_json::to_string(&DeploymentListResponse {
+        data: vec![
+            Deployment {
+                id: "second".to_string(),
+                status: Some("succeeded".to_string()),
+            },
+            Deployment {
+                id: "first".to_string(),
+                status: None,
+            },
+            Deployment {
+                id: "creating".to_string(),
+                status: Some("running".to_string()),
+            },
+        ],
+    })?;
+
+    let mut server = mockito::Server::new();
+    let mock = server
+        .mock("GET", "/openai/deployments")
+        .match_query(Matcher::Any)
+        .match_header("api-key", "abc")
+        .with_status(200)
+        .with_body(body)
+        .create();
+
+    let backend = AzureOpenAI::with_url(server.url());
+    let res = backend.list_models().await?;
+    mock.assert();
+
+    assert_eq!(res, vec!["first".to_string(), "second".to_string()]);
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_gets_completions() -> Result<()> {
+    let body = [
+        r#"data: {"choices":[],"prompt_filter_results":[{"prompt_index":0}]}"#,
+        r#"data: {"choices":[{"delta":{"content":"Hello "},"finish_reason":null}]}"#,
+        r#"data: {"choices":[{"delta":{"content":"World"},"finish_reason":null}]}"#,
+        r#"data: {"choices":[{"delta":{},"finish_reason":"stop"}]}"#,
+        "data: [DONE]",
+    ]
+    .join("\n\n");
+
+    let prompt = BackendPrompt {
+        text: "Say hi to the world".to_string(),
+        backend_context: serde_json::to_string(&vec![MessageRequest {
+            role: "assistant".to_string(),
+            content: "How may I help you?".to_string(),
+        }])?,
+    };
+
+    Config::set(ConfigKey::Model, "model-1");
+
+    let mut server = mockito::Server::new();
+    let mock = server
+        .mock("POST", "/openai/deployments/model-1/chat/completions")
+        .match_query(Matcher::UrlEncoded(
+            "api-version".to_string(),
+            "2024-02-01".to_string(),
+        ))
+        .match_header("api-key", "abc")
+        .with_status(200)
+        .with_body(body)
+        .create();
+
+    let (tx, mut rx) = mpsc::unbounded_channel::<Event>();
+
+    let backend = AzureOpenAI::with_url(server.url());
+    backend.get_completion(prompt, &tx).await?;
+
+    mock.assert();
+
+    let first_recv = to_res(rx.recv().await)?;
+    let second_recv = to_res(rx.recv().await)?;
+    let third_recv = to_res(rx.recv().await)?;
+
+    assert_eq!(first_recv.author, Author::Model);
+    assert_eq!(first_recv.text, "Hello ".to_string());
+    assert!(!first_recv.done);
+    assert_eq!(first_recv.context, None);
+
+    assert_eq!(second_recv.author, Author::Model);
+    assert_eq!(second_recv.text, "World".to_string());
+    assert!(!second_recv.done);
+    assert_eq!(second_recv.context, None);
+
+    assert_eq!(third_recv.author, Author::Model);
+    assert!(third_recv.text.is_empty());
+    assert!(third_recv.done);
+    insta_snapshot(|| {
+        insta::assert_toml_snapshot!(third_recv.context);
+    });
+
+    return Ok(());
+}
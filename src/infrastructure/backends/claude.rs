@@ -18,11 +18,14 @@ use tokio_util::io::StreamReader;
 
 use crate::configuration::Config;
 use crate::configuration::ConfigKey;
+use crate::configuration::ModelAliases;
 use crate::domain::models::Author;
 use crate::domain::models::Backend;
+use crate::domain::models::BackendCapabilities;
 use crate::domain::models::BackendName;
 use crate::domain::models::BackendPrompt;
 use crate::domain::models::BackendResponse;
+use crate::domain::models::BackendStatusError;
 use crate::domain::models::Event;
 
 fn convert_err(err: reqwest::Error) -> std::io::Error {
@@ -95,6 +98,10 @@ impl Backend for Claude {
         return BackendName::Claude;
     }
 
+    fn url(&self) -> String {
+        return self.url.to_string();
+    }
+
     #[allow(clippy::implicit_return)]
     async fn health_check(&self) -> Result<()> {
         if self.url.is_empty() {
@@ -130,6 +137,18 @@ impl Backend for Claude {
         return Ok(());
     }
 
+    #[allow(clippy::implicit_return)]
+    async fn capabilities(&self) -> BackendCapabilities {
+        return BackendCapabilities {
+            system_prompt: true,
+            images: true,
+            tool_calls: true,
+            streaming: true,
+            stateful_context: true,
+            max_context_length: None,
+        };
+    }
+
     #[allow(clippy::implicit_return)]
     async fn list_models(&self) -> Result<Vec<String>> {
         let backup = vec![
@@ -195,7 +214,7 @@ impl Backend for Claude {
             .header("content-type", "application/json")
             .header("anthropic-version", "2023-06-01")
             .header("anthropic-beta", "messages-2023-12-15")
-            .json(&req)
+            .json(&ModelAliases::apply_params(&req, None)?)
             .send()
             .await?;
 
@@ -204,7 +223,11 @@ impl Backend for Claude {
                 status = res.status().as_u16(),
                 "Failed to make completion request to Claude"
             );
-            bail!("Failed to make completion request to Claude");
+            return Err(BackendStatusError::new(
+                "Failed to make completion request to Claude",
+                res.status().as_u16(),
+            )
+            .into());
         }
 
         let stream = res.bytes_stream().map_err(convert_err);
@@ -265,4 +288,32 @@ impl Backend for Claude {
 
         return Ok(());
     }
+
+    fn interrupted_context(
+        &self,
+        prompt: &BackendPrompt,
+        partial_text: &str,
+    ) -> Result<Option<String>> {
+        // Claude rejects empty assistant turns, and a user turn on its own
+        // would leave two in a row once the next prompt is sent. So nothing
+        // is kept when cancelled before the first token arrives.
+        if partial_text.trim().is_empty() {
+            return Ok(Some(prompt.backend_context.to_string()));
+        }
+
+        let mut messages: Vec<MessageRequest> = vec![];
+        if !prompt.backend_context.is_empty() {
+            messages = serde_json::from_str(&prompt.backend_context)?;
+        }
+        messages.push(MessageRequest {
+            role: "user".to_string(),
+            content: prompt.text.to_string(),
+        });
+        messages.push(MessageRequest {
+            role: "assistant".to_string(),
+            content: partial_text.to_string(),
+        });
+
+        return Ok(Some(serde_json::to_string(&messages)?));
+    }
 }
@@ -159,3 +159,55 @@ async fn it_gets_completions() -> Result<()> {
 
     return Ok(());
 }
+
+#[test]
+fn it_keeps_partial_responses_in_interrupted_context() -> Result<()> {
+    let prompt = BackendPrompt {
+        text: "Say hi to the world".to_string(),
+        backend_context: "".to_string(),
+    };
+
+    let backend = Claude::with_url("http://localhost:8080".to_string());
+    let context = backend
+        .interrupted_context(&prompt, "Hello Wo")?
+        .unwrap_or_default();
+    let res: Vec<MessageRequest> = serde_json::from_str(&context)?;
+
+    assert_eq!(res.len(), 2);
+    assert_eq!(res[0].role, "user");
+    assert_eq!(res[1].role, "assistant");
+    assert_eq!(res[1].content, "Hello Wo");
+
+    return Ok(());
+}
+
+#[test]
+fn it_skips_empty_responses_in_interrupted_context() -> Result<()> {
+    let backend_context = serde_json::to_string(&vec![
+        MessageRequest {
+            role: "user".to_string(),
+            content: "Hi".to_string(),
+        },
+        MessageRequest {
+            role: "assistant".to_string(),
+            content: "Hello!".to_string(),
+        },
+    ])?;
+    let prompt = BackendPrompt {
+        text: "Say hi to the world".to_string(),
+        backend_context: backend_context.to_string(),
+    };
+
+    let backend = Claude::with_url("http://localhost:8080".to_string());
+    let context = backend.interrupted_context(&prompt, " \n")?;
+    assert_eq!(context, Some(backend_context));
+
+    let prompt = BackendPrompt {
+        text: "Say hi to the world".to_string(),
+        backend_context: "".to_string(),
+    };
+    let context = backend.interrupted_context(&prompt, "")?;
+    assert_eq!(context, Some("".to_string()));
+
+    return Ok(());
+}
@@ -16,11 +16,14 @@ use tokio_util::io::StreamReader;
 
 use crate::configuration::Config;
 use crate::configuration::ConfigKey;
+use crate::configuration::ModelAliases;
 use crate::domain::models::Author;
 use crate::domain::models::Backend;
+use crate::domain::models::BackendCapabilities;
 use crate::domain::models::BackendName;
 use crate::domain::models::BackendPrompt;
 use crate::domain::models::BackendResponse;
+use crate::domain::models::BackendStatusError;
 use crate::domain::models::Event;
 
 fn convert_err(err: reqwest::Error) -> std::io::Error {
@@ -93,6 +96,10 @@ impl Backend for Gemini {
         return BackendName::Gemini;
     }
 
+    fn url(&self) -> String {
+        return self.url.to_string();
+    }
+
     #[allow(clippy::implicit_return)]
     async fn health_check(&self) -> Result<()> {
         if self.url.is_empty() {
@@ -129,6 +136,18 @@ impl Backend for Gemini {
         return Ok(());
     }
 
+    #[allow(clippy::implicit_return)]
+    async fn capabilities(&self) -> BackendCapabilities {
+        return BackendCapabilities {
+            system_prompt: true,
+            images: true,
+            tool_calls: true,
+            streaming: true,
+            stateful_context: true,
+            max_context_length: None,
+        };
+    }
+
     #[allow(clippy::implicit_return)]
     async fn list_models(&self) -> Result<Vec<String>> {
         let res = reqwest::Client::new()
@@ -186,7 +205,7 @@ impl Backend for Gemini {
                 model = Config::get(ConfigKey::Model),
                 key = self.token,
             ))
-            .json(&req)
+            .json(&ModelAliases::apply_params(&req, Some("generationConfig"))?)
             .send()
             .await?;
 
@@ -195,10 +214,14 @@ impl Backend for Gemini {
                 status = res.status().as_u16(),
                 "Failed to make completion request to Gemini"
             );
-            bail!(format!(
-                "Failed to make completion request to Gemini, {}",
-                res.status().as_u16()
-            ));
+            return Err(BackendStatusError::new(
+                &format!(
+                    "Failed to make completion request to Gemini, {}",
+                    res.status().as_u16()
+                ),
+                res.status().as_u16(),
+            )
+            .into());
         }
         let stream = res.bytes_stream().map_err(convert_err);
         let mut lines_reader = StreamReader::new(stream).lines();
@@ -246,4 +269,32 @@ impl Backend for Gemini {
 
         return Ok(());
     }
+
+    fn interrupted_context(
+        &self,
+        prompt: &BackendPrompt,
+        partial_text: &str,
+    ) -> Result<Option<String>> {
+        // Gemini rejects model turns without any text, as well as two user
+        // turns in a row. So nothing is kept when cancelled before the first
+        // token arrives.
+        if partial_text.trim().is_empty() {
+            return Ok(Some(prompt.backend_context.to_string()));
+        }
+
+        let mut contents: Vec<Content> = vec![];
+        if !prompt.backend_context.is_empty() {
+            contents = serde_json::from_str(&prompt.backend_context)?;
+        }
+        contents.push(Content {
+            role: "user".to_string(),
+            parts: vec![ContentParts::Text(prompt.text.to_string())],
+        });
+        contents.push(Content {
+            role: "model".to_string(),
+            parts: vec![ContentParts::Text(partial_text.to_string())],
+        });
+
+        return Ok(Some(serde_json::to_string(&contents)?));
+    }
 }
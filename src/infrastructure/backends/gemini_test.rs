@@ -193,3 +193,27 @@ async fn it_gets_completions() -> Result<()> {
 
     return Ok(());
 }
+
+#[test]
+fn it_skips_empty_responses_in_interrupted_context() -> Result<()> {
+    let backend_context = serde_json::to_string(&vec![
+        Content {
+            role: "user".to_string(),
+            parts: vec![ContentParts::Text("Hi".to_string())],
+        },
+        Content {
+            role: "model".to_string(),
+            parts: vec![ContentParts::Text("Hello!".to_string())],
+        },
+    ])?;
+    let prompt = BackendPrompt {
+        text: "Say hi to the world".to_string(),
+        backend_context: backend_context.to_string(),
+    };
+
+    let backend = Gemini::with_url("http://localhost:8080".to_string());
+    let context = backend.interrupted_context(&prompt, "")?;
+    assert_eq!(context, Some(backend_context));
+
+    return Ok(());
+}
@@ -13,17 +13,23 @@ use futures::stream::TryStreamExt;
 use itertools::Itertools;
 use serde::Deserialize;
 use serde::Serialize;
+use serde_json::json;
+use serde_json::Map;
+use serde_json::Value;
 use tokio::io::AsyncBufReadExt;
 use tokio::sync::mpsc;
 use tokio_util::io::StreamReader;
 
 use crate::configuration::Config;
 use crate::configuration::ConfigKey;
+use crate::configuration::ModelAliases;
 use crate::domain::models::Author;
 use crate::domain::models::Backend;
+use crate::domain::models::BackendCapabilities;
 use crate::domain::models::BackendName;
 use crate::domain::models::BackendPrompt;
 use crate::domain::models::BackendResponse;
+use crate::domain::models::BackendStatusError;
 use crate::domain::models::Event;
 
 fn convert_err(err: reqwest::Error) -> std::io::Error {
@@ -40,8 +46,33 @@ struct OpenAPIJSONResponse {
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct InputSchemaProperty {
+    #[serde(rename = "type")]
+    _type: Option<String>,
+    items: Option<Box<InputSchemaProperty>>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct InputSchema {
+    #[serde(rename = "type")]
+    _type: Option<String>,
+    #[serde(default)]
+    properties: HashMap<String, InputSchemaProperty>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct HistoryEntry {
+    human: String,
+    ai: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 struct CompletionRequest {
-    input: HashMap<String, String>,
+    input: Value,
+    /// Runnable config, which carries model alias params as `configurable`
+    /// fields.
+    #[serde(default, skip_serializing_if = "Map::is_empty")]
+    config: Map<String, Value>,
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -51,8 +82,49 @@ struct CompletionResponse {
     content: Option<String>,
 }
 
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct InvokeResponse {
+    output: Value,
+}
+
+/// Runnables either output plain strings, or message objects with a `content`
+/// field.
+fn output_to_text(output: Value) -> Result<Option<String>> {
+    if let Value::String(text) = output {
+        return Ok(Some(text));
+    }
+
+    let res: CompletionResponse = serde_json::from_value(output)?;
+    if let Some(status_code) = res.status_code {
+        if status_code >= 400 {
+            return Err(anyhow!(res.message.unwrap_or_default()));
+        }
+    }
+
+    return Ok(res.content);
+}
+
+/// Sessions saved before history was sent hold `not-supported` as their
+/// context, so anything that isn't history starts the conversation afresh
+/// rather than failing every prompt.
+fn history_from_context(backend_context: &str) -> Vec<HistoryEntry> {
+    if backend_context.is_empty() {
+        return vec![];
+    }
+
+    match serde_json::from_str(backend_context) {
+        Ok(history) => return history,
+        Err(err) => {
+            tracing::warn!(error = ?err, "Ignoring LangChain context that isn't chat history");
+            return vec![];
+        }
+    }
+}
+
 pub struct LangChain {
     url: String,
+    input_key: String,
+    history_key: String,
     timeout: String,
 }
 
@@ -60,17 +132,211 @@ impl Default for LangChain {
     fn default() -> LangChain {
         return LangChain {
             url: Config::get(ConfigKey::LangChainURL),
+            input_key: Config::get(ConfigKey::LangChainInputKey),
+            history_key: Config::get(ConfigKey::LangChainHistoryKey),
             timeout: Config::get(ConfigKey::BackendHealthCheckTimeout),
         };
     }
 }
 
+impl LangChain {
+    async fn get_input_schema(&self, model: &str) -> InputSchema {
+        // Fetched at startup, so don't hold it up on an unresponsive server.
+        let mut req =
+            reqwest::Client::new().get(format!("{url}/{model}/input_schema", url = self.url));
+        if let Ok(timeout) = self.timeout.parse::<u64>() {
+            req = req.timeout(Duration::from_millis(timeout));
+        }
+        let res = req.send().await;
+
+        if let Ok(schema_res) = res {
+            if schema_res.status().is_success() {
+                if let Ok(schema) = schema_res.json::<InputSchema>().await {
+                    return schema;
+                }
+            }
+        }
+
+        tracing::warn!(model = model, "Failed to fetch LangChain input schema");
+        return InputSchema::default();
+    }
+
+    /// Whether the runnable exposes a stream endpoint, or only the invoke
+    /// fallback. Assumes streaming when the server can't be reached, since
+    /// that's what completions try first.
+    async fn supports_streaming(&self, model: &str) -> bool {
+        let mut req = reqwest::Client::new().get(format!("{url}/openapi.json", url = self.url));
+        if let Ok(timeout) = self.timeout.parse::<u64>() {
+            req = req.timeout(Duration::from_millis(timeout));
+        }
+
+        if let Ok(res) = req.send().await {
+            if let Ok(openapi) = res.json::<OpenAPIJSONResponse>().await {
+                return openapi.paths.contains_key(&format!("/{model}/stream"))
+                    || !openapi.paths.contains_key(&format!("/{model}/invoke"));
+            }
+        }
+
+        tracing::warn!(model = model, "Failed to fetch LangChain OpenAPI spec");
+        return true;
+    }
+
+    /// The key the prompt is sent as. Uses the configured key when set,
+    /// otherwise the first well known string property from the schema.
+    fn input_key(&self, schema: &InputSchema) -> String {
+        if !self.input_key.is_empty() {
+            return self.input_key.to_string();
+        }
+
+        let string_keys = schema
+            .properties
+            .iter()
+            .filter(|(_, property)| return property._type.as_deref() == Some("string"))
+            .map(|(key, _)| return key.to_string())
+            .sorted()
+            .collect::<Vec<String>>();
+
+        for key in ["question", "input", "query", "prompt", "text"] {
+            if string_keys.contains(&key.to_string()) {
+                return key.to_string();
+            }
+        }
+
+        if let Some(key) = string_keys.first() {
+            return key.to_string();
+        }
+
+        return "question".to_string();
+    }
+
+    /// The key chat history is sent as, if the runnable accepts history.
+    fn history_key<'a>(
+        &self,
+        schema: &'a InputSchema,
+    ) -> Option<(String, Option<&'a InputSchemaProperty>)> {
+        if !self.history_key.is_empty() {
+            return Some((
+                self.history_key.to_string(),
+                schema.properties.get(&self.history_key),
+            ));
+        }
+
+        for key in ["chat_history", "history", "messages"] {
+            if let Some(property) = schema.properties.get(key) {
+                if property._type.as_deref() == Some("array") {
+                    return Some((key.to_string(), Some(property)));
+                }
+            }
+        }
+
+        return None;
+    }
+
+    fn build_input(&self, schema: &InputSchema, prompt: &BackendPrompt) -> Result<Value> {
+        if schema._type.as_deref() == Some("string") {
+            return Ok(Value::String(prompt.text.to_string()));
+        }
+
+        let mut input = Map::new();
+        input.insert(
+            self.input_key(schema),
+            Value::String(prompt.text.to_string()),
+        );
+
+        if let Some((history_key, property)) = self.history_key(schema) {
+            let history = history_from_context(&prompt.backend_context);
+
+            // Runnables typed with tuples expect `[human, ai]` pairs, otherwise
+            // fallback to a list of messages.
+            let is_tuples = property
+                .and_then(|e| return e.items.as_ref())
+                .and_then(|e| return e._type.as_deref())
+                == Some("array");
+
+            let history_value = if is_tuples {
+                history
+                    .iter()
+                    .map(|entry| return json!([entry.human, entry.ai]))
+                    .collect::<Vec<Value>>()
+            } else {
+                history
+                    .iter()
+                    .flat_map(|entry| {
+                        return vec![
+                            json!({ "type": "human", "content": entry.human }),
+                            json!({ "type": "ai", "content": entry.ai }),
+                        ];
+                    })
+                    .collect::<Vec<Value>>()
+            };
+
+            input.insert(history_key, Value::Array(history_value));
+        }
+
+        return Ok(Value::Object(input));
+    }
+
+    fn build_context(&self, prompt: &BackendPrompt, response: &str) -> Result<String> {
+        let mut history = history_from_context(&prompt.backend_context);
+        history.push(HistoryEntry {
+            human: prompt.text.to_string(),
+            ai: response.to_string(),
+        });
+
+        return Ok(serde_json::to_string(&history)?);
+    }
+
+    /// Fallback for runnables that don't support streaming.
+    async fn invoke(
+        &self,
+        model: &str,
+        req: &CompletionRequest,
+        tx: &mpsc::UnboundedSender<Event>,
+    ) -> Result<String> {
+        let res = reqwest::Client::new()
+            .post(format!("{url}/{model}/invoke", url = self.url))
+            .json(req)
+            .send()
+            .await?;
+
+        if !res.status().is_success() {
+            tracing::error!(
+                status = res.status().as_u16(),
+                "Failed to make invoke request to LangChain"
+            );
+            return Err(BackendStatusError::new(
+                "Failed to make completion request to LangChain",
+                res.status().as_u16(),
+            )
+            .into());
+        }
+
+        let ores = res.json::<InvokeResponse>().await?;
+        let text = output_to_text(ores.output)?.unwrap_or_default();
+        if !text.is_empty() {
+            let msg = BackendResponse {
+                author: Author::Model,
+                text: text.to_string(),
+                done: false,
+                context: None,
+            };
+            tx.send(Event::BackendPromptResponse(msg))?;
+        }
+
+        return Ok(text);
+    }
+}
+
 #[async_trait]
 impl Backend for LangChain {
     fn name(&self) -> BackendName {
         return BackendName::LangChain;
     }
 
+    fn url(&self) -> String {
+        return self.url.to_string();
+    }
+
     #[allow(clippy::implicit_return)]
     async fn health_check(&self) -> Result<()> {
         if self.url.is_empty() {
@@ -97,6 +363,21 @@ impl Backend for LangChain {
         return Ok(());
     }
 
+    #[allow(clippy::implicit_return)]
+    async fn capabilities(&self) -> BackendCapabilities {
+        let model = Config::get(ConfigKey::Model);
+        let schema = self.get_input_schema(&model).await;
+
+        return BackendCapabilities {
+            system_prompt: false,
+            images: false,
+            tool_calls: false,
+            streaming: self.supports_streaming(&model).await,
+            stateful_context: self.history_key(&schema).is_some(),
+            max_context_length: None,
+        };
+    }
+
     #[allow(clippy::implicit_return)]
     async fn list_models(&self) -> Result<Vec<String>> {
         let res = reqwest::Client::new()
@@ -110,11 +391,13 @@ impl Backend for LangChain {
             .paths
             .keys()
             .filter_map(|url_path| {
-                if !url_path.ends_with("/stream") || url_path.contains("{config_hash}") {
+                if url_path.contains("{config_hash}") {
                     return None;
                 }
 
-                let model = url_path.replace("/stream", "");
+                let model = url_path
+                    .strip_suffix("/stream")
+                    .or_else(|| return url_path.strip_suffix("/invoke"))?;
                 return Some(model[1..model.len()].to_string());
             })
             .unique()
@@ -131,75 +414,84 @@ impl Backend for LangChain {
         prompt: BackendPrompt,
         tx: &'a mpsc::UnboundedSender<Event>,
     ) -> Result<()> {
-        let mut input = HashMap::new();
-        // TODO consider making the key configurable.
-        input.insert("question".to_string(), prompt.text);
-
-        let req = CompletionRequest { input };
+        let model = Config::get(ConfigKey::Model);
+        let schema = self.get_input_schema(&model).await;
+        let config =
+            ModelAliases::apply_params(&Map::<String, Value>::new(), Some("configurable"))?;
+        let req = CompletionRequest {
+            input: self.build_input(&schema, &prompt)?,
+            config: serde_json::from_value(config)?,
+        };
 
         let res = reqwest::Client::new()
-            .post(format!(
-                "{url}/{model}/stream",
-                url = self.url,
-                model = Config::get(ConfigKey::Model)
-            ))
+            .post(format!("{url}/{model}/stream", url = self.url))
             .json(&req)
             .send()
             .await?;
 
-        if !res.status().is_success() {
-            tracing::error!(
-                status = res.status().as_u16(),
-                "Failed to make completion request to LangChain"
-            );
-            bail!("Failed to make completion request to LangChain");
-        }
+        let status = res.status().as_u16();
+        let mut last_message = "".to_string();
+        if status == 404 || status == 405 {
+            last_message = self.invoke(&model, &req, tx).await?;
+        } else {
+            if !res.status().is_success() {
+                tracing::error!(
+                    status = status,
+                    "Failed to make completion request to LangChain"
+                );
+                return Err(BackendStatusError::new(
+                    "Failed to make completion request to LangChain",
+                    status,
+                )
+                .into());
+            }
 
-        let stream = res.bytes_stream().map_err(convert_err);
-        let mut lines_reader = StreamReader::new(stream).lines();
+            let stream = res.bytes_stream().map_err(convert_err);
+            let mut lines_reader = StreamReader::new(stream).lines();
 
-        while let Ok(line) = lines_reader.next_line().await {
-            if line.is_none() {
-                break;
-            }
-            let mut cleaned_line = line.unwrap().trim().to_string();
-            if !cleaned_line.starts_with("data:") {
-                continue;
-            }
-            cleaned_line = cleaned_line.split_off(5).trim().to_string();
-            let ores: CompletionResponse = serde_json::from_str(&cleaned_line).unwrap();
+            while let Ok(line) = lines_reader.next_line().await {
+                if line.is_none() {
+                    break;
+                }
+                let mut cleaned_line = line.unwrap().trim().to_string();
+                if !cleaned_line.starts_with("data:") {
+                    continue;
+                }
+                cleaned_line = cleaned_line.split_off(5).trim().to_string();
+                let output: Value = serde_json::from_str(&cleaned_line)?;
 
-            if let Some(status_code) = ores.status_code {
-                if status_code >= 400 {
-                    return Err(anyhow!(ores.message.unwrap()));
+                let text = output_to_text(output)?.unwrap_or_default();
+                if text.is_empty() {
+                    continue;
                 }
-            }
 
-            if ores.content.is_none() {
-                continue;
+                last_message += &text;
+                let msg = BackendResponse {
+                    author: Author::Model,
+                    text,
+                    done: false,
+                    context: None,
+                };
+                tx.send(Event::BackendPromptResponse(msg))?;
             }
-            let text = ores.content.unwrap();
-            if text.is_empty() {
-                continue;
-            }
-
-            let msg = BackendResponse {
-                author: Author::Model,
-                text,
-                done: false,
-                context: None,
-            };
-            tx.send(Event::BackendPromptResponse(msg))?;
         }
 
         let msg = BackendResponse {
             author: Author::Model,
             text: "".to_string(),
             done: true,
-            context: Some("not-supported".to_string()),
+            context: Some(self.build_context(&prompt, &last_message)?),
         };
         tx.send(Event::BackendPromptResponse(msg))?;
 
         return Ok(());
     }
+
+    fn interrupted_context(
+        &self,
+        prompt: &BackendPrompt,
+        partial_text: &str,
+    ) -> Result<Option<String>> {
+        return Ok(Some(self.build_context(prompt, partial_text)?));
+    }
 }
@@ -4,7 +4,12 @@ use anyhow::bail;
 use anyhow::Result;
 use tokio::sync::mpsc;
 
+use super::CompletionRequest;
 use super::CompletionResponse;
+use super::HistoryEntry;
+use super::InputSchema;
+use super::InputSchemaProperty;
+use super::InvokeResponse;
 use super::LangChain;
 use crate::configuration::Config;
 use crate::configuration::ConfigKey;
@@ -20,6 +25,8 @@ impl LangChain {
     fn with_url(url: String) -> LangChain {
         return LangChain {
             url,
+            input_key: "".to_string(),
+            history_key: "".to_string(),
             timeout: "200".to_string(),
         };
     }
@@ -70,6 +77,7 @@ async fn it_lists_models() -> Result<()> {
     paths.insert("/model-1/stream".to_string(), Empty {});
     paths.insert("/model-2/stream".to_string(), Empty {});
     paths.insert("/model-2/{config_hash}/stream".to_string(), Empty {});
+    paths.insert("/model-3/invoke".to_string(), Empty {});
     paths.insert("/other".to_string(), Empty {});
     let body = serde_json::to_string(&OpenAPIJSONResponse { paths })?;
 
@@ -84,7 +92,39 @@ async fn it_lists_models() -> Result<()> {
     let res = backend.list_models().await?;
     mock.assert();
 
-    assert_eq!(res, vec!["model-1".to_string(), "model-2".to_string()]);
+    assert_eq!(
+        res,
+        vec![
+            "model-1".to_string(),
+            "model-2".to_string(),
+            "model-3".to_string()
+        ]
+    );
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_reports_streaming_from_available_endpoints() -> Result<()> {
+    Config::set(ConfigKey::Model, "model-1");
+
+    let mut paths = HashMap::new();
+    paths.insert("/model-1/invoke".to_string(), Empty {});
+    paths.insert("/model-2/stream".to_string(), Empty {});
+    let body = serde_json::to_string(&OpenAPIJSONResponse { paths })?;
+
+    let mut server = mockito::Server::new();
+    let mock = server
+        .mock("GET", "/openapi.json")
+        .with_status(200)
+        .with_body(body)
+        .expect(2)
+        .create();
+
+    let backend = LangChain::with_url(server.url());
+    assert!(!backend.supports_streaming("model-1").await);
+    assert!(backend.supports_streaming("model-2").await);
+    mock.assert();
 
     return Ok(());
 }
@@ -150,7 +190,212 @@ async fn it_gets_completions() -> Result<()> {
     assert_eq!(third_recv.author, Author::Model);
     assert!(third_recv.text.is_empty());
     assert!(third_recv.done);
-    assert_eq!(third_recv.context, Some("not-supported".to_string()));
+    let history: Vec<HistoryEntry> = serde_json::from_str(&third_recv.context.unwrap())?;
+    assert_eq!(
+        history,
+        vec![HistoryEntry {
+            human: "Say hi to the world".to_string(),
+            ai: "Hello World".to_string(),
+        }]
+    );
+
+    return Ok(());
+}
+
+fn schema_with_history(items_type: &str) -> InputSchema {
+    let mut properties = HashMap::new();
+    properties.insert(
+        "input".to_string(),
+        InputSchemaProperty {
+            _type: Some("string".to_string()),
+            items: None,
+        },
+    );
+    properties.insert(
+        "chat_history".to_string(),
+        InputSchemaProperty {
+            _type: Some("array".to_string()),
+            items: Some(Box::new(InputSchemaProperty {
+                _type: Some(items_type.to_string()),
+                items: None,
+            })),
+        },
+    );
+
+    return InputSchema {
+        _type: Some("object".to_string()),
+        properties,
+    };
+}
+
+fn prompt_with_history() -> Result<BackendPrompt> {
+    return Ok(BackendPrompt {
+        text: "And again".to_string(),
+        backend_context: serde_json::to_string(&vec![HistoryEntry {
+            human: "Say hi".to_string(),
+            ai: "Hi!".to_string(),
+        }])?,
+    });
+}
+
+#[test]
+fn it_builds_input_with_message_history() -> Result<()> {
+    let backend = LangChain::with_url("http://localhost".to_string());
+    let input = backend.build_input(&schema_with_history("object"), &prompt_with_history()?)?;
+
+    insta::assert_snapshot!(serde_json::to_string(&input)?, @r###"{"chat_history":[{"content":"Say hi","type":"human"},{"content":"Hi!","type":"ai"}],"input":"And again"}"###);
+    return Ok(());
+}
+
+#[test]
+fn it_builds_input_with_tuple_history() -> Result<()> {
+    let backend = LangChain::with_url("http://localhost".to_string());
+    let input = backend.build_input(&schema_with_history("array"), &prompt_with_history()?)?;
+
+    insta::assert_snapshot!(serde_json::to_string(&input)?, @r###"{"chat_history":[["Say hi","Hi!"]],"input":"And again"}"###);
+    return Ok(());
+}
+
+#[test]
+fn it_builds_input_without_history_from_old_sessions() -> Result<()> {
+    let backend = LangChain::with_url("http://localhost".to_string());
+    for backend_context in ["not-supported", "{\"unexpected\": true}"] {
+        let prompt = BackendPrompt {
+            text: "And again".to_string(),
+            backend_context: backend_context.to_string(),
+        };
+        let input = backend.build_input(&schema_with_history("object"), &prompt)?;
+        assert_eq!(
+            serde_json::to_string(&input)?,
+            r#"{"chat_history":[],"input":"And again"}"#
+        );
+
+        let context = backend.build_context(&prompt, "Hi!")?;
+        assert_eq!(context, r#"[{"human":"And again","ai":"Hi!"}]"#);
+    }
+
+    return Ok(());
+}
+
+#[test]
+fn it_sends_params_as_configurable_fields() -> Result<()> {
+    let mut req = CompletionRequest {
+        input: serde_json::json!("Say hi"),
+        config: serde_json::Map::new(),
+    };
+    insta::assert_snapshot!(serde_json::to_string(&req)?, @r###"{"input":"Say hi"}"###);
+
+    req.config = serde_json::from_str(r#"{"configurable": {"temperature": 0.2}}"#)?;
+    insta::assert_snapshot!(serde_json::to_string(&req)?, @r###"{"input":"Say hi","config":{"configurable":{"temperature":0.2}}}"###);
+
+    return Ok(());
+}
+
+#[test]
+fn it_builds_input_with_configured_key() -> Result<()> {
+    let mut backend = LangChain::with_url("http://localhost".to_string());
+    backend.input_key = "topic".to_string();
+    let input = backend.build_input(&InputSchema::default(), &prompt_with_history()?)?;
+
+    insta::assert_snapshot!(serde_json::to_string(&input)?, @r###"{"topic":"And again"}"###);
+    return Ok(());
+}
+
+#[test]
+fn it_builds_input_for_string_schema() -> Result<()> {
+    let backend = LangChain::with_url("http://localhost".to_string());
+    let schema = InputSchema {
+        _type: Some("string".to_string()),
+        properties: HashMap::new(),
+    };
+    let input = backend.build_input(&schema, &prompt_with_history()?)?;
+
+    insta::assert_snapshot!(serde_json::to_string(&input)?, @r###""And again""###);
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_gets_completions_with_history_and_string_output() -> Result<()> {
+    Config::set(ConfigKey::Model, "model-1");
+
+    let body = ["data: \"Hello \"", "data: \"again\"", ""].join("\n");
+
+    let mut server = mockito::Server::new();
+    let schema_mock = server
+        .mock("GET", "/model-1/input_schema")
+        .with_status(200)
+        .with_body(serde_json::to_string(&schema_with_history("object"))?)
+        .create();
+    let mock = server
+        .mock("POST", "/model-1/stream")
+        .match_body(mockito::Matcher::PartialJsonString(
+            r#"{"input": {"input": "And again"}}"#.to_string(),
+        ))
+        .with_status(200)
+        .with_body(body)
+        .create();
+
+    let (tx, mut rx) = mpsc::unbounded_channel::<Event>();
+
+    let backend = LangChain::with_url(server.url());
+    backend.get_completion(prompt_with_history()?, &tx).await?;
+
+    schema_mock.assert();
+    mock.assert();
+
+    let first_recv = to_res(rx.recv().await)?;
+    let second_recv = to_res(rx.recv().await)?;
+    let third_recv = to_res(rx.recv().await)?;
+
+    assert_eq!(first_recv.text, "Hello ".to_string());
+    assert_eq!(second_recv.text, "again".to_string());
+    assert!(third_recv.done);
+
+    let history: Vec<HistoryEntry> = serde_json::from_str(&third_recv.context.unwrap())?;
+    assert_eq!(history.len(), 2);
+    assert_eq!(history[1].ai, "Hello again");
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_falls_back_to_invoke() -> Result<()> {
+    Config::set(ConfigKey::Model, "model-1");
+
+    let body = serde_json::to_string(&InvokeResponse {
+        output: serde_json::json!({ "content": "Hello World" }),
+    })?;
+
+    let mut server = mockito::Server::new();
+    let stream_mock = server
+        .mock("POST", "/model-1/stream")
+        .with_status(404)
+        .create();
+    let invoke_mock = server
+        .mock("POST", "/model-1/invoke")
+        .with_status(200)
+        .with_body(body)
+        .create();
+
+    let prompt = BackendPrompt {
+        text: "Say hi to the world".to_string(),
+        backend_context: "".to_string(),
+    };
+
+    let (tx, mut rx) = mpsc::unbounded_channel::<Event>();
+
+    let backend = LangChain::with_url(server.url());
+    backend.get_completion(prompt, &tx).await?;
+
+    stream_mock.assert();
+    invoke_mock.assert();
+
+    let first_recv = to_res(rx.recv().await)?;
+    let second_recv = to_res(rx.recv().await)?;
+
+    assert_eq!(first_recv.text, "Hello World".to_string());
+    assert!(!first_recv.done);
+    assert!(second_recv.done);
 
     return Ok(());
 }
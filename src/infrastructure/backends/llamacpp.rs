@@ -0,0 +1,482 @@
+#[cfg(test)]
+#[path = "llamacpp_test.rs"]
+mod tests;
+
+use std::path;
+use std::time::Duration;
+
+use anyhow::bail;
+use anyhow::Result;
+use async_trait::async_trait;
+use futures::stream::TryStreamExt;
+use serde::Deserialize;
+use serde::Serialize;
+use tokio::fs;
+use tokio::io::AsyncBufReadExt;
+use tokio::sync::mpsc;
+use tokio_util::io::StreamReader;
+
+use crate::configuration::Config;
+use crate::configuration::ConfigKey;
+use crate::configuration::ModelAliases;
+use crate::domain::models::Author;
+use crate::domain::models::Backend;
+use crate::domain::models::BackendCapabilities;
+use crate::domain::models::BackendName;
+use crate::domain::models::BackendPrompt;
+use crate::domain::models::BackendResponse;
+use crate::domain::models::BackendStatusError;
+use crate::domain::models::Event;
+
+fn convert_err(err: reqwest::Error) -> std::io::Error {
+    let err_msg = err.to_string();
+    return std::io::Error::new(std::io::ErrorKind::Interrupted, err_msg);
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct HealthResponse {
+    status: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct GenerationSettings {
+    model: Option<String>,
+    n_ctx: Option<usize>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct PropsResponse {
+    model_path: Option<String>,
+    default_generation_settings: Option<GenerationSettings>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct MessageRequest {
+    role: String,
+    content: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct ApplyTemplateRequest {
+    messages: Vec<MessageRequest>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct ApplyTemplateResponse {
+    prompt: String,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct CompletionRequest {
+    prompt: String,
+    stream: bool,
+    cache_prompt: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id_slot: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    grammar: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    json_schema: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    n_probs: Option<u32>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    stop: Vec<String>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct CompletionResponse {
+    content: String,
+    stop: bool,
+    #[serde(alias = "slot_id")]
+    id_slot: Option<i64>,
+    completion_probabilities: Option<serde_json::Value>,
+}
+
+/// One of the most likely tokens llama.cpp considered. Older servers report
+/// `prob`, and newer ones `logprob`.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct TokenProbability {
+    #[serde(default, alias = "token")]
+    tok_str: String,
+    prob: Option<f64>,
+    logprob: Option<f64>,
+}
+
+impl TokenProbability {
+    fn probability(&self) -> f64 {
+        if let Some(prob) = self.prob {
+            return prob;
+        }
+
+        return self.logprob.map(f64::exp).unwrap_or_default();
+    }
+}
+
+/// A generated token, along with the tokens that were considered for it.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct TokenProbabilities {
+    #[serde(default, alias = "token")]
+    content: String,
+    #[serde(default, alias = "top_logprobs")]
+    probs: Vec<TokenProbability>,
+}
+
+/// Lists the tokens considered for each generated token, shown below the
+/// response when `n_probs` is set.
+fn format_probabilities(tokens: &[TokenProbabilities]) -> String {
+    let lines = tokens
+        .iter()
+        .map(|token| {
+            let candidates = token
+                .probs
+                .iter()
+                .map(|candidate| {
+                    return format!(
+                        "`{:?}` {:.1}%",
+                        candidate.tok_str,
+                        candidate.probability() * 100.0
+                    );
+                })
+                .collect::<Vec<String>>()
+                .join(", ");
+            return format!("- `{:?}`: {candidates}", token.content);
+        })
+        .collect::<Vec<String>>()
+        .join("\n");
+
+    return format!("\n\nToken probabilities:\n\n{lines}");
+}
+
+/// Chat history and the server slot holding its KV cache, stored as the
+/// backend context between prompts.
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct CompletionContext {
+    messages: Vec<MessageRequest>,
+    id_slot: Option<i64>,
+}
+
+/// Formats the chat as a plain transcript for servers that can't apply the
+/// model's chat template.
+fn transcript(messages: &[MessageRequest]) -> String {
+    let mut prompt = messages
+        .iter()
+        .map(|message| {
+            let role = match message.role.as_str() {
+                "assistant" => "Assistant",
+                _ => "User",
+            };
+            return format!("{role}: {}\n", message.content);
+        })
+        .collect::<String>();
+
+    prompt += "Assistant:";
+    return prompt;
+}
+
+pub struct LlamaCpp {
+    url: String,
+    grammar_file: String,
+    json_schema_file: String,
+    n_probs: String,
+    timeout: String,
+}
+
+impl Default for LlamaCpp {
+    fn default() -> LlamaCpp {
+        return LlamaCpp {
+            url: Config::get(ConfigKey::LlamaCppURL),
+            grammar_file: Config::get(ConfigKey::LlamaCppGrammarFile),
+            json_schema_file: Config::get(ConfigKey::LlamaCppJsonSchemaFile),
+            n_probs: Config::get(ConfigKey::LlamaCppNProbs),
+            timeout: Config::get(ConfigKey::BackendHealthCheckTimeout),
+        };
+    }
+}
+
+impl LlamaCpp {
+    async fn get_props(&self) -> Result<PropsResponse> {
+        let res = reqwest::Client::new()
+            .get(format!("{url}/props", url = self.url))
+            .timeout(Duration::from_millis(self.timeout.parse::<u64>()?))
+            .send()
+            .await?
+            .json::<PropsResponse>()
+            .await?;
+
+        return Ok(res);
+    }
+
+    fn parse_context(&self, backend_context: &str) -> Result<CompletionContext> {
+        if backend_context.is_empty() {
+            return Ok(CompletionContext::default());
+        }
+
+        return Ok(serde_json::from_str(backend_context)?);
+    }
+
+    /// Renders messages with the model's chat template through `/apply-template`,
+    /// falling back to a plain transcript on older servers.
+    async fn build_prompt(&self, messages: &[MessageRequest]) -> Result<(String, Vec<String>)> {
+        let res = reqwest::Client::new()
+            .post(format!("{url}/apply-template", url = self.url))
+            .json(&ApplyTemplateRequest {
+                messages: messages.to_vec(),
+            })
+            .send()
+            .await;
+
+        if let Ok(res) = res {
+            if res.status().is_success() {
+                let body = res.json::<ApplyTemplateResponse>().await?;
+                return Ok((body.prompt, vec![]));
+            }
+            tracing::debug!(
+                status = res.status().as_u16(),
+                "llama.cpp could not apply chat template, using transcript"
+            );
+        }
+
+        return Ok((transcript(messages), vec!["\nUser:".to_string()]));
+    }
+
+    async fn build_request(
+        &self,
+        prompt: String,
+        stop: Vec<String>,
+        id_slot: Option<i64>,
+    ) -> Result<CompletionRequest> {
+        let mut req = CompletionRequest {
+            prompt,
+            stream: true,
+            cache_prompt: true,
+            id_slot,
+            stop,
+            ..CompletionRequest::default()
+        };
+
+        if !self.json_schema_file.is_empty() {
+            let schema = fs::read_to_string(path::PathBuf::from(&self.json_schema_file)).await?;
+            req.json_schema = Some(serde_json::from_str(&schema)?);
+        } else if !self.grammar_file.is_empty() {
+            let grammar = fs::read_to_string(path::PathBuf::from(&self.grammar_file)).await?;
+            req.grammar = Some(grammar);
+        }
+
+        let n_probs = self.n_probs.parse::<u32>().unwrap_or(0);
+        if n_probs > 0 {
+            req.n_probs = Some(n_probs);
+        }
+
+        return Ok(req);
+    }
+}
+
+#[async_trait]
+impl Backend for LlamaCpp {
+    fn name(&self) -> BackendName {
+        return BackendName::LlamaCpp;
+    }
+
+    fn url(&self) -> String {
+        return self.url.to_string();
+    }
+
+    #[allow(clippy::implicit_return)]
+    async fn health_check(&self) -> Result<()> {
+        if self.url.is_empty() {
+            bail!("llama.cpp URL is not defined");
+        }
+
+        let res = reqwest::Client::new()
+            .get(format!("{url}/health", url = self.url))
+            .timeout(Duration::from_millis(self.timeout.parse::<u64>()?))
+            .send()
+            .await;
+
+        if res.is_err() {
+            tracing::error!(error = ?res.unwrap_err(), "llama.cpp is not running");
+            bail!("llama.cpp is not running");
+        }
+
+        let res = res.unwrap();
+        let status = res.status().as_u16();
+        if status == 503 {
+            let body = res.json::<HealthResponse>().await.unwrap_or_default();
+            tracing::error!(status = body.status, "llama.cpp is not ready");
+            bail!("llama.cpp is still loading the model");
+        }
+
+        if status != 200 {
+            tracing::error!(status = status, "llama.cpp health check failed");
+            bail!("llama.cpp health check failed");
+        }
+
+        return Ok(());
+    }
+
+    #[allow(clippy::implicit_return)]
+    async fn capabilities(&self) -> BackendCapabilities {
+        let mut max_context_length = None;
+        if let Ok(props) = self.get_props().await {
+            max_context_length = props
+                .default_generation_settings
+                .and_then(|settings| return settings.n_ctx);
+        }
+
+        return BackendCapabilities {
+            system_prompt: true,
+            images: false,
+            tool_calls: false,
+            streaming: true,
+            stateful_context: true,
+            max_context_length,
+        };
+    }
+
+    #[allow(clippy::implicit_return)]
+    async fn list_models(&self) -> Result<Vec<String>> {
+        let res = self.get_props().await?;
+
+        // llama-server hosts a single model, reported as the path it was loaded from.
+        let model_path = res
+            .model_path
+            .or_else(|| return res.default_generation_settings.and_then(|e| return e.model))
+            .unwrap_or_default();
+
+        let model = path::Path::new(&model_path)
+            .file_name()
+            .map(|e| return e.to_string_lossy().to_string())
+            .unwrap_or_else(|| return "default".to_string());
+
+        return Ok(vec![model]);
+    }
+
+    #[allow(clippy::implicit_return)]
+    async fn get_completion<'a>(
+        &self,
+        prompt: BackendPrompt,
+        tx: &'a mpsc::UnboundedSender<Event>,
+    ) -> Result<()> {
+        let mut context = self.parse_context(&prompt.backend_context)?;
+        context.messages.push(MessageRequest {
+            role: "user".to_string(),
+            content: prompt.text,
+        });
+
+        let (text_prompt, stop) = self.build_prompt(&context.messages).await?;
+        let req = self
+            .build_request(text_prompt, stop, context.id_slot)
+            .await?;
+
+        let res = reqwest::Client::new()
+            .post(format!("{url}/completion", url = self.url))
+            .json(&ModelAliases::apply_params(&req, None)?)
+            .send()
+            .await?;
+
+        if !res.status().is_success() {
+            tracing::error!(
+                status = res.status().as_u16(),
+                "Failed to make completion request to llama.cpp"
+            );
+            return Err(BackendStatusError::new(
+                "Failed to make completion request to llama.cpp",
+                res.status().as_u16(),
+            )
+            .into());
+        }
+
+        let stream = res.bytes_stream().map_err(convert_err);
+        let mut lines_reader = StreamReader::new(stream).lines();
+
+        let mut last_message = "".to_string();
+        let mut probabilities: Vec<TokenProbabilities> = vec![];
+        while let Ok(line) = lines_reader.next_line().await {
+            if line.is_none() {
+                break;
+            }
+
+            let mut cleaned_line = line.unwrap().trim().to_string();
+            if cleaned_line.starts_with("data:") {
+                cleaned_line = cleaned_line.split_off(5).trim().to_string();
+            }
+            if cleaned_line.is_empty() {
+                continue;
+            }
+
+            let ores: CompletionResponse = serde_json::from_str(&cleaned_line)?;
+            tracing::debug!(body = ?ores, "Completion response");
+
+            if ores.id_slot.is_some() {
+                context.id_slot = ores.id_slot;
+            }
+
+            if let Some(value) = ores.completion_probabilities {
+                match serde_json::from_value::<Vec<TokenProbabilities>>(value) {
+                    Ok(tokens) => probabilities.extend(tokens),
+                    Err(err) => tracing::warn!(error = ?err, "Unexpected token probabilities"),
+                }
+            }
+
+            if !ores.content.is_empty() {
+                last_message += &ores.content;
+                let msg = BackendResponse {
+                    author: Author::Model,
+                    text: ores.content,
+                    done: false,
+                    context: None,
+                };
+                tx.send(Event::BackendPromptResponse(msg))?;
+            }
+
+            if ores.stop {
+                break;
+            }
+        }
+
+        // Shown in the response, but kept out of the chat history the model
+        // sees on the next prompt.
+        if !probabilities.is_empty() {
+            let msg = BackendResponse {
+                author: Author::Model,
+                text: format_probabilities(&probabilities),
+                done: false,
+                context: None,
+            };
+            tx.send(Event::BackendPromptResponse(msg))?;
+        }
+
+        context.messages.push(MessageRequest {
+            role: "assistant".to_string(),
+            content: last_message,
+        });
+
+        let msg = BackendResponse {
+            author: Author::Model,
+            text: "".to_string(),
+            done: true,
+            context: Some(serde_json::to_string(&context)?),
+        };
+        tx.send(Event::BackendPromptResponse(msg))?;
+
+        return Ok(());
+    }
+
+    fn interrupted_context(
+        &self,
+        prompt: &BackendPrompt,
+        partial_text: &str,
+    ) -> Result<Option<String>> {
+        let mut context = self.parse_context(&prompt.backend_context)?;
+        context.messages.push(MessageRequest {
+            role: "user".to_string(),
+            content: prompt.text.to_string(),
+        });
+        context.messages.push(MessageRequest {
+            role: "assistant".to_string(),
+            content: partial_text.to_string(),
+        });
+
+        return Ok(Some(serde_json::to_string(&context)?));
+    }
+}
@@ -0,0 +1,397 @@
+use anyhow::bail;
+use anyhow::Result;
+use mockito::Matcher;
+use test_utils::insta_snapshot;
+use tokio::sync::mpsc;
+
+use super::ApplyTemplateResponse;
+use super::CompletionContext;
+use super::CompletionResponse;
+use super::GenerationSettings;
+use super::HealthResponse;
+use super::LlamaCpp;
+use super::MessageRequest;
+use super::PropsResponse;
+use crate::domain::models::Author;
+use crate::domain::models::Backend;
+use crate::domain::models::BackendPrompt;
+use crate::domain::models::BackendResponse;
+use crate::domain::models::Event;
+
+impl LlamaCpp {
+    fn with_url(url: String) -> LlamaCpp {
+        return LlamaCpp {
+            url,
+            grammar_file: "".to_string(),
+            json_schema_file: "".to_string(),
+            n_probs: "0".to_string(),
+            timeout: "200".to_string(),
+        };
+    }
+}
+
+fn to_res(action: Option<Event>) -> Result<BackendResponse> {
+    let act = match action.unwrap() {
+        Event::BackendPromptResponse(res) => res,
+        _ => bail!("Wrong type from recv"),
+    };
+
+    return Ok(act);
+}
+
+fn completion_body() -> Result<String> {
+    let first_line = serde_json::to_string(&CompletionResponse {
+        content: "Hello ".to_string(),
+        stop: false,
+        id_slot: Some(1),
+        completion_probabilities: None,
+    })?;
+
+    let second_line = serde_json::to_string(&CompletionResponse {
+        content: "World".to_string(),
+        stop: false,
+        id_slot: Some(1),
+        completion_probabilities: None,
+    })?;
+
+    let third_line = serde_json::to_string(&CompletionResponse {
+        content: "".to_string(),
+        stop: true,
+        id_slot: Some(1),
+        completion_probabilities: None,
+    })?;
+
+    return Ok([first_line, second_line, third_line]
+        .iter()
+        .map(|line| return format!("data: {line}\n"))
+        .collect::<Vec<String>>()
+        .join("\n"));
+}
+
+#[tokio::test]
+async fn it_successfully_health_checks() {
+    let mut server = mockito::Server::new();
+    let mock = server
+        .mock("GET", "/health")
+        .with_status(200)
+        .with_body(r#"{"status":"ok"}"#)
+        .create();
+
+    let backend = LlamaCpp::with_url(server.url());
+    let res = backend.health_check().await;
+
+    assert!(res.is_ok());
+    mock.assert();
+}
+
+#[tokio::test]
+async fn it_fails_health_checks_while_loading_model() -> Result<()> {
+    let body = serde_json::to_string(&HealthResponse {
+        status: "loading model".to_string(),
+    })?;
+
+    let mut server = mockito::Server::new();
+    let mock = server
+        .mock("GET", "/health")
+        .with_status(503)
+        .with_body(body)
+        .create();
+
+    let backend = LlamaCpp::with_url(server.url());
+    let res = backend.health_check().await;
+
+    assert!(res.is_err());
+    assert_eq!(
+        res.unwrap_err().to_string(),
+        "llama.cpp is still loading the model"
+    );
+    mock.assert();
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_fails_health_checks() {
+    let mut server = mockito::Server::new();
+    let mock = server.mock("GET", "/health").with_status(500).create();
+
+    let backend = LlamaCpp::with_url(server.url());
+    let res = backend.health_check().await;
+
+    assert!(res.is_err());
+    mock.assert();
+}
+
+#[tokio::test]
+async fn it_lists_models() -> Result<()> {
+    let body = serde_json::to_string(&PropsResponse {
+        model_path: Some("/models/mistral-7b-instruct.Q4_K_M.gguf".to_string()),
+        default_generation_settings: None,
+    })?;
+
+    let mut server = mockito::Server::new();
+    let mock = server
+        .mock("GET", "/props")
+        .with_status(200)
+        .with_body(body)
+        .create();
+
+    let backend = LlamaCpp::with_url(server.url());
+    let res = backend.list_models().await?;
+    mock.assert();
+
+    assert_eq!(res, vec!["mistral-7b-instruct.Q4_K_M.gguf".to_string()]);
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_reports_context_length_in_capabilities() -> Result<()> {
+    let body = serde_json::to_string(&PropsResponse {
+        model_path: None,
+        default_generation_settings: Some(GenerationSettings {
+            model: None,
+            n_ctx: Some(4096),
+        }),
+    })?;
+
+    let mut server = mockito::Server::new();
+    let mock = server
+        .mock("GET", "/props")
+        .with_status(200)
+        .with_body(body)
+        .create();
+
+    let backend = LlamaCpp::with_url(server.url());
+    let res = backend.capabilities().await;
+    mock.assert();
+
+    assert!(res.stateful_context);
+    assert_eq!(res.max_context_length, Some(4096));
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_gets_completions() -> Result<()> {
+    let prompt = BackendPrompt {
+        text: "Say hi to the world".to_string(),
+        backend_context: serde_json::to_string(&CompletionContext {
+            messages: vec![MessageRequest {
+                role: "assistant".to_string(),
+                content: "How may I help you?".to_string(),
+            }],
+            id_slot: Some(1),
+        })?,
+    };
+
+    let mut server = mockito::Server::new();
+    let template_mock = server
+        .mock("POST", "/apply-template")
+        .with_status(200)
+        .with_body(serde_json::to_string(&ApplyTemplateResponse {
+            prompt: "<templated>".to_string(),
+        })?)
+        .create();
+    let completion_mock = server
+        .mock("POST", "/completion")
+        .match_body(Matcher::PartialJsonString(
+            r#"{"prompt":"<templated>","stream":true,"cache_prompt":true,"id_slot":1}"#.to_string(),
+        ))
+        .with_status(200)
+        .with_body(completion_body()?)
+        .create();
+
+    let (tx, mut rx) = mpsc::unbounded_channel::<Event>();
+
+    let backend = LlamaCpp::with_url(server.url());
+    backend.get_completion(prompt, &tx).await?;
+
+    template_mock.assert();
+    completion_mock.assert();
+
+    let first_recv = to_res(rx.recv().await)?;
+    let second_recv = to_res(rx.recv().await)?;
+    let third_recv = to_res(rx.recv().await)?;
+
+    assert_eq!(first_recv.author, Author::Model);
+    assert_eq!(first_recv.text, "Hello ".to_string());
+    assert!(!first_recv.done);
+    assert_eq!(first_recv.context, None);
+
+    assert_eq!(second_recv.author, Author::Model);
+    assert_eq!(second_recv.text, "World".to_string());
+    assert!(!second_recv.done);
+    assert_eq!(second_recv.context, None);
+
+    assert_eq!(third_recv.author, Author::Model);
+    assert!(third_recv.text.is_empty());
+    assert!(third_recv.done);
+    insta_snapshot(|| {
+        insta::assert_toml_snapshot!(third_recv.context);
+    });
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_lists_token_probabilities_below_responses() -> Result<()> {
+    let body = [
+        r#"data: {"content":"Hi","stop":false,"completion_probabilities":[{"content":"Hi","probs":[{"tok_str":"Hi","prob":0.75},{"tok_str":"Hello","prob":0.25}]}]}"#,
+        r#"data: {"content":" there","stop":false,"completion_probabilities":[{"id":1,"token":" there","logprob":0.0,"top_logprobs":[{"id":1,"token":" there","logprob":0.0}]}]}"#,
+        r#"data: {"content":"","stop":true}"#,
+    ]
+    .join("\n\n");
+
+    let mut server = mockito::Server::new();
+    let completion_mock = server
+        .mock("POST", "/completion")
+        .match_body(Matcher::PartialJsonString(r#"{"n_probs":2}"#.to_string()))
+        .with_status(200)
+        .with_body(body)
+        .create();
+
+    let (tx, mut rx) = mpsc::unbounded_channel::<Event>();
+
+    let mut backend = LlamaCpp::with_url(server.url());
+    backend.n_probs = "2".to_string();
+    backend
+        .get_completion(
+            BackendPrompt {
+                text: "Say hi".to_string(),
+                backend_context: "".to_string(),
+            },
+            &tx,
+        )
+        .await?;
+    completion_mock.assert();
+
+    assert_eq!(to_res(rx.recv().await)?.text, "Hi");
+    assert_eq!(to_res(rx.recv().await)?.text, " there");
+    assert_eq!(
+        to_res(rx.recv().await)?.text,
+        "\n\nToken probabilities:\n\n- `\"Hi\"`: `\"Hi\"` 75.0%, `\"Hello\"` 25.0%\n- `\" there\"`: `\" there\"` 100.0%"
+    );
+
+    let done = to_res(rx.recv().await)?;
+    assert!(done.done);
+    let context: CompletionContext = serde_json::from_str(&done.context.unwrap())?;
+    assert_eq!(context.messages[1].content, "Hi there");
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_gets_completions_with_transcript_when_template_is_unavailable() -> Result<()> {
+    let prompt = BackendPrompt {
+        text: "Say hi to the world".to_string(),
+        backend_context: "".to_string(),
+    };
+
+    let mut server = mockito::Server::new();
+    let template_mock = server
+        .mock("POST", "/apply-template")
+        .with_status(404)
+        .create();
+    let completion_mock = server
+        .mock("POST", "/completion")
+        .match_body(Matcher::PartialJsonString(
+            r#"{"prompt":"User: Say hi to the world\nAssistant:","stop":["\nUser:"]}"#.to_string(),
+        ))
+        .with_status(200)
+        .with_body(completion_body()?)
+        .create();
+
+    let (tx, mut rx) = mpsc::unbounded_channel::<Event>();
+
+    let backend = LlamaCpp::with_url(server.url());
+    backend.get_completion(prompt, &tx).await?;
+
+    template_mock.assert();
+    completion_mock.assert();
+
+    let _ = to_res(rx.recv().await)?;
+    let _ = to_res(rx.recv().await)?;
+    let last_recv = to_res(rx.recv().await)?;
+    assert!(last_recv.done);
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_gets_constrained_completions() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let grammar_file = dir.path().join("grammar.gbnf");
+    let schema_file = dir.path().join("schema.json");
+    tokio::fs::write(&grammar_file, r#"root ::= "yes" | "no""#).await?;
+    tokio::fs::write(&schema_file, r#"{"type":"object"}"#).await?;
+
+    let prompt = BackendPrompt {
+        text: "Say hi to the world".to_string(),
+        backend_context: "".to_string(),
+    };
+
+    let mut server = mockito::Server::new();
+    let _template_mock = server
+        .mock("POST", "/apply-template")
+        .with_status(200)
+        .with_body(serde_json::to_string(&ApplyTemplateResponse {
+            prompt: "<templated>".to_string(),
+        })?)
+        .create();
+    let grammar_mock = server
+        .mock("POST", "/completion")
+        .match_body(Matcher::PartialJsonString(
+            r#"{"grammar":"root ::= \"yes\" | \"no\"","n_probs":3}"#.to_string(),
+        ))
+        .with_status(200)
+        .with_body(completion_body()?)
+        .create();
+
+    let (tx, _rx) = mpsc::unbounded_channel::<Event>();
+
+    let mut backend = LlamaCpp::with_url(server.url());
+    backend.grammar_file = grammar_file.to_string_lossy().to_string();
+    backend.n_probs = "3".to_string();
+    backend.get_completion(prompt.clone(), &tx).await?;
+    grammar_mock.assert();
+
+    let schema_mock = server
+        .mock("POST", "/completion")
+        .match_body(Matcher::PartialJsonString(
+            r#"{"json_schema":{"type":"object"}}"#.to_string(),
+        ))
+        .with_status(200)
+        .with_body(completion_body()?)
+        .create();
+
+    backend.json_schema_file = schema_file.to_string_lossy().to_string();
+    backend.get_completion(prompt, &tx).await?;
+    schema_mock.assert();
+
+    return Ok(());
+}
+
+#[test]
+fn it_keeps_partial_responses_in_interrupted_context() -> Result<()> {
+    let prompt = BackendPrompt {
+        text: "Say hi to the world".to_string(),
+        backend_context: serde_json::to_string(&CompletionContext {
+            messages: vec![],
+            id_slot: Some(2),
+        })?,
+    };
+
+    let backend = LlamaCpp::with_url("http://localhost:8080".to_string());
+    let context = backend
+        .interrupted_context(&prompt, "Hello Wo")?
+        .unwrap_or_default();
+    let res: CompletionContext = serde_json::from_str(&context)?;
+
+    assert_eq!(res.id_slot, Some(2));
+    assert_eq!(res.messages.len(), 2);
+    assert_eq!(res.messages[1].role, "assistant");
+    assert_eq!(res.messages[1].content, "Hello Wo");
+
+    return Ok(());
+}
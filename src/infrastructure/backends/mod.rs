@@ -1,6 +1,8 @@
+pub mod azureopenai;
 pub mod claude;
 pub mod gemini;
 pub mod langchain;
+pub mod llamacpp;
 pub mod ollama;
 pub mod openai;
 use anyhow::bail;
@@ -33,6 +35,14 @@ impl BackendManager {
             return Ok(Box::<gemini::Gemini>::default());
         }
 
+        if name == BackendName::LlamaCpp {
+            return Ok(Box::<llamacpp::LlamaCpp>::default());
+        }
+
+        if name == BackendName::AzureOpenAI {
+            return Ok(Box::<azureopenai::AzureOpenAI>::default());
+        }
+
         bail!(format!("No backend implemented for {name}"))
     }
 }
@@ -16,11 +16,14 @@ use tokio_util::io::StreamReader;
 
 use crate::configuration::Config;
 use crate::configuration::ConfigKey;
+use crate::configuration::ModelAliases;
 use crate::domain::models::Author;
 use crate::domain::models::Backend;
+use crate::domain::models::BackendCapabilities;
 use crate::domain::models::BackendName;
 use crate::domain::models::BackendPrompt;
 use crate::domain::models::BackendResponse;
+use crate::domain::models::BackendStatusError;
 use crate::domain::models::Event;
 
 fn convert_err(err: reqwest::Error) -> std::io::Error {
@@ -72,6 +75,10 @@ impl Backend for Ollama {
         return BackendName::Ollama;
     }
 
+    fn url(&self) -> String {
+        return self.url.to_string();
+    }
+
     #[allow(clippy::implicit_return)]
     async fn health_check(&self) -> Result<()> {
         let res = reqwest::Client::new()
@@ -94,6 +101,18 @@ impl Backend for Ollama {
         return Ok(());
     }
 
+    #[allow(clippy::implicit_return)]
+    async fn capabilities(&self) -> BackendCapabilities {
+        return BackendCapabilities {
+            system_prompt: true,
+            images: true,
+            tool_calls: false,
+            streaming: true,
+            stateful_context: true,
+            max_context_length: None,
+        };
+    }
+
     #[allow(clippy::implicit_return)]
     async fn list_models(&self) -> Result<Vec<String>> {
         let res = reqwest::Client::new()
@@ -134,7 +153,7 @@ impl Backend for Ollama {
 
         let res = reqwest::Client::new()
             .post(format!("{url}/api/generate", url = self.url))
-            .json(&req)
+            .json(&ModelAliases::apply_params(&req, Some("options"))?)
             .send()
             .await?;
 
@@ -143,7 +162,11 @@ impl Backend for Ollama {
                 status = res.status().as_u16(),
                 "Failed to make completion request to Ollama"
             );
-            bail!("Failed to make completion request to Ollama");
+            return Err(BackendStatusError::new(
+                "Failed to make completion request to Ollama",
+                res.status().as_u16(),
+            )
+            .into());
         }
 
         let stream = res.bytes_stream().map_err(convert_err);
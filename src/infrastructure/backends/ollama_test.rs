@@ -10,6 +10,7 @@ use crate::domain::models::Author;
 use crate::domain::models::Backend;
 use crate::domain::models::BackendPrompt;
 use crate::domain::models::BackendResponse;
+use crate::domain::models::BackendStatusError;
 use crate::domain::models::Event;
 
 impl Ollama {
@@ -132,3 +133,34 @@ async fn it_gets_completions() -> Result<()> {
 
     return Ok(());
 }
+
+#[tokio::test]
+async fn it_reports_the_status_of_failed_completions() -> Result<()> {
+    let prompt = BackendPrompt {
+        text: "Say hi to the world".to_string(),
+        backend_context: "".to_string(),
+    };
+
+    let mut server = mockito::Server::new();
+    let mock = server
+        .mock("POST", "/api/generate")
+        .with_status(404)
+        .create();
+
+    let (tx, _rx) = mpsc::unbounded_channel::<Event>();
+
+    let backend = Ollama::with_url(server.url());
+    let err = backend.get_completion(prompt, &tx).await.unwrap_err();
+
+    mock.assert();
+
+    let status_err = err.downcast_ref::<BackendStatusError>().unwrap();
+    assert_eq!(status_err.status, 404);
+    assert!(!status_err.is_server_error());
+    assert_eq!(
+        err.to_string(),
+        "Failed to make completion request to Ollama".to_string()
+    );
+
+    return Ok(());
+}
@@ -16,11 +16,14 @@ use tokio_util::io::StreamReader;
 
 use crate::configuration::Config;
 use crate::configuration::ConfigKey;
+use crate::configuration::ModelAliases;
 use crate::domain::models::Author;
 use crate::domain::models::Backend;
+use crate::domain::models::BackendCapabilities;
 use crate::domain::models::BackendName;
 use crate::domain::models::BackendPrompt;
 use crate::domain::models::BackendResponse;
+use crate::domain::models::BackendStatusError;
 use crate::domain::models::Event;
 
 fn convert_err(err: reqwest::Error) -> std::io::Error {
@@ -39,9 +42,9 @@ struct ModelListResponse {
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
-struct MessageRequest {
-    role: String,
-    content: String,
+pub struct MessageRequest {
+    pub role: String,
+    pub content: String,
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -67,6 +70,68 @@ struct CompletionResponse {
     choices: Vec<CompletionChoiceResponse>,
 }
 
+/// Streams chat completion deltas to the UI as they arrive, returning the
+/// complete message once the stream finishes. Shared by OpenAI compatible
+/// backends.
+pub async fn stream_completion(
+    res: reqwest::Response,
+    tx: &mpsc::UnboundedSender<Event>,
+) -> Result<String> {
+    let stream = res.bytes_stream().map_err(convert_err);
+    let mut lines_reader = StreamReader::new(stream).lines();
+
+    let mut last_message = "".to_string();
+    while let Ok(line) = lines_reader.next_line().await {
+        if line.is_none() {
+            break;
+        }
+
+        let mut cleaned_line = line.unwrap().trim().to_string();
+        if cleaned_line.starts_with("data:") {
+            cleaned_line = cleaned_line.split_off(5).trim().to_string();
+        }
+        if cleaned_line.is_empty() {
+            continue;
+        }
+        if cleaned_line == "[DONE]" {
+            break;
+        }
+
+        let ores: CompletionResponse = serde_json::from_str(&cleaned_line)?;
+        tracing::debug!(body = ?ores, "Completion response");
+
+        // Azure sends content filter results in chunks without any choices.
+        if ores.choices.is_empty() {
+            continue;
+        }
+
+        let choice = &ores.choices[0];
+        if choice.finish_reason.is_some() {
+            break;
+        }
+        if choice.delta.content.is_none() {
+            continue;
+        }
+
+        let text = choice.delta.content.clone().unwrap().to_string();
+        if text.is_empty() {
+            continue;
+        }
+
+        last_message += &text;
+        let msg = BackendResponse {
+            author: Author::Model,
+            text,
+            done: false,
+            context: None,
+        };
+
+        tx.send(Event::BackendPromptResponse(msg))?;
+    }
+
+    return Ok(last_message);
+}
+
 pub struct OpenAI {
     url: String,
     token: String,
@@ -89,6 +154,10 @@ impl Backend for OpenAI {
         return BackendName::OpenAI;
     }
 
+    fn url(&self) -> String {
+        return self.url.to_string();
+    }
+
     #[allow(clippy::implicit_return)]
     async fn health_check(&self) -> Result<()> {
         if self.url.is_empty() {
@@ -124,6 +193,18 @@ impl Backend for OpenAI {
         return Ok(());
     }
 
+    #[allow(clippy::implicit_return)]
+    async fn capabilities(&self) -> BackendCapabilities {
+        return BackendCapabilities {
+            system_prompt: true,
+            images: true,
+            tool_calls: true,
+            streaming: true,
+            stateful_context: true,
+            max_context_length: None,
+        };
+    }
+
     #[allow(clippy::implicit_return)]
     async fn list_models(&self) -> Result<Vec<String>> {
         let res = reqwest::Client::new()
@@ -171,7 +252,7 @@ impl Backend for OpenAI {
         let res = reqwest::Client::new()
             .post(format!("{url}/v1/chat/completions", url = self.url))
             .header("Authorization", format!("Bearer {}", self.token))
-            .json(&req)
+            .json(&ModelAliases::apply_params(&req, None)?)
             .send()
             .await?;
 
@@ -180,52 +261,14 @@ impl Backend for OpenAI {
                 status = res.status().as_u16(),
                 "Failed to make completion request to OpenAI"
             );
-            bail!("Failed to make completion request to OpenAI");
+            return Err(BackendStatusError::new(
+                "Failed to make completion request to OpenAI",
+                res.status().as_u16(),
+            )
+            .into());
         }
 
-        let stream = res.bytes_stream().map_err(convert_err);
-        let mut lines_reader = StreamReader::new(stream).lines();
-
-        let mut last_message = "".to_string();
-        while let Ok(line) = lines_reader.next_line().await {
-            if line.is_none() {
-                break;
-            }
-
-            let mut cleaned_line = line.unwrap().trim().to_string();
-            if cleaned_line.starts_with("data:") {
-                cleaned_line = cleaned_line.split_off(5).trim().to_string();
-            }
-            if cleaned_line.is_empty() {
-                continue;
-            }
-
-            let ores: CompletionResponse = serde_json::from_str(&cleaned_line).unwrap();
-            tracing::debug!(body = ?ores, "Completion response");
-
-            let choice = &ores.choices[0];
-            if choice.finish_reason.is_some() {
-                break;
-            }
-            if choice.delta.content.is_none() {
-                continue;
-            }
-
-            let text = choice.delta.content.clone().unwrap().to_string();
-            if text.is_empty() {
-                continue;
-            }
-
-            last_message += &text;
-            let msg = BackendResponse {
-                author: Author::Model,
-                text,
-                done: false,
-                context: None,
-            };
-
-            tx.send(Event::BackendPromptResponse(msg))?;
-        }
+        let last_message = stream_completion(res, tx).await?;
 
         messages.push(MessageRequest {
             role: "assistant".to_string(),
@@ -242,4 +285,25 @@ impl Backend for OpenAI {
 
         return Ok(());
     }
+
+    fn interrupted_context(
+        &self,
+        prompt: &BackendPrompt,
+        partial_text: &str,
+    ) -> Result<Option<String>> {
+        let mut messages: Vec<MessageRequest> = vec![];
+        if !prompt.backend_context.is_empty() {
+            messages = serde_json::from_str(&prompt.backend_context)?;
+        }
+        messages.push(MessageRequest {
+            role: "user".to_string(),
+            content: prompt.text.to_string(),
+        });
+        messages.push(MessageRequest {
+            role: "assistant".to_string(),
+            content: partial_text.to_string(),
+        });
+
+        return Ok(Some(serde_json::to_string(&messages)?));
+    }
 }
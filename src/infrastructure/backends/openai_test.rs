@@ -171,3 +171,26 @@ async fn it_gets_completions() -> Result<()> {
 
     return Ok(());
 }
+
+#[test]
+fn it_builds_interrupted_context() -> Result<()> {
+    let prompt = BackendPrompt {
+        text: "Say hi to the world".to_string(),
+        backend_context: serde_json::to_string(&vec![MessageRequest {
+            role: "assistant".to_string(),
+            content: "How may I help you?".to_string(),
+        }])?,
+    };
+
+    let backend = OpenAI::with_url("http://localhost".to_string());
+    let context = backend.interrupted_context(&prompt, "Hello ")?.unwrap();
+    let messages: Vec<MessageRequest> = serde_json::from_str(&context)?;
+
+    assert_eq!(messages.len(), 3);
+    assert_eq!(messages[1].role, "user");
+    assert_eq!(messages[1].content, "Say hi to the world");
+    assert_eq!(messages[2].role, "assistant");
+    assert_eq!(messages[2].content, "Hello ");
+
+    return Ok(());
+}
@@ -0,0 +1,194 @@
+#[cfg(test)]
+#[path = "emacs_test.rs"]
+mod tests;
+
+use std::str;
+
+use anyhow::bail;
+use anyhow::Result;
+use async_trait::async_trait;
+use base64::engine::general_purpose::STANDARD as b64;
+use base64::Engine;
+use serde::Deserialize;
+use serde::Deserializer;
+use serde::Serialize;
+use serde::Serializer;
+use tokio::process::Command;
+
+use crate::domain::models::AcceptType;
+use crate::domain::models::Editor;
+use crate::domain::models::EditorContext;
+use crate::domain::models::EditorName;
+
+fn base64_to_string<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    let val = match serde::de::Deserialize::deserialize(deserializer)? {
+        serde_json::Value::String(s) => s,
+        _ => return Err(serde::de::Error::custom("Wrong type, expected string")),
+    };
+
+    let b64_res = b64.decode(val).map_err(serde::de::Error::custom)?;
+    let str_res = str::from_utf8(&b64_res)
+        .map_err(serde::de::Error::custom)?
+        .to_string();
+
+    return Ok(str_res);
+}
+
+fn string_to_base64<S: Serializer>(val: &str, serializer: S) -> Result<S::Ok, S::Error> {
+    return serializer.serialize_str(&b64.encode(val));
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct ContextResponse {
+    file_path: String,
+    language: String,
+    #[serde(deserialize_with = "base64_to_string")]
+    code: String,
+    start_line: i64,
+    end_line: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+struct SubmitChangesRequest {
+    accept_type: String,
+    file_path: String,
+    #[serde(serialize_with = "string_to_base64")]
+    code: String,
+    start_line: i64,
+    end_line: Option<i64>,
+}
+
+impl From<ContextResponse> for EditorContext {
+    fn from(val: ContextResponse) -> Self {
+        return EditorContext {
+            file_path: val.file_path,
+            language: val.language,
+            code: val.code,
+            start_line: val.start_line,
+            end_line: val.end_line,
+        };
+    }
+}
+
+/// Quotes text as an Emacs Lisp string literal.
+fn to_elisp_string(text: &str) -> String {
+    let escaped = text.replace('\\', "\\\\").replace('"', "\\\"");
+    return format!("\"{escaped}\"");
+}
+
+/// Reads a value printed by `emacsclient --eval`. Strings are printed as Lisp
+/// string literals, and `nil` is returned as `None`.
+fn from_elisp_string(text: &str) -> Result<Option<String>> {
+    let text = text.trim();
+    if text == "nil" {
+        return Ok(None);
+    }
+
+    if text.len() < 2 || !text.starts_with('"') || !text.ends_with('"') {
+        bail!(format!("Emacs returned an unexpected value: {text}"));
+    }
+
+    let mut res = String::new();
+    let mut chars = text[1..text.len() - 1].chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            if let Some(escaped) = chars.next() {
+                res.push(escaped);
+            }
+            continue;
+        }
+        res.push(c);
+    }
+
+    return Ok(Some(res));
+}
+
+async fn run_elisp_command(expr: &str) -> Result<String> {
+    let output = Command::new("emacsclient")
+        .args(["--eval", expr])
+        .output()
+        .await?;
+    let res = String::from_utf8(output.stdout)?;
+
+    tracing::debug!(expr = expr, res = ?res, "Emacs request/response");
+
+    if !output.status.success() {
+        let stderr = String::from_utf8(output.stderr)?;
+        bail!(format!("emacsclient failed: {}", stderr.trim()));
+    }
+
+    return Ok(res);
+}
+
+/// Talks to a running Emacs server with `emacsclient`, relying on the
+/// `oatmeal.el` helper package being loaded.
+#[derive(Default)]
+pub struct Emacs {}
+
+#[async_trait]
+impl Editor for Emacs {
+    fn name(&self) -> EditorName {
+        return EditorName::Emacs;
+    }
+
+    #[allow(clippy::implicit_return)]
+    async fn health_check(&self) -> Result<()> {
+        let res = match run_elisp_command("(featurep 'oatmeal)").await {
+            Ok(res) => res,
+            Err(err) => {
+                bail!(format!(
+                    "Failed to reach an Emacs server with emacsclient. Is `server-start` running? {err}"
+                ))
+            }
+        };
+
+        if res.trim() != "t" {
+            bail!("The oatmeal Emacs package is not loaded. See the Emacs section in the README.");
+        }
+
+        return Ok(());
+    }
+
+    #[allow(clippy::implicit_return)]
+    async fn get_context(&self) -> Result<Option<EditorContext>> {
+        let res = run_elisp_command("(oatmeal-get-context)").await?;
+        let json_str = match from_elisp_string(&res)? {
+            Some(json_str) => json_str,
+            None => return Ok(None),
+        };
+        let ctx: ContextResponse = serde_json::from_str(&json_str)?;
+
+        return Ok(Some(ctx.into()));
+    }
+
+    #[allow(clippy::implicit_return)]
+    async fn clear_context(&self) -> Result<()> {
+        run_elisp_command("(oatmeal-clear-context)").await?;
+        return Ok(());
+    }
+
+    #[allow(clippy::implicit_return)]
+    async fn send_codeblock<'a>(
+        &self,
+        context: EditorContext,
+        codeblock: String,
+        accept_type: AcceptType,
+    ) -> Result<()> {
+        let req = SubmitChangesRequest {
+            accept_type: accept_type.to_string(),
+            file_path: context.file_path,
+            code: codeblock,
+            start_line: context.start_line,
+            end_line: context.end_line,
+        };
+
+        let json_str = serde_json::to_string(&req)?;
+        run_elisp_command(&format!(
+            "(oatmeal-submit-changes {})",
+            to_elisp_string(&json_str)
+        ))
+        .await?;
+
+        return Ok(());
+    }
+}
@@ -0,0 +1,73 @@
+use anyhow::Result;
+
+use super::from_elisp_string;
+use super::to_elisp_string;
+use super::ContextResponse;
+use super::SubmitChangesRequest;
+use crate::domain::models::EditorContext;
+
+#[test]
+fn it_quotes_elisp_strings() {
+    let res = to_elisp_string(r#"{"file_path":"C:\\src\\main.rs"}"#);
+    insta::assert_snapshot!(res, @r###""{\"file_path\":\"C:\\\\src\\\\main.rs\"}""###);
+}
+
+#[test]
+fn it_reads_elisp_strings() -> Result<()> {
+    let res = from_elisp_string("\"{\\\"file_path\\\":\\\"src/main.rs\\\"}\"\n")?;
+    assert_eq!(res, Some(r#"{"file_path":"src/main.rs"}"#.to_string()));
+
+    return Ok(());
+}
+
+#[test]
+fn it_round_trips_elisp_strings() -> Result<()> {
+    let text = "say \"hi\" \\ to ünïcode";
+    let res = from_elisp_string(&to_elisp_string(text))?;
+    assert_eq!(res, Some(text.to_string()));
+
+    return Ok(());
+}
+
+#[test]
+fn it_reads_nil_as_none() -> Result<()> {
+    let res = from_elisp_string("nil\n")?;
+    assert!(res.is_none());
+
+    return Ok(());
+}
+
+#[test]
+fn it_fails_reading_unexpected_values() {
+    let err = from_elisp_string("42").unwrap_err();
+    insta::assert_snapshot!(err.to_string(), @"Emacs returned an unexpected value: 42");
+}
+
+#[test]
+fn it_decodes_base64_context() -> Result<()> {
+    let json_str = r#"{"file_path":"src/main.rs","language":"rust","code":"Zm4gbWFpbigpIHt9","start_line":1,"end_line":1}"#;
+    let ctx: EditorContext = serde_json::from_str::<ContextResponse>(json_str)?.into();
+
+    assert_eq!(ctx.file_path, "src/main.rs");
+    assert_eq!(ctx.language, "rust");
+    assert_eq!(ctx.code, "fn main() {}");
+    assert_eq!(ctx.start_line, 1);
+    assert_eq!(ctx.end_line, Some(1));
+
+    return Ok(());
+}
+
+#[test]
+fn it_encodes_submitted_code_as_base64() -> Result<()> {
+    let req = SubmitChangesRequest {
+        accept_type: "append".to_string(),
+        file_path: "src/main.rs".to_string(),
+        code: "fn main() {}".to_string(),
+        start_line: 1,
+        end_line: None,
+    };
+
+    insta::assert_snapshot!(serde_json::to_string(&req)?, @r###"{"accept_type":"append","file_path":"src/main.rs","code":"Zm4gbWFpbigpIHt9","start_line":1,"end_line":null}"###);
+
+    return Ok(());
+}
@@ -0,0 +1,287 @@
+#[cfg(test)]
+#[path = "file_test.rs"]
+mod tests;
+
+use std::env;
+use std::fs;
+use std::io;
+use std::path::Component;
+use std::path::Path;
+use std::path::PathBuf;
+
+use anyhow::bail;
+use anyhow::Result;
+use async_trait::async_trait;
+
+use crate::domain::models::AcceptType;
+use crate::domain::models::Editor;
+use crate::domain::models::EditorContext;
+use crate::domain::models::EditorName;
+
+/// Resolves `file_path` against `root` without touching the filesystem, as
+/// the file may not exist yet.
+fn normalize_path(root: &Path, file_path: &str) -> PathBuf {
+    let mut res = PathBuf::new();
+    for component in root.join(file_path).components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                res.pop();
+            }
+            _ => res.push(component),
+        }
+    }
+
+    return res;
+}
+
+/// Applies a code block to file contents. Replacing without a line range
+/// overwrites the whole file, and appending inserts after the last selected
+/// line. Files using CRLF line endings keep them.
+fn apply_codeblock(
+    contents: &str,
+    codeblock: &str,
+    context: &EditorContext,
+    accept_type: &AcceptType,
+) -> String {
+    let mut lines = contents.lines().collect::<Vec<&str>>();
+    let start = usize::try_from(context.start_line.max(1) - 1)
+        .unwrap()
+        .min(lines.len());
+    let end = context
+        .end_line
+        .map(|end_line| return usize::try_from(end_line.max(0)).unwrap().min(lines.len()));
+
+    match (accept_type, end) {
+        (AcceptType::Replace, Some(end)) => {
+            lines.splice(start..end.max(start), codeblock.lines());
+        }
+        (AcceptType::Replace, None) => {
+            lines = codeblock.lines().collect();
+        }
+        (AcceptType::Append, _) => {
+            let after = end.unwrap_or_else(|| {
+                return usize::try_from(context.start_line.max(0))
+                    .unwrap()
+                    .min(lines.len());
+            });
+            lines.splice(after..after, codeblock.lines());
+        }
+    }
+
+    if lines.is_empty() {
+        return "".to_string();
+    }
+
+    let mut line_ending = "\n";
+    if contents.contains("\r\n") {
+        line_ending = "\r\n";
+    }
+
+    return format!("{}{line_ending}", lines.join(line_ending));
+}
+
+/// Resolves symlinks in the deepest part of `path` that exists, keeping the
+/// rest as is. Returns `None` when that part can't be resolved, such as a
+/// dangling symlink.
+fn resolve_existing(path: &Path) -> Option<PathBuf> {
+    let mut existing = path;
+    let mut rest = vec![];
+    while existing.symlink_metadata().is_err() {
+        rest.push(existing.file_name()?);
+        existing = existing.parent()?;
+    }
+
+    let mut res = existing.canonicalize().ok()?;
+    for name in rest.iter().rev() {
+        res.push(name);
+    }
+
+    return Some(res);
+}
+
+/// Writes accepted code blocks straight to files on disk, relative to the
+/// working directory. Files are backed up before they're changed.
+pub struct FileEditor {
+    root: PathBuf,
+    backup_dir: PathBuf,
+    /// Whether files outside of the working directory may be written to,
+    /// once the user has confirmed it.
+    allow_outside_root: bool,
+}
+
+impl FileEditor {
+    pub fn new() -> Result<FileEditor> {
+        let cache_dir = match dirs::cache_dir() {
+            Some(dir) => dir,
+            None => bail!("Failed to find a cache directory for file backups"),
+        };
+
+        return Ok(FileEditor {
+            root: env::current_dir()?,
+            backup_dir: cache_dir.join("oatmeal/backups"),
+            allow_outside_root: false,
+        });
+    }
+
+    /// Allows writing to files outside of the working directory, for when the
+    /// user has confirmed it.
+    pub fn allowing_outside_root(mut self) -> FileEditor {
+        self.allow_outside_root = true;
+        return self;
+    }
+
+    /// Checks whether a path escapes the working directory, either through
+    /// `..` or symlinks.
+    pub fn is_outside_root(&self, file_path: &str) -> bool {
+        let root = self.root.canonicalize().unwrap_or(self.root.to_path_buf());
+        match resolve_existing(&normalize_path(&self.root, file_path)) {
+            Some(path) => return !path.starts_with(root),
+            None => return true,
+        }
+    }
+
+    /// Reads a file relative to the working directory. Files that don't exist
+    /// yet are empty.
+    pub fn read(&self, file_path: &str) -> Result<String> {
+        let path = normalize_path(&self.root, file_path);
+        match fs::read_to_string(&path) {
+            Ok(contents) => return Ok(contents),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok("".to_string()),
+            Err(err) => bail!(format!("Failed to read {}: {err}", path.display())),
+        }
+    }
+
+    /// Writes a file relative to the working directory, backing up what was
+    /// there before. Files outside of the working directory are refused unless
+    /// they've been allowed.
+    pub fn write(&self, file_path: &str, contents: &str) -> Result<()> {
+        if !self.allow_outside_root && self.is_outside_root(file_path) {
+            bail!(format!(
+                "{file_path} is outside of the working directory, so it wasn't written to"
+            ));
+        }
+
+        let path = normalize_path(&self.root, file_path);
+        if let Some(backup_path) = self.backup(&path)? {
+            tracing::info!(path = ?path, backup = ?backup_path, "Backed up file");
+        }
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, contents)?;
+
+        return Ok(());
+    }
+
+    /// Reads a file as editor context. `lines` selects an inclusive, 1-based
+    /// line range, otherwise the whole file is selected. Files that don't
+    /// exist yet have no code.
+    pub fn context(&self, file_path: &str, lines: Option<(i64, i64)>) -> Result<EditorContext> {
+        let path = normalize_path(&self.root, file_path);
+        let contents = self.read(file_path)?;
+
+        let language = path
+            .extension()
+            .map(|ext| return ext.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        let line_count = i64::try_from(contents.lines().count())?;
+        let (start_line, end_line) = match lines {
+            Some((start_line, end_line)) => (start_line, Some(end_line.min(line_count))),
+            None if line_count == 0 => (1, None),
+            None => (1, Some(line_count)),
+        };
+
+        let code = match end_line {
+            Some(end_line) => contents
+                .lines()
+                .skip(usize::try_from(start_line.max(1) - 1)?)
+                .take(usize::try_from((end_line - start_line.max(1) + 1).max(0))?)
+                .collect::<Vec<&str>>()
+                .join("\n"),
+            None => "".to_string(),
+        };
+
+        return Ok(EditorContext {
+            file_path: file_path.to_string(),
+            language,
+            code,
+            start_line,
+            end_line,
+        });
+    }
+
+    /// Copies an existing file in to the backup directory, named after the
+    /// time and its path.
+    fn backup(&self, path: &Path) -> Result<Option<PathBuf>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let name = path
+            .strip_prefix(&self.root)
+            .unwrap_or(path)
+            .to_string_lossy()
+            .replace(['/', '\\', ':'], "%");
+        let backup_path = self.backup_dir.join(format!(
+            "{}-{name}",
+            chrono::Local::now().format("%Y%m%dT%H%M%S%.3f")
+        ));
+
+        fs::create_dir_all(&self.backup_dir)?;
+        fs::copy(path, &backup_path)?;
+
+        return Ok(Some(backup_path));
+    }
+}
+
+#[async_trait]
+impl Editor for FileEditor {
+    fn name(&self) -> EditorName {
+        return EditorName::File;
+    }
+
+    #[allow(clippy::implicit_return)]
+    async fn health_check(&self) -> Result<()> {
+        if !self.root.is_dir() {
+            bail!(format!(
+                "Working directory {} does not exist",
+                self.root.display()
+            ));
+        }
+
+        return Ok(());
+    }
+
+    #[allow(clippy::implicit_return)]
+    async fn get_context(&self) -> Result<Option<EditorContext>> {
+        return Ok(None);
+    }
+
+    #[allow(clippy::implicit_return)]
+    async fn clear_context(&self) -> Result<()> {
+        return Ok(());
+    }
+
+    #[allow(clippy::implicit_return)]
+    async fn send_codeblock<'a>(
+        &self,
+        context: EditorContext,
+        codeblock: String,
+        accept_type: AcceptType,
+    ) -> Result<()> {
+        if context.file_path.is_empty() {
+            bail!("No file path was given to write code blocks to");
+        }
+
+        let contents = self.read(&context.file_path)?;
+        self.write(
+            &context.file_path,
+            &apply_codeblock(&contents, &codeblock, &context, &accept_type),
+        )?;
+
+        return Ok(());
+    }
+}
@@ -0,0 +1,291 @@
+use std::fs;
+
+use anyhow::Result;
+use tempfile::TempDir;
+
+use super::FileEditor;
+use crate::domain::models::AcceptType;
+use crate::domain::models::Editor;
+use crate::domain::models::EditorContext;
+
+fn editor(dir: &TempDir) -> FileEditor {
+    return FileEditor {
+        root: dir.path().join("project"),
+        backup_dir: dir.path().join("backups"),
+        allow_outside_root: false,
+    };
+}
+
+fn setup() -> Result<(TempDir, FileEditor)> {
+    let dir = tempfile::tempdir()?;
+    fs::create_dir_all(dir.path().join("project/src"))?;
+    fs::write(
+        dir.path().join("project/src/main.rs"),
+        "fn one() {}\nfn two() {}\nfn three() {}\n",
+    )?;
+
+    let editor = editor(&dir);
+    return Ok((dir, editor));
+}
+
+fn read(dir: &TempDir, file_path: &str) -> Result<String> {
+    return Ok(fs::read_to_string(
+        dir.path().join("project").join(file_path),
+    )?);
+}
+
+#[tokio::test]
+async fn it_successfully_health_checks() -> Result<()> {
+    let (_dir, editor) = setup()?;
+    editor.health_check().await?;
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_fails_health_checks_without_a_working_directory() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let err = editor(&dir).health_check().await.unwrap_err();
+    assert!(err.to_string().ends_with("does not exist"));
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_returns_no_context() -> Result<()> {
+    let (_dir, editor) = setup()?;
+    assert!(editor.get_context().await?.is_none());
+
+    return Ok(());
+}
+
+#[test]
+fn it_reads_whole_files_as_context() -> Result<()> {
+    let (_dir, editor) = setup()?;
+    let context = editor.context("src/main.rs", None)?;
+
+    assert_eq!(context.file_path, "src/main.rs");
+    assert_eq!(context.language, "rs");
+    assert_eq!(context.code, "fn one() {}\nfn two() {}\nfn three() {}");
+    assert_eq!(context.start_line, 1);
+    assert_eq!(context.end_line, Some(3));
+
+    return Ok(());
+}
+
+#[test]
+fn it_reads_line_ranges_as_context() -> Result<()> {
+    let (_dir, editor) = setup()?;
+    let context = editor.context("src/main.rs", Some((2, 3)))?;
+
+    assert_eq!(context.code, "fn two() {}\nfn three() {}");
+    assert_eq!(context.start_line, 2);
+    assert_eq!(context.end_line, Some(3));
+
+    return Ok(());
+}
+
+#[test]
+fn it_reads_missing_files_as_empty_context() -> Result<()> {
+    let (_dir, editor) = setup()?;
+    let context = editor.context("src/lib.rs", None)?;
+
+    assert_eq!(context.code, "");
+    assert_eq!(context.end_line, None);
+
+    return Ok(());
+}
+
+#[test]
+fn it_detects_paths_outside_the_working_directory() -> Result<()> {
+    let (dir, editor) = setup()?;
+
+    assert!(!editor.is_outside_root("src/main.rs"));
+    assert!(!editor.is_outside_root("src/../Cargo.toml"));
+    assert!(editor.is_outside_root("../outside.rs"));
+    assert!(editor.is_outside_root(&dir.path().join("outside.rs").to_string_lossy()));
+
+    return Ok(());
+}
+
+#[cfg(unix)]
+#[test]
+fn it_detects_symlinks_outside_the_working_directory() -> Result<()> {
+    let (dir, editor) = setup()?;
+    fs::create_dir_all(dir.path().join("elsewhere"))?;
+    std::os::unix::fs::symlink(
+        dir.path().join("elsewhere"),
+        dir.path().join("project/link"),
+    )?;
+    std::os::unix::fs::symlink(
+        dir.path().join("missing.rs"),
+        dir.path().join("project/dangling.rs"),
+    )?;
+
+    assert!(editor.is_outside_root("link/main.rs"));
+    assert!(editor.is_outside_root("link/nested/main.rs"));
+    assert!(editor.is_outside_root("dangling.rs"));
+
+    let err = editor.write("link/main.rs", "fn main() {}").unwrap_err();
+    insta::assert_snapshot!(err.to_string(), @"link/main.rs is outside of the working directory, so it wasn't written to");
+    assert!(!dir.path().join("elsewhere/main.rs").exists());
+
+    return Ok(());
+}
+
+#[test]
+fn it_writes_outside_the_working_directory_once_allowed() -> Result<()> {
+    let (dir, editor) = setup()?;
+
+    assert!(editor.write("../outside.rs", "fn main() {}").is_err());
+    assert!(!dir.path().join("outside.rs").exists());
+
+    editor
+        .allowing_outside_root()
+        .write("../outside.rs", "fn main() {}")?;
+    assert_eq!(
+        fs::read_to_string(dir.path().join("outside.rs"))?,
+        "fn main() {}"
+    );
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_replaces_line_ranges() -> Result<()> {
+    let (dir, editor) = setup()?;
+    let context = editor.context("src/main.rs", Some((2, 2)))?;
+
+    editor
+        .send_codeblock(
+            context,
+            "fn two() {\n    return 2;\n}".to_string(),
+            AcceptType::Replace,
+        )
+        .await?;
+
+    insta::assert_snapshot!(read(&dir, "src/main.rs")?, @r###"
+    fn one() {}
+    fn two() {
+        return 2;
+    }
+    fn three() {}
+    "###);
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_appends_after_selected_lines() -> Result<()> {
+    let (dir, editor) = setup()?;
+    let context = editor.context("src/main.rs", Some((1, 1)))?;
+
+    editor
+        .send_codeblock(context, "fn four() {}".to_string(), AcceptType::Append)
+        .await?;
+
+    insta::assert_snapshot!(read(&dir, "src/main.rs")?, @r###"
+    fn one() {}
+    fn four() {}
+    fn two() {}
+    fn three() {}
+    "###);
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_keeps_crlf_line_endings() -> Result<()> {
+    let (dir, editor) = setup()?;
+    fs::write(
+        dir.path().join("project/src/main.rs"),
+        "fn one() {}\r\nfn two() {}\r\nfn three() {}\r\n",
+    )?;
+    let context = editor.context("src/main.rs", Some((2, 2)))?;
+
+    editor
+        .send_codeblock(
+            context,
+            "fn two() {\n    return 2;\n}".to_string(),
+            AcceptType::Replace,
+        )
+        .await?;
+
+    assert_eq!(
+        read(&dir, "src/main.rs")?,
+        "fn one() {}\r\nfn two() {\r\n    return 2;\r\n}\r\nfn three() {}\r\n"
+    );
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_appends_to_the_end_of_files() -> Result<()> {
+    let (dir, editor) = setup()?;
+    let context = editor.context("src/main.rs", None)?;
+
+    editor
+        .send_codeblock(context, "fn four() {}".to_string(), AcceptType::Append)
+        .await?;
+
+    insta::assert_snapshot!(read(&dir, "src/main.rs")?, @r###"
+    fn one() {}
+    fn two() {}
+    fn three() {}
+    fn four() {}
+    "###);
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_creates_new_files() -> Result<()> {
+    let (dir, editor) = setup()?;
+    let context = editor.context("src/nested/lib.rs", None)?;
+
+    editor
+        .send_codeblock(context, "pub mod nested;".to_string(), AcceptType::Replace)
+        .await?;
+
+    assert_eq!(read(&dir, "src/nested/lib.rs")?, "pub mod nested;\n");
+    assert!(!dir.path().join("backups").exists());
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_backs_up_files_before_writing() -> Result<()> {
+    let (dir, editor) = setup()?;
+    let context = editor.context("src/main.rs", None)?;
+
+    editor
+        .send_codeblock(context, "fn main() {}".to_string(), AcceptType::Replace)
+        .await?;
+
+    assert_eq!(read(&dir, "src/main.rs")?, "fn main() {}\n");
+
+    let backups = fs::read_dir(dir.path().join("backups"))?
+        .map(|entry| return entry.unwrap().path())
+        .collect::<Vec<_>>();
+    assert_eq!(backups.len(), 1);
+    assert!(backups[0].to_string_lossy().ends_with("-src%main.rs"));
+    assert_eq!(
+        fs::read_to_string(&backups[0])?,
+        "fn one() {}\nfn two() {}\nfn three() {}\n"
+    );
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_fails_without_a_file_path() -> Result<()> {
+    let (_dir, editor) = setup()?;
+    let err = editor
+        .send_codeblock(EditorContext::default(), "".to_string(), AcceptType::Append)
+        .await
+        .unwrap_err();
+
+    insta::assert_snapshot!(err.to_string(), @"No file path was given to write code blocks to");
+
+    return Ok(());
+}
@@ -0,0 +1,368 @@
+#[cfg(test)]
+#[path = "jsonrpc_test.rs"]
+mod tests;
+
+use std::path;
+use std::time::Duration;
+
+use anyhow::bail;
+use anyhow::Result;
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde::Serialize;
+use serde_json::json;
+use serde_json::Value;
+use tokio::fs;
+use tokio::io::AsyncBufReadExt;
+use tokio::io::AsyncWriteExt;
+use tokio::io::BufReader;
+use tokio::io::Lines;
+use tokio::net::tcp::OwnedReadHalf;
+use tokio::net::tcp::OwnedWriteHalf;
+use tokio::net::TcpStream;
+use tokio::time;
+
+use crate::domain::models::AcceptType;
+use crate::domain::models::Editor;
+use crate::domain::models::EditorContext;
+use crate::domain::models::EditorName;
+
+pub const JSONRPC_VERSION: &str = "2.0";
+
+/// Method a connection has to start with, presenting the server's token.
+pub const AUTHENTICATE_METHOD: &str = "authenticate";
+
+/// Milliseconds to wait on the editor server before giving up on a request,
+/// so an unresponsive plugin can't hang Oatmeal.
+const REQUEST_TIMEOUT_MS: u64 = 5000;
+
+/// Where `oatmeal serve-editor` is listening, and the token connections have
+/// to authenticate with. Written to a file only the user can read, so other
+/// local users and processes can neither talk to the editor nor pose as the
+/// server.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EditorServerInfo {
+    pub address: String,
+    pub token: String,
+}
+
+impl EditorServerInfo {
+    pub fn default_path() -> Result<path::PathBuf> {
+        let cache_dir = match dirs::cache_dir() {
+            Some(dir) => dir,
+            None => bail!("Failed to find a cache directory for the editor server"),
+        };
+
+        return Ok(cache_dir.join("oatmeal/editor-server.json"));
+    }
+
+    pub async fn read(file: &path::Path) -> Result<EditorServerInfo> {
+        let payload = fs::read_to_string(file).await?;
+        return Ok(serde_json::from_str(&payload)?);
+    }
+
+    pub async fn write(&self, file: &path::Path) -> Result<()> {
+        if let Some(parent) = file.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+
+        // Permissions only apply when a file is created, so never reuse one.
+        if file.exists() {
+            fs::remove_file(file).await?;
+        }
+
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        options.mode(0o600);
+
+        let mut handle = options.open(file).await?;
+        handle
+            .write_all(serde_json::to_string(self)?.as_bytes())
+            .await?;
+        handle.flush().await?;
+
+        return Ok(());
+    }
+
+    /// Writes the info for a starting server, refusing while another server
+    /// that's still listening owns the file, as sessions can only find one.
+    /// Info left behind by a server that didn't clean up is replaced.
+    pub async fn claim(&self, file: &path::Path) -> Result<()> {
+        if let Ok(existing) = EditorServerInfo::read(file).await {
+            if existing.is_listening().await {
+                bail!(format!(
+                    "An editor server is already running on {}. Stop it before starting another.",
+                    existing.address
+                ));
+            }
+        }
+
+        return self.write(file).await;
+    }
+
+    /// Removes the info file when stopping, unless another server has since
+    /// claimed it.
+    pub async fn release(&self, file: &path::Path) -> Result<()> {
+        if EditorServerInfo::read(file).await? != *self {
+            return Ok(());
+        }
+
+        fs::remove_file(file).await?;
+        return Ok(());
+    }
+
+    async fn is_listening(&self) -> bool {
+        let connect = TcpStream::connect(&self.address);
+        return matches!(
+            time::timeout(Duration::from_millis(500), connect).await,
+            Ok(Ok(_))
+        );
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RpcRequest {
+    pub jsonrpc: String,
+    pub id: Value,
+    pub method: String,
+    #[serde(default)]
+    pub params: Value,
+}
+
+impl RpcRequest {
+    pub fn new(id: Value, method: &str, params: Value) -> RpcRequest {
+        return RpcRequest {
+            jsonrpc: JSONRPC_VERSION.to_string(),
+            id,
+            method: method.to_string(),
+            params,
+        };
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RpcResponse {
+    pub jsonrpc: String,
+    pub id: Value,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<RpcError>,
+}
+
+impl RpcResponse {
+    pub fn success(id: Value, result: Value) -> RpcResponse {
+        return RpcResponse {
+            jsonrpc: JSONRPC_VERSION.to_string(),
+            id,
+            result: Some(result),
+            error: None,
+        };
+    }
+
+    pub fn error(id: Value, code: i64, message: &str) -> RpcResponse {
+        return RpcResponse {
+            jsonrpc: JSONRPC_VERSION.to_string(),
+            id,
+            result: None,
+            error: Some(RpcError {
+                code,
+                message: message.to_string(),
+            }),
+        };
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct ContextResponse {
+    file_path: String,
+    language: String,
+    code: String,
+    start_line: i64,
+    end_line: Option<i64>,
+}
+
+impl From<ContextResponse> for EditorContext {
+    fn from(val: ContextResponse) -> Self {
+        return EditorContext {
+            file_path: val.file_path,
+            language: val.language,
+            code: val.code,
+            start_line: val.start_line,
+            end_line: val.end_line,
+        };
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct SendCodeblockRequest {
+    accept_type: String,
+    file_path: String,
+    code: String,
+    start_line: i64,
+    end_line: Option<i64>,
+}
+
+struct Connection {
+    reader: Lines<BufReader<OwnedReadHalf>>,
+    writer: OwnedWriteHalf,
+}
+
+impl Connection {
+    async fn request(&mut self, req: RpcRequest) -> Result<Value> {
+        let mut payload = serde_json::to_string(&req)?;
+        payload += "\n";
+        self.writer.write_all(payload.as_bytes()).await?;
+
+        let line = match self.reader.next_line().await? {
+            Some(line) => line,
+            None => bail!(format!(
+                "Editor server closed the connection during {}",
+                req.method
+            )),
+        };
+        let res: RpcResponse = serde_json::from_str(&line)?;
+        tracing::debug!(req = ?req, res = ?res, "Editor server request/response");
+
+        if let Some(err) = res.error {
+            bail!(format!("Editor failed to {}: {}", req.method, err.message));
+        }
+
+        return Ok(res.result.unwrap_or_default());
+    }
+}
+
+/// Talks to an editor plugin through `oatmeal serve-editor`, which relays
+/// JSON-RPC requests between Oatmeal and the editor.
+pub struct JsonRpc {
+    /// File the server wrote its address and token to.
+    info_file: Option<path::PathBuf>,
+    timeout: Duration,
+}
+
+impl Default for JsonRpc {
+    fn default() -> JsonRpc {
+        return JsonRpc {
+            info_file: EditorServerInfo::default_path().ok(),
+            timeout: Duration::from_millis(REQUEST_TIMEOUT_MS),
+        };
+    }
+}
+
+impl JsonRpc {
+    async fn connect(&self) -> Result<Connection> {
+        let info = match &self.info_file {
+            Some(file) => EditorServerInfo::read(file).await.ok(),
+            None => None,
+        };
+        let info = match info {
+            Some(info) => info,
+            None => bail!("No editor server is running. Start one with `oatmeal serve-editor` from your editor plugin."),
+        };
+
+        let stream = match TcpStream::connect(&info.address).await {
+            Ok(stream) => stream,
+            Err(err) => bail!(format!(
+                "No editor server is listening on {}. Start one with `oatmeal serve-editor` from your editor plugin. {err}",
+                info.address
+            )),
+        };
+        let (reader, writer) = stream.into_split();
+        let mut conn = Connection {
+            reader: BufReader::new(reader).lines(),
+            writer,
+        };
+
+        conn.request(RpcRequest::new(
+            json!(0),
+            AUTHENTICATE_METHOD,
+            json!({ "token": info.token }),
+        ))
+        .await?;
+
+        return Ok(conn);
+    }
+
+    async fn call(&self, method: &str, params: Value) -> Result<Value> {
+        let res = time::timeout(self.timeout, async {
+            let mut conn = self.connect().await?;
+            return conn
+                .request(RpcRequest::new(json!(1), method, params))
+                .await;
+        })
+        .await;
+
+        match res {
+            Ok(res) => return res,
+            Err(_) => bail!(format!(
+                "Editor didn't respond to {method} within {}ms",
+                self.timeout.as_millis()
+            )),
+        }
+    }
+}
+
+#[async_trait]
+impl Editor for JsonRpc {
+    fn name(&self) -> EditorName {
+        return EditorName::JsonRpc;
+    }
+
+    #[allow(clippy::implicit_return)]
+    async fn health_check(&self) -> Result<()> {
+        match time::timeout(self.timeout, self.connect()).await {
+            Ok(res) => res?,
+            Err(_) => bail!(format!(
+                "Editor server didn't respond within {}ms",
+                self.timeout.as_millis()
+            )),
+        };
+
+        return Ok(());
+    }
+
+    #[allow(clippy::implicit_return)]
+    async fn get_context(&self) -> Result<Option<EditorContext>> {
+        let res = self.call("get_context", json!({})).await?;
+        if res.is_null() {
+            return Ok(None);
+        }
+        let ctx: ContextResponse = serde_json::from_value(res)?;
+
+        return Ok(Some(ctx.into()));
+    }
+
+    #[allow(clippy::implicit_return)]
+    async fn clear_context(&self) -> Result<()> {
+        self.call("clear_context", json!({})).await?;
+        return Ok(());
+    }
+
+    #[allow(clippy::implicit_return)]
+    async fn send_codeblock<'a>(
+        &self,
+        context: EditorContext,
+        codeblock: String,
+        accept_type: AcceptType,
+    ) -> Result<()> {
+        let req = SendCodeblockRequest {
+            accept_type: accept_type.to_string(),
+            file_path: context.file_path,
+            code: codeblock,
+            start_line: context.start_line,
+            end_line: context.end_line,
+        };
+
+        self.call("send_codeblock", serde_json::to_value(req)?)
+            .await?;
+
+        return Ok(());
+    }
+}
@@ -0,0 +1,232 @@
+#[cfg(test)]
+#[path = "jsonrpc_server_test.rs"]
+mod tests;
+
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use anyhow::Result;
+use dashmap::DashMap;
+use serde_json::json;
+use serde_json::Value;
+use tokio::io;
+use tokio::io::AsyncBufReadExt;
+use tokio::io::AsyncRead;
+use tokio::io::AsyncWrite;
+use tokio::io::AsyncWriteExt;
+use tokio::io::BufReader;
+use tokio::net::TcpListener;
+use tokio::net::TcpStream;
+use tokio::sync::mpsc;
+use tokio::sync::oneshot;
+use uuid::Uuid;
+
+use super::jsonrpc::EditorServerInfo;
+use super::jsonrpc::RpcRequest;
+use super::jsonrpc::RpcResponse;
+use super::jsonrpc::AUTHENTICATE_METHOD;
+use crate::configuration::Config;
+use crate::configuration::ConfigKey;
+
+/// Methods an editor plugin is expected to implement.
+const EDITOR_METHODS: [&str; 3] = ["get_context", "send_codeblock", "clear_context"];
+
+const UNAUTHORIZED: i64 = -32001;
+const PARSE_ERROR: i64 = -32700;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INTERNAL_ERROR: i64 = -32603;
+
+type PendingRequests = Arc<DashMap<u64, oneshot::Sender<RpcResponse>>>;
+
+/// Forwards a request to the editor under a new ID, so requests from several
+/// Oatmeal sessions can't collide, and returns the editor's response with the
+/// original ID restored.
+async fn relay(
+    req: RpcRequest,
+    pending: PendingRequests,
+    next_id: Arc<AtomicU64>,
+    editor_tx: mpsc::UnboundedSender<RpcRequest>,
+) -> RpcResponse {
+    if !EDITOR_METHODS.contains(&req.method.as_str()) {
+        return RpcResponse::error(
+            req.id,
+            METHOD_NOT_FOUND,
+            &format!("Method not found: {}", req.method),
+        );
+    }
+
+    let id = next_id.fetch_add(1, Ordering::SeqCst);
+    let (tx, rx) = oneshot::channel();
+    pending.insert(id, tx);
+
+    if editor_tx
+        .send(RpcRequest::new(json!(id), &req.method, req.params))
+        .is_err()
+    {
+        pending.remove(&id);
+        return RpcResponse::error(req.id, INTERNAL_ERROR, "Editor is not connected");
+    }
+
+    match rx.await {
+        Ok(mut res) => {
+            res.id = req.id;
+            return res;
+        }
+        Err(_) => {
+            return RpcResponse::error(
+                req.id,
+                INTERNAL_ERROR,
+                "Editor disconnected before responding",
+            );
+        }
+    }
+}
+
+/// Checks the first request of a connection presents the server's token.
+fn authenticate(line: &str, token: &str) -> RpcResponse {
+    let req = match serde_json::from_str::<RpcRequest>(line) {
+        Ok(req) => req,
+        Err(err) => return RpcResponse::error(Value::Null, PARSE_ERROR, &err.to_string()),
+    };
+
+    if req.method != AUTHENTICATE_METHOD || req.params["token"].as_str() != Some(token) {
+        return RpcResponse::error(req.id, UNAUTHORIZED, "Unauthorized");
+    }
+
+    return RpcResponse::success(req.id, json!(true));
+}
+
+async fn handle_connection(
+    stream: TcpStream,
+    token: Arc<String>,
+    pending: PendingRequests,
+    next_id: Arc<AtomicU64>,
+    editor_tx: mpsc::UnboundedSender<RpcRequest>,
+) -> Result<()> {
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+
+    let res = match lines.next_line().await? {
+        Some(line) => authenticate(&line, &token),
+        None => return Ok(()),
+    };
+    let mut payload = serde_json::to_string(&res)?;
+    payload += "\n";
+    writer.write_all(payload.as_bytes()).await?;
+    if res.error.is_some() {
+        return Ok(());
+    }
+
+    while let Some(line) = lines.next_line().await? {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let res = match serde_json::from_str::<RpcRequest>(&line) {
+            Ok(req) => relay(req, pending.clone(), next_id.clone(), editor_tx.clone()).await,
+            Err(err) => RpcResponse::error(Value::Null, PARSE_ERROR, &err.to_string()),
+        };
+
+        let mut payload = serde_json::to_string(&res)?;
+        payload += "\n";
+        writer.write_all(payload.as_bytes()).await?;
+    }
+
+    return Ok(());
+}
+
+fn handle_editor_line(line: &str, pending: &PendingRequests) {
+    if line.trim().is_empty() {
+        return;
+    }
+
+    let res = match serde_json::from_str::<RpcResponse>(line) {
+        Ok(res) => res,
+        Err(err) => {
+            tracing::warn!(error = ?err, line = line, "Editor sent an invalid response");
+            return;
+        }
+    };
+
+    let id = match res.id.as_u64() {
+        Some(id) => id,
+        None => {
+            tracing::warn!(id = ?res.id, "Editor responded with an unknown ID");
+            return;
+        }
+    };
+
+    if let Some((_, tx)) = pending.remove(&id) {
+        let _ = tx.send(res);
+    }
+}
+
+/// Relays JSON-RPC requests from Oatmeal sessions connecting over TCP to an
+/// editor plugin talking over stdin and stdout, so a plugin only needs to
+/// spawn `oatmeal serve-editor` and answer requests. Connections have to
+/// authenticate with the token written alongside the server's address to
+/// `EditorServerInfo::default_path`.
+pub struct EditorServer {}
+
+impl EditorServer {
+    pub async fn start() -> Result<()> {
+        let listener = TcpListener::bind(Config::get(ConfigKey::EditorServerAddress)).await?;
+        let info = EditorServerInfo {
+            address: listener.local_addr()?.to_string(),
+            token: Uuid::new_v4().to_string(),
+        };
+        let info_file = EditorServerInfo::default_path()?;
+        info.claim(&info_file).await?;
+
+        let res = EditorServer::serve(listener, &info.token, io::stdin(), io::stdout()).await;
+        if let Err(err) = info.release(&info_file).await {
+            tracing::warn!(error = ?err, "Failed to remove editor server info file");
+        }
+
+        return res;
+    }
+
+    /// Runs until the editor closes its side of the connection.
+    pub async fn serve<R: AsyncRead + Unpin, W: AsyncWrite + Unpin>(
+        listener: TcpListener,
+        token: &str,
+        editor_reader: R,
+        mut editor_writer: W,
+    ) -> Result<()> {
+        let token = Arc::new(token.to_string());
+        let pending: PendingRequests = Arc::new(DashMap::new());
+        let next_id = Arc::new(AtomicU64::new(1));
+        let (editor_tx, mut editor_rx) = mpsc::unbounded_channel::<RpcRequest>();
+        let mut editor_lines = BufReader::new(editor_reader).lines();
+
+        loop {
+            tokio::select! {
+                conn = listener.accept() => {
+                    let (stream, _) = conn?;
+                    let token_conn = token.clone();
+                    let pending_conn = pending.clone();
+                    let next_id_conn = next_id.clone();
+                    let editor_tx_conn = editor_tx.clone();
+                    tokio::spawn(async move {
+                        if let Err(err) = handle_connection(stream, token_conn, pending_conn, next_id_conn, editor_tx_conn).await {
+                            tracing::warn!(error = ?err, "Editor server connection failed");
+                        }
+                    });
+                }
+                Some(req) = editor_rx.recv() => {
+                    let mut payload = serde_json::to_string(&req)?;
+                    payload += "\n";
+                    editor_writer.write_all(payload.as_bytes()).await?;
+                    editor_writer.flush().await?;
+                }
+                line = editor_lines.next_line() => {
+                    match line? {
+                        Some(line) => handle_editor_line(&line, &pending),
+                        None => return Ok(()),
+                    }
+                }
+            }
+        }
+    }
+}
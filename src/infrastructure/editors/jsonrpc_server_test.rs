@@ -0,0 +1,179 @@
+use anyhow::Result;
+use serde_json::json;
+use tokio::io;
+use tokio::io::AsyncBufReadExt;
+use tokio::io::AsyncWriteExt;
+use tokio::io::BufReader;
+use tokio::net::TcpListener;
+use tokio::net::TcpStream;
+use tokio::task::JoinHandle;
+
+use super::EditorServer;
+use crate::domain::models::Editor;
+use crate::infrastructure::editors::jsonrpc::JsonRpc;
+use crate::infrastructure::editors::jsonrpc::RpcRequest;
+use crate::infrastructure::editors::jsonrpc::RpcResponse;
+
+/// Starts an editor server with a fake editor plugin on the other side of its
+/// stdio, which answers `get_context` with a fixed file and fails anything
+/// else.
+async fn start_server() -> Result<(String, JoinHandle<Result<()>>)> {
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let address = listener.local_addr()?.to_string();
+
+    let (editor_stream, server_stream) = io::duplex(4096);
+    let (server_reader, server_writer) = io::split(server_stream);
+    let server = tokio::spawn(async move {
+        return EditorServer::serve(listener, "secret", server_reader, server_writer).await;
+    });
+
+    tokio::spawn(async move {
+        let (editor_reader, mut editor_writer) = io::split(editor_stream);
+        let mut lines = BufReader::new(editor_reader).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            let req: RpcRequest = serde_json::from_str(&line).unwrap();
+            let mut res = json!({ "jsonrpc": "2.0", "id": req.id });
+            if req.method == "get_context" {
+                res["result"] = json!({
+                    "file_path": "src/main.rs",
+                    "language": "rust",
+                    "code": "",
+                    "start_line": 0,
+                    "end_line": null
+                });
+            } else {
+                res["error"] = json!({ "code": -32000, "message": "Not supported" });
+            }
+            let payload = format!("{}\n", serde_json::to_string(&res).unwrap());
+            editor_writer.write_all(payload.as_bytes()).await.unwrap();
+        }
+    });
+
+    return Ok((address, server));
+}
+
+/// Sends raw lines over a single connection, returning the response to the
+/// last one.
+async fn send_raw(address: &str, lines: &[&str]) -> Result<RpcResponse> {
+    let stream = TcpStream::connect(address).await?;
+    let (reader, mut writer) = stream.into_split();
+    let mut reader = BufReader::new(reader).lines();
+
+    let mut res = None;
+    for line in lines {
+        writer.write_all(format!("{line}\n").as_bytes()).await?;
+        res = reader.next_line().await?;
+    }
+
+    return Ok(serde_json::from_str(&res.unwrap())?);
+}
+
+const AUTHENTICATE: &str =
+    r#"{"jsonrpc":"2.0","id":0,"method":"authenticate","params":{"token":"secret"}}"#;
+
+#[tokio::test]
+async fn it_relays_requests_to_the_editor() -> Result<()> {
+    let (address, _server) = start_server().await?;
+
+    let ctx = JsonRpc::with_address(address)
+        .await?
+        .editor
+        .get_context()
+        .await?
+        .unwrap();
+    assert_eq!(ctx.file_path, "src/main.rs");
+    assert_eq!(ctx.language, "rust");
+    assert_eq!(ctx.end_line, None);
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_restores_request_ids() -> Result<()> {
+    let (address, _server) = start_server().await?;
+
+    let res = send_raw(
+        &address,
+        &[
+            AUTHENTICATE,
+            r#"{"jsonrpc":"2.0","id":"abc","method":"clear_context"}"#,
+        ],
+    )
+    .await?;
+    assert_eq!(res.id, json!("abc"));
+    assert_eq!(res.error.unwrap().message, "Not supported");
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_rejects_unknown_methods() -> Result<()> {
+    let (address, _server) = start_server().await?;
+
+    let res = send_raw(
+        &address,
+        &[
+            AUTHENTICATE,
+            r#"{"jsonrpc":"2.0","id":7,"method":"format_file"}"#,
+        ],
+    )
+    .await?;
+    assert_eq!(res.id, json!(7));
+    assert_eq!(res.error.unwrap().code, -32601);
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_rejects_invalid_json() -> Result<()> {
+    let (address, _server) = start_server().await?;
+
+    let res = send_raw(&address, &[AUTHENTICATE, "not json"]).await?;
+    assert_eq!(res.error.unwrap().code, -32700);
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_rejects_invalid_tokens() -> Result<()> {
+    let (address, _server) = start_server().await?;
+
+    let res = send_raw(
+        &address,
+        &[r#"{"jsonrpc":"2.0","id":0,"method":"authenticate","params":{"token":"guess"}}"#],
+    )
+    .await?;
+    assert_eq!(res.error.unwrap().code, -32001);
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_rejects_requests_before_authenticating() -> Result<()> {
+    let (address, _server) = start_server().await?;
+
+    let stream = TcpStream::connect(&address).await?;
+    let (reader, mut writer) = stream.into_split();
+    let mut reader = BufReader::new(reader).lines();
+    writer
+        .write_all(b"{\"jsonrpc\":\"2.0\",\"id\":1,\"method\":\"get_context\"}\n")
+        .await?;
+
+    let res: RpcResponse = serde_json::from_str(&reader.next_line().await?.unwrap())?;
+    assert_eq!(res.error.unwrap().code, -32001);
+    assert!(reader.next_line().await?.is_none());
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_stops_when_the_editor_disconnects() -> Result<()> {
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let (editor_stream, server_stream) = io::duplex(4096);
+    let (server_reader, server_writer) = io::split(server_stream);
+    drop(editor_stream);
+
+    EditorServer::serve(listener, "secret", server_reader, server_writer).await?;
+
+    return Ok(());
+}
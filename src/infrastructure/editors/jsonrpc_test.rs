@@ -0,0 +1,354 @@
+use std::time::Duration;
+
+use anyhow::Result;
+use serde_json::json;
+use serde_json::Value;
+use tempfile::TempDir;
+use tokio::io::AsyncBufReadExt;
+use tokio::io::AsyncWriteExt;
+use tokio::io::BufReader;
+use tokio::net::TcpListener;
+use tokio::task::JoinHandle;
+
+use super::EditorServerInfo;
+use super::JsonRpc;
+use super::RpcRequest;
+use super::RpcResponse;
+use crate::domain::models::AcceptType;
+use crate::domain::models::Editor;
+use crate::domain::models::EditorContext;
+
+/// A JSON-RPC editor along with the server info file it reads, which is
+/// removed when dropped.
+pub struct TestJsonRpc {
+    _dir: TempDir,
+    pub editor: JsonRpc,
+}
+
+impl JsonRpc {
+    pub async fn with_address(address: String) -> Result<TestJsonRpc> {
+        let dir = tempfile::tempdir()?;
+        let info_file = dir.path().join("editor-server.json");
+        EditorServerInfo {
+            address,
+            token: "secret".to_string(),
+        }
+        .write(&info_file)
+        .await?;
+
+        return Ok(TestJsonRpc {
+            _dir: dir,
+            editor: JsonRpc {
+                info_file: Some(info_file),
+                timeout: Duration::from_millis(500),
+            },
+        });
+    }
+}
+
+fn respond(req: &RpcRequest, response: Value) -> Result<String> {
+    let mut res = response;
+    res["jsonrpc"] = json!("2.0");
+    res["id"] = req.id.clone();
+
+    return Ok(format!("{}\n", serde_json::to_string(&res)?));
+}
+
+/// Accepts a single connection, responding to one request after it
+/// authenticates with the given response body. Returns the last request that
+/// was received.
+async fn serve_once(response: Value) -> Result<(String, JoinHandle<Result<RpcRequest>>)> {
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let address = listener.local_addr()?.to_string();
+
+    let handle = tokio::spawn(async move {
+        let (stream, _) = listener.accept().await?;
+        let (reader, mut writer) = stream.into_split();
+        let mut lines = BufReader::new(reader).lines();
+
+        let auth: RpcRequest = serde_json::from_str(&lines.next_line().await?.unwrap())?;
+        assert_eq!(auth.method, "authenticate");
+        assert_eq!(auth.params, json!({ "token": "secret" }));
+        writer
+            .write_all(respond(&auth, json!({ "result": true }))?.as_bytes())
+            .await?;
+
+        let req: RpcRequest = match lines.next_line().await? {
+            Some(line) => serde_json::from_str(&line)?,
+            None => return Ok(auth),
+        };
+        writer
+            .write_all(respond(&req, response)?.as_bytes())
+            .await?;
+
+        return Ok(req);
+    });
+
+    return Ok((address, handle));
+}
+
+#[tokio::test]
+async fn it_successfully_health_checks() -> Result<()> {
+    let (address, handle) = serve_once(json!({ "result": null })).await?;
+
+    JsonRpc::with_address(address)
+        .await?
+        .editor
+        .health_check()
+        .await?;
+    let req = handle.await??;
+
+    assert_eq!(req.method, "authenticate");
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_fails_health_checks() -> Result<()> {
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let address = listener.local_addr()?.to_string();
+    drop(listener);
+
+    let res = JsonRpc::with_address(address)
+        .await?
+        .editor
+        .health_check()
+        .await;
+    assert!(res.is_err());
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_gets_context() -> Result<()> {
+    let (address, handle) = serve_once(json!({
+        "result": {
+            "file_path": "src/main.rs",
+            "language": "rust",
+            "code": "fn main() {}",
+            "start_line": 1,
+            "end_line": 1
+        }
+    }))
+    .await?;
+
+    let ctx = JsonRpc::with_address(address)
+        .await?
+        .editor
+        .get_context()
+        .await?
+        .unwrap();
+    let req = handle.await??;
+
+    assert_eq!(req.method, "get_context");
+    assert_eq!(ctx.file_path, "src/main.rs");
+    assert_eq!(ctx.language, "rust");
+    assert_eq!(ctx.code, "fn main() {}");
+    assert_eq!(ctx.start_line, 1);
+    assert_eq!(ctx.end_line, Some(1));
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_gets_no_context() -> Result<()> {
+    let (address, handle) = serve_once(json!({ "result": null })).await?;
+
+    let ctx = JsonRpc::with_address(address)
+        .await?
+        .editor
+        .get_context()
+        .await?;
+    handle.await??;
+
+    assert!(ctx.is_none());
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_sends_codeblocks() -> Result<()> {
+    let (address, handle) = serve_once(json!({ "result": null })).await?;
+
+    let context = EditorContext {
+        file_path: "src/main.rs".to_string(),
+        language: "rust".to_string(),
+        code: "fn main() {}".to_string(),
+        start_line: 1,
+        end_line: Some(1),
+    };
+    JsonRpc::with_address(address)
+        .await?
+        .editor
+        .send_codeblock(
+            context,
+            "fn main() {\n    println!(\"Hi\");\n}".to_string(),
+            AcceptType::Replace,
+        )
+        .await?;
+    let req = handle.await??;
+
+    assert_eq!(req.method, "send_codeblock");
+    assert_eq!(
+        req.params,
+        json!({
+            "accept_type": "replace",
+            "file_path": "src/main.rs",
+            "code": "fn main() {\n    println!(\"Hi\");\n}",
+            "start_line": 1,
+            "end_line": 1
+        })
+    );
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_returns_editor_errors() -> Result<()> {
+    let (address, handle) = serve_once(json!({
+        "error": { "code": -32000, "message": "No file is open" }
+    }))
+    .await?;
+
+    let err = JsonRpc::with_address(address)
+        .await?
+        .editor
+        .clear_context()
+        .await
+        .unwrap_err();
+    handle.await??;
+
+    insta::assert_snapshot!(err.to_string(), @"Editor failed to clear_context: No file is open");
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_fails_without_a_running_server() -> Result<()> {
+    let editor = JsonRpc {
+        info_file: Some(tempfile::tempdir()?.path().join("editor-server.json")),
+        timeout: Duration::from_millis(500),
+    };
+
+    let err = editor.health_check().await.unwrap_err();
+    insta::assert_snapshot!(err.to_string(), @"No editor server is running. Start one with `oatmeal serve-editor` from your editor plugin.");
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_times_out_on_unresponsive_servers() -> Result<()> {
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let address = listener.local_addr()?.to_string();
+    let _handle = tokio::spawn(async move {
+        let (stream, _) = listener.accept().await?;
+        tokio::time::sleep(Duration::from_secs(5)).await;
+        drop(stream);
+        return anyhow::Ok(());
+    });
+
+    let err = JsonRpc::with_address(address)
+        .await?
+        .editor
+        .clear_context()
+        .await
+        .unwrap_err();
+    insta::assert_snapshot!(err.to_string(), @"Editor didn't respond to clear_context within 500ms");
+
+    return Ok(());
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn it_writes_server_info_only_readable_by_the_user() -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = tempfile::tempdir()?;
+    let info_file = dir.path().join("editor-server.json");
+    std::fs::write(&info_file, "stale")?;
+    let info = EditorServerInfo {
+        address: "127.0.0.1:7531".to_string(),
+        token: "secret".to_string(),
+    };
+
+    info.write(&info_file).await?;
+
+    let mode = std::fs::metadata(&info_file)?.permissions().mode();
+    assert_eq!(mode & 0o777, 0o600);
+    assert_eq!(EditorServerInfo::read(&info_file).await?, info);
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_refuses_to_claim_info_owned_by_a_running_server() -> Result<()> {
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let dir = tempfile::tempdir()?;
+    let info_file = dir.path().join("editor-server.json");
+    let running = EditorServerInfo {
+        address: listener.local_addr()?.to_string(),
+        token: "running".to_string(),
+    };
+    running.write(&info_file).await?;
+
+    let starting = EditorServerInfo {
+        address: "127.0.0.1:7531".to_string(),
+        token: "starting".to_string(),
+    };
+    let err = starting.claim(&info_file).await.unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        format!(
+            "An editor server is already running on {}. Stop it before starting another.",
+            running.address
+        )
+    );
+
+    // Stopping the new server leaves the running one's info alone.
+    starting.release(&info_file).await?;
+    assert_eq!(EditorServerInfo::read(&info_file).await?, running);
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_claims_info_left_by_a_stopped_server() -> Result<()> {
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let stopped = EditorServerInfo {
+        address: listener.local_addr()?.to_string(),
+        token: "stopped".to_string(),
+    };
+    drop(listener);
+
+    let dir = tempfile::tempdir()?;
+    let info_file = dir.path().join("editor-server.json");
+    stopped.write(&info_file).await?;
+
+    let starting = EditorServerInfo {
+        address: "127.0.0.1:7531".to_string(),
+        token: "starting".to_string(),
+    };
+    starting.claim(&info_file).await?;
+    assert_eq!(EditorServerInfo::read(&info_file).await?, starting);
+
+    starting.release(&info_file).await?;
+    assert!(!info_file.exists());
+
+    return Ok(());
+}
+
+#[test]
+fn it_serializes_error_responses() -> Result<()> {
+    let res = RpcResponse::error(json!(3), -32601, "Method not found");
+
+    assert_eq!(
+        serde_json::to_value(res)?,
+        json!({
+            "jsonrpc": "2.0",
+            "id": 3,
+            "error": { "code": -32601, "message": "Method not found" }
+        })
+    );
+
+    return Ok(());
+}
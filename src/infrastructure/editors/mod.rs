@@ -1,6 +1,11 @@
 pub mod clipboard;
+pub mod emacs;
+pub mod file;
+pub mod jsonrpc;
+pub mod jsonrpc_server;
 pub mod neovim;
 pub mod noop;
+pub mod tmux;
 
 use anyhow::bail;
 use anyhow::Result;
@@ -20,6 +25,22 @@ impl EditorManager {
             return Ok(Box::<neovim::Neovim>::default());
         }
 
+        if name == EditorName::Emacs {
+            return Ok(Box::<emacs::Emacs>::default());
+        }
+
+        if name == EditorName::JsonRpc {
+            return Ok(Box::<jsonrpc::JsonRpc>::default());
+        }
+
+        if name == EditorName::File {
+            return Ok(Box::new(file::FileEditor::new()?));
+        }
+
+        if name == EditorName::Tmux {
+            return Ok(Box::<tmux::Tmux>::default());
+        }
+
         if name == EditorName::None {
             return Ok(Box::<noop::NoopEditor>::default());
         }
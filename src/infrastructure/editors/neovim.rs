@@ -1,23 +1,97 @@
+#[cfg(test)]
+#[path = "neovim_test.rs"]
+mod tests;
+
 use std::env;
+use std::io;
+use std::net::SocketAddr;
 use std::str;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
 
+use anyhow::anyhow;
 use anyhow::bail;
 use anyhow::Result;
 use async_trait::async_trait;
 use base64::engine::general_purpose::STANDARD as b64;
 use base64::Engine;
+use dashmap::DashMap;
+use rmpv::Value;
 use serde::Deserialize;
 use serde::Deserializer;
 use serde::Serialize;
-use tokio::fs::File;
+use tokio::io::AsyncRead;
+use tokio::io::AsyncReadExt;
+use tokio::io::AsyncWrite;
 use tokio::io::AsyncWriteExt;
-use tokio::process::Command;
+use tokio::net::TcpStream;
+use tokio::sync::mpsc;
+use tokio::sync::oneshot;
+use tokio::sync::Mutex;
+use tokio::sync::OnceCell;
 
 use crate::domain::models::AcceptType;
 use crate::domain::models::Editor;
 use crate::domain::models::EditorContext;
 use crate::domain::models::EditorName;
 
+type RpcReader = Box<dyn AsyncRead + Send + Unpin>;
+type RpcWriter = Box<dyn AsyncWrite + Send + Unpin>;
+type PendingCalls = Arc<DashMap<u64, oneshot::Sender<Result<Value, String>>>>;
+
+const MESSAGE_REQUEST: u64 = 0;
+const MESSAGE_RESPONSE: u64 = 1;
+const MESSAGE_NOTIFICATION: u64 = 2;
+
+const CONTEXT_CHANGED_EVENT: &str = "oatmeal_context_changed";
+
+/// Notifies the connected channel when the cursor, selection, or contents of a
+/// file buffer change. The autocommands remove themselves once the channel is
+/// closed.
+const SUBSCRIBE_LUA: &str = r#"
+local chan = ...
+local group = vim.api.nvim_create_augroup("oatmeal_context_" .. chan, { clear = true })
+vim.api.nvim_create_autocmd({ "BufEnter", "CursorMoved", "ModeChanged", "TextChanged" }, {
+  group = group,
+  callback = function()
+    if vim.bo.buftype ~= "" then
+      return
+    end
+    if not pcall(vim.rpcnotify, chan, "oatmeal_context_changed") then
+      vim.api.nvim_del_augroup_by_id(group)
+    end
+  end,
+})
+"#;
+
+/// Applies an accepted code block to the file's buffer, loading it if it
+/// isn't open. Takes the payload as an argument so nothing is shared through
+/// the filesystem. Replacing without a line range overwrites the whole
+/// buffer, and appending inserts after the last selected line.
+const SUBMIT_CHANGES_LUA: &str = r#"
+local changes = vim.json.decode(..., { luanil = { object = true } })
+local buf = vim.fn.bufadd(changes.file_path)
+vim.fn.bufload(buf)
+
+local code = changes.code:gsub("\n$", "")
+local lines = vim.split(code, "\n", { plain = true })
+local line_count = vim.api.nvim_buf_line_count(buf)
+local start_line = math.min(math.max(changes.start_line, 1) - 1, line_count)
+
+if changes.accept_type == "replace" then
+  if changes.end_line == nil then
+    vim.api.nvim_buf_set_lines(buf, 0, -1, false, lines)
+  else
+    local end_line = math.max(math.min(changes.end_line, line_count), start_line)
+    vim.api.nvim_buf_set_lines(buf, start_line, end_line, false, lines)
+  end
+else
+  local after = math.min(math.max(changes.end_line or changes.start_line, 0), line_count)
+  vim.api.nvim_buf_set_lines(buf, after, after, false, lines)
+end
+"#;
+
 fn base64_to_string<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
     let val = match serde::de::Deserialize::deserialize(deserializer)? {
         serde_json::Value::String(s) => s,
@@ -61,31 +135,210 @@ impl From<ContextResponse> for EditorContext {
     }
 }
 
-async fn run_lua_command(func: &str) -> Result<String> {
-    let nvim_server_path = env::var("NVIM")?;
-    let lua_func = format!("v:lua.{func}");
-    let args = vec![
-        "--headless",
-        "--server",
-        &nvim_server_path,
-        "--remote-expr",
-        &lua_func,
-    ];
+/// `$NVIM` is a unix socket or named pipe path, or `host:port` when Neovim
+/// was started with `--listen` on a TCP address.
+async fn connect(address: &str) -> Result<(RpcReader, RpcWriter)> {
+    if address.parse::<SocketAddr>().is_ok() {
+        let (reader, writer) = TcpStream::connect(address).await?.into_split();
+        return Ok((Box::new(reader), Box::new(writer)));
+    }
+
+    return connect_socket(address).await;
+}
+
+#[cfg(unix)]
+async fn connect_socket(address: &str) -> Result<(RpcReader, RpcWriter)> {
+    let (reader, writer) = tokio::net::UnixStream::connect(address).await?.into_split();
+    return Ok((Box::new(reader), Box::new(writer)));
+}
+
+#[cfg(windows)]
+async fn connect_socket(address: &str) -> Result<(RpcReader, RpcWriter)> {
+    let pipe = tokio::net::windows::named_pipe::ClientOptions::new().open(address)?;
+    let (reader, writer) = tokio::io::split(pipe);
+    return Ok((Box::new(reader), Box::new(writer)));
+}
+
+fn encode_message(message: &Value) -> Result<Vec<u8>> {
+    let mut buf = vec![];
+    rmpv::encode::write_value(&mut buf, message)?;
+    return Ok(buf);
+}
+
+/// Reads the next msgpack value from the stream, buffering partial reads.
+/// Returns `None` once the stream is closed.
+async fn read_message<R: AsyncRead + Unpin>(
+    reader: &mut R,
+    buf: &mut Vec<u8>,
+) -> Result<Option<Value>> {
+    loop {
+        if !buf.is_empty() {
+            let mut cursor = io::Cursor::new(&buf[..]);
+            match rmpv::decode::read_value(&mut cursor) {
+                Ok(message) => {
+                    let position = cursor.position() as usize;
+                    buf.drain(..position);
+                    return Ok(Some(message));
+                }
+                Err(rmpv::decode::Error::InvalidMarkerRead(err))
+                | Err(rmpv::decode::Error::InvalidDataRead(err))
+                    if err.kind() == io::ErrorKind::UnexpectedEof => {}
+                Err(err) => return Err(err.into()),
+            }
+        }
+
+        let mut chunk = [0; 8192];
+        let read = reader.read(&mut chunk).await?;
+        if read == 0 {
+            return Ok(None);
+        }
+        buf.extend_from_slice(&chunk[..read]);
+    }
+}
+
+/// Neovim reports errors as `[type, message]`.
+fn error_message(err: &Value) -> String {
+    if let Some(msg) = err.as_array().and_then(|e| return e.get(1)?.as_str()) {
+        return msg.to_string();
+    }
+    return err.to_string();
+}
+
+async fn read_loop(
+    mut reader: RpcReader,
+    pending: PendingCalls,
+    context_tx: mpsc::UnboundedSender<()>,
+) -> Result<()> {
+    let mut buf = vec![];
+    while let Some(message) = read_message(&mut reader, &mut buf).await? {
+        let parts = match message.as_array() {
+            Some(parts) => parts,
+            None => continue,
+        };
+
+        match parts.first().and_then(|t| return t.as_u64()) {
+            Some(MESSAGE_RESPONSE) if parts.len() == 4 => {
+                let id = parts[1].as_u64().unwrap_or_default();
+                if let Some((_, tx)) = pending.remove(&id) {
+                    let res = if parts[2].is_nil() {
+                        Ok(parts[3].clone())
+                    } else {
+                        Err(error_message(&parts[2]))
+                    };
+                    let _ = tx.send(res);
+                }
+            }
+            Some(MESSAGE_NOTIFICATION) => {
+                if parts.get(1).and_then(|m| return m.as_str()) == Some(CONTEXT_CHANGED_EVENT) {
+                    let _ = context_tx.send(());
+                }
+            }
+            _ => {
+                tracing::warn!(message = ?message, "Unexpected message from Neovim");
+            }
+        }
+    }
 
-    let stdout = Command::new("nvim")
-        .args(args.clone())
-        .output()
-        .await?
-        .stdout;
-    let res = String::from_utf8(stdout)?;
+    // Neovim has closed the connection, so fail any calls still waiting.
+    pending.clear();
 
-    tracing::error!(args = ?args, res = ?res, "Neovim requeest/response");
+    return Ok(());
+}
 
-    return Ok(res);
+/// A msgpack-RPC connection to Neovim, shared by every call an editor makes.
+struct Connection {
+    writer: Mutex<RpcWriter>,
+    pending: PendingCalls,
+    next_id: AtomicU64,
+    channel_id: u64,
+    context_rx: std::sync::Mutex<Option<mpsc::UnboundedReceiver<()>>>,
 }
 
-#[derive(Default)]
-pub struct Neovim {}
+impl Connection {
+    async fn open(address: &str) -> Result<Connection> {
+        let (reader, writer) = connect(address).await?;
+        let pending: PendingCalls = Arc::new(DashMap::new());
+        let (context_tx, context_rx) = mpsc::unbounded_channel();
+
+        let pending_reader = pending.clone();
+        tokio::spawn(async move {
+            if let Err(err) = read_loop(reader, pending_reader, context_tx).await {
+                tracing::warn!(error = ?err, "Neovim connection failed");
+            }
+        });
+
+        let mut conn = Connection {
+            writer: Mutex::new(writer),
+            pending,
+            next_id: AtomicU64::new(1),
+            channel_id: 0,
+            context_rx: std::sync::Mutex::new(Some(context_rx)),
+        };
+
+        let api_info = conn.call("nvim_get_api_info", vec![]).await?;
+        conn.channel_id = api_info
+            .as_array()
+            .and_then(|info| return info.first()?.as_u64())
+            .ok_or_else(|| return anyhow!("Neovim did not return a channel ID"))?;
+
+        return Ok(conn);
+    }
+
+    async fn call(&self, method: &str, params: Vec<Value>) -> Result<Value> {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        let (tx, rx) = oneshot::channel();
+        self.pending.insert(id, tx);
+
+        let req = Value::Array(vec![
+            MESSAGE_REQUEST.into(),
+            id.into(),
+            method.into(),
+            Value::Array(params),
+        ]);
+        let payload = encode_message(&req)?;
+        {
+            let mut writer = self.writer.lock().await;
+            writer.write_all(&payload).await?;
+            writer.flush().await?;
+        }
+
+        let res = rx
+            .await
+            .map_err(|_| return anyhow!("Neovim closed the connection during {method}"))?;
+        tracing::debug!(method = method, res = ?res, "Neovim request/response");
+
+        return res.map_err(|err| return anyhow!("Neovim failed to {method}: {err}"));
+    }
+
+    async fn exec_lua(&self, code: &str, args: Vec<Value>) -> Result<Value> {
+        return self
+            .call("nvim_exec_lua", vec![code.into(), Value::Array(args)])
+            .await;
+    }
+}
+
+pub struct Neovim {
+    address: String,
+    connection: OnceCell<Connection>,
+}
+
+impl Default for Neovim {
+    fn default() -> Neovim {
+        return Neovim {
+            address: env::var("NVIM").unwrap_or_default(),
+            connection: OnceCell::new(),
+        };
+    }
+}
+
+impl Neovim {
+    async fn connection(&self) -> Result<&Connection> {
+        return self
+            .connection
+            .get_or_try_init(|| return Connection::open(&self.address))
+            .await;
+    }
+}
 
 #[async_trait]
 impl Editor for Neovim {
@@ -95,27 +348,43 @@ impl Editor for Neovim {
 
     #[allow(clippy::implicit_return)]
     async fn health_check(&self) -> Result<()> {
-        if env::var("NVIM").is_err() {
+        if self.address.is_empty() {
             bail!("Not running within a Neovim terminal")
         }
 
+        if let Err(err) = self.connection().await {
+            bail!(format!(
+                "Failed to connect to Neovim at {}: {err}",
+                self.address
+            ))
+        }
+
         return Ok(());
     }
 
     #[allow(clippy::implicit_return)]
     async fn get_context(&self) -> Result<Option<EditorContext>> {
-        let json_str = run_lua_command("oatmeal_get_context()").await?;
+        let res = self
+            .connection()
+            .await?
+            .exec_lua("return oatmeal_get_context()", vec![])
+            .await?;
+
+        let json_str = res.as_str().unwrap_or("[]");
         if json_str.trim() == "[]" {
             return Ok(None);
         }
-        let ctx: ContextResponse = serde_json::from_str(&json_str)?;
+        let ctx: ContextResponse = serde_json::from_str(json_str)?;
 
         return Ok(Some(ctx.into()));
     }
 
     #[allow(clippy::implicit_return)]
     async fn clear_context(&self) -> Result<()> {
-        run_lua_command("oatmeal_clear_context()").await?;
+        self.connection()
+            .await?
+            .exec_lua("return oatmeal_clear_context()", vec![])
+            .await?;
         return Ok(());
     }
 
@@ -135,18 +404,25 @@ impl Editor for Neovim {
         };
 
         let json_str = serde_json::to_string(&req)?;
+        self.connection()
+            .await?
+            .exec_lua(SUBMIT_CHANGES_LUA, vec![json_str.into()])
+            .await?;
+
+        return Ok(());
+    }
 
-        let temp_file_path = env::temp_dir().join("oatmeal-context");
-        let mut file = File::create(&temp_file_path).await?;
-        file.write_all(json_str.as_bytes()).await?;
-        file.sync_all().await?;
+    #[allow(clippy::implicit_return)]
+    async fn subscribe_context(&self) -> Result<Option<mpsc::UnboundedReceiver<()>>> {
+        let conn = self.connection().await?;
+        let context_rx = conn.context_rx.lock().unwrap().take();
+        if context_rx.is_none() {
+            return Ok(None);
+        }
 
-        run_lua_command(&format!(
-            "oatmeal_submit_changes(\"{}\")",
-            temp_file_path.display()
-        ))
-        .await?;
+        conn.exec_lua(SUBSCRIBE_LUA, vec![conn.channel_id.into()])
+            .await?;
 
-        return Ok(());
+        return Ok(context_rx);
     }
 }
@@ -0,0 +1,194 @@
+use anyhow::Result;
+use rmpv::Value;
+use tokio::io::AsyncWriteExt;
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+use tokio::sync::OnceCell;
+
+use super::encode_message;
+use super::read_message;
+use super::Neovim;
+use crate::domain::models::AcceptType;
+use crate::domain::models::Editor;
+use crate::domain::models::EditorContext;
+
+impl Neovim {
+    fn with_address(address: String) -> Neovim {
+        return Neovim {
+            address,
+            connection: OnceCell::new(),
+        };
+    }
+}
+
+/// Starts a fake Neovim that answers `nvim_exec_lua` with `lua_result`, and
+/// forwards the Lua code and arguments of each call it receives.
+async fn start_nvim(lua_result: Value) -> Result<(String, mpsc::UnboundedReceiver<Value>)> {
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let address = listener.local_addr()?.to_string();
+    let (calls_tx, calls_rx) = mpsc::unbounded_channel();
+
+    tokio::spawn(async move {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let mut buf = vec![];
+        while let Some(message) = read_message(&mut stream, &mut buf).await.unwrap() {
+            let parts = message.as_array().unwrap().clone();
+            let id = parts[1].clone();
+            let method = parts[2].as_str().unwrap().to_string();
+            let params = parts[3].as_array().unwrap().clone();
+
+            let mut res = vec![1.into(), id, Value::Nil, Value::Nil];
+            if method == "nvim_get_api_info" {
+                res[3] = Value::Array(vec![7.into(), Value::Map(vec![])]);
+            } else if method == "nvim_exec_lua" {
+                let code = params[0].as_str().unwrap();
+                if code.contains("oatmeal_fail") {
+                    res[2] = Value::Array(vec![1.into(), "Lua error".into()]);
+                } else if code.contains("nvim_create_autocmd") {
+                    let notification = Value::Array(vec![
+                        2.into(),
+                        "oatmeal_context_changed".into(),
+                        Value::Array(vec![]),
+                    ]);
+                    stream
+                        .write_all(&encode_message(&notification).unwrap())
+                        .await
+                        .unwrap();
+                } else {
+                    res[3] = lua_result.clone();
+                }
+                calls_tx.send(Value::Array(params)).unwrap();
+            }
+
+            let payload = encode_message(&Value::Array(res)).unwrap();
+            stream.write_all(&payload).await.unwrap();
+        }
+    });
+
+    return Ok((address, calls_rx));
+}
+
+#[tokio::test]
+async fn it_fails_health_checks_outside_neovim() -> Result<()> {
+    let err = Neovim::with_address("".to_string())
+        .health_check()
+        .await
+        .unwrap_err();
+
+    insta::assert_snapshot!(err.to_string(), @"Not running within a Neovim terminal");
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_successfully_health_checks() -> Result<()> {
+    let (address, _calls) = start_nvim(Value::Nil).await?;
+    Neovim::with_address(address).health_check().await?;
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_gets_context() -> Result<()> {
+    let (address, mut calls) = start_nvim(
+        r#"{"file_path":"src/main.rs","language":"rust","code":"Zm4gbWFpbigpIHt9","start_line":1,"end_line":1}"#.into(),
+    )
+    .await?;
+
+    let ctx = Neovim::with_address(address).get_context().await?.unwrap();
+    assert_eq!(ctx.file_path, "src/main.rs");
+    assert_eq!(ctx.language, "rust");
+    assert_eq!(ctx.code, "fn main() {}");
+    assert_eq!(ctx.start_line, 1);
+    assert_eq!(ctx.end_line, Some(1));
+
+    let call = calls.recv().await.unwrap();
+    assert_eq!(call[0].as_str(), Some("return oatmeal_get_context()"));
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_gets_no_context() -> Result<()> {
+    let (address, _calls) = start_nvim("[]".into()).await?;
+
+    let ctx = Neovim::with_address(address).get_context().await?;
+    assert!(ctx.is_none());
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_sends_codeblocks_as_arguments() -> Result<()> {
+    let (address, mut calls) = start_nvim(Value::Nil).await?;
+
+    let context = EditorContext {
+        file_path: "src/main.rs".to_string(),
+        language: "rust".to_string(),
+        code: "fn main() {}".to_string(),
+        start_line: 1,
+        end_line: Some(1),
+    };
+    Neovim::with_address(address)
+        .send_codeblock(context, "let x = 5;".to_string(), AcceptType::Append)
+        .await?;
+
+    let call = calls.recv().await.unwrap();
+    assert!(call[0].as_str().unwrap().contains("nvim_buf_set_lines"));
+    let payload = call[1][0].as_str().unwrap();
+    insta::assert_snapshot!(payload, @r###"{"accept_type":"append","file_path":"src/main.rs","code":"let x = 5;","start_line":1,"end_line":1}"###);
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_returns_neovim_errors() -> Result<()> {
+    let (address, _calls) = start_nvim(Value::Nil).await?;
+
+    let neovim = Neovim::with_address(address);
+    let err = neovim
+        .connection()
+        .await?
+        .exec_lua("return oatmeal_fail()", vec![])
+        .await
+        .unwrap_err();
+
+    insta::assert_snapshot!(err.to_string(), @"Neovim failed to nvim_exec_lua: Lua error");
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_subscribes_to_context_changes() -> Result<()> {
+    let (address, mut calls) = start_nvim(Value::Nil).await?;
+
+    let neovim = Neovim::with_address(address);
+    let mut changes = neovim.subscribe_context().await?.unwrap();
+    changes.recv().await.unwrap();
+
+    let call = calls.recv().await.unwrap();
+    assert_eq!(call[1][0].as_u64(), Some(7));
+
+    // Only one subscriber per connection.
+    assert!(neovim.subscribe_context().await?.is_none());
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_reads_messages_split_across_reads() -> Result<()> {
+    let message = Value::Array(vec![2.into(), "event".into(), Value::Array(vec![])]);
+    let payload = encode_message(&message)?;
+    let (mut client, mut server) = tokio::io::duplex(2);
+
+    tokio::spawn(async move {
+        client.write_all(&payload).await.unwrap();
+    });
+
+    let mut buf = vec![];
+    let res = read_message(&mut server, &mut buf).await?;
+    assert_eq!(res, Some(message));
+    assert!(read_message(&mut server, &mut buf).await?.is_none());
+
+    return Ok(());
+}
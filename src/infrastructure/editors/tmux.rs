@@ -0,0 +1,219 @@
+#[cfg(test)]
+#[path = "tmux_test.rs"]
+mod tests;
+
+use std::env;
+use std::process::Stdio;
+
+use anyhow::bail;
+use anyhow::Result;
+use async_trait::async_trait;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+use crate::configuration::Config;
+use crate::configuration::ConfigKey;
+use crate::domain::models::AcceptType;
+use crate::domain::models::Editor;
+use crate::domain::models::EditorContext;
+use crate::domain::models::EditorName;
+
+/// Lines of scrollback captured from the pane, on top of what's visible.
+const CAPTURE_HISTORY_LINES: i64 = 200;
+
+/// tmux buffer code blocks are pasted from, so the user's own buffers are left
+/// alone.
+const PASTE_BUFFER: &str = "oatmeal";
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TmuxPane {
+    pub id: String,
+    pub target: String,
+    pub command: String,
+    pub active: bool,
+}
+
+/// Captures the contents of another tmux pane as context, and types accepted
+/// code blocks in to it.
+pub struct Tmux {
+    /// Socket of the tmux server, otherwise the server Oatmeal runs in.
+    socket: Option<String>,
+    target: String,
+}
+
+impl Default for Tmux {
+    fn default() -> Tmux {
+        return Tmux {
+            socket: None,
+            target: Config::get(ConfigKey::TmuxPane),
+        };
+    }
+}
+
+impl Tmux {
+    async fn run(&self, args: &[&str]) -> Result<String> {
+        return self.run_with_input(args, None).await;
+    }
+
+    /// Runs a tmux command, writing `input` to its stdin when given.
+    async fn run_with_input(&self, args: &[&str], input: Option<&str>) -> Result<String> {
+        let mut cmd = Command::new("tmux");
+        if let Some(socket) = &self.socket {
+            cmd.args(["-S", socket]);
+        }
+
+        let mut child = cmd
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        if let Some(mut stdin) = child.stdin.take() {
+            if let Some(input) = input {
+                stdin.write_all(input.as_bytes()).await?;
+            }
+        }
+
+        let output = child.wait_with_output().await?;
+        let res = String::from_utf8(output.stdout)?;
+
+        tracing::debug!(args = ?args, res = ?res, "tmux request/response");
+
+        if !output.status.success() {
+            let stderr = String::from_utf8(output.stderr)?;
+            bail!(format!("tmux failed: {}", stderr.trim()));
+        }
+
+        return Ok(res);
+    }
+
+    /// Lists every pane of the tmux server.
+    pub async fn list_panes(&self) -> Result<Vec<TmuxPane>> {
+        let res = self
+            .run(&[
+                "list-panes",
+                "-a",
+                "-F",
+                "#{pane_id}\t#{session_name}:#{window_index}.#{pane_index}\t#{pane_current_command}\t#{pane_active}",
+            ])
+            .await?;
+
+        let panes = res
+            .lines()
+            .filter_map(|line| {
+                match line.split('\t').collect::<Vec<&str>>()[..] {
+                    [id, target, command, active] => {
+                        return Some(TmuxPane {
+                            id: id.to_string(),
+                            target: target.to_string(),
+                            command: command.to_string(),
+                            active: active == "1",
+                        });
+                    }
+                    _ => return None,
+                }
+            })
+            .collect();
+
+        return Ok(panes);
+    }
+
+    /// Resolves the configured target, such as `{last}`, to a pane ID.
+    async fn pane_id(&self) -> Result<String> {
+        let res = self
+            .run(&["display-message", "-p", "-t", &self.target, "#{pane_id}"])
+            .await?;
+
+        // tmux prints nothing, rather than failing, for targets that don't
+        // exist.
+        let pane_id = res.trim();
+        if pane_id.is_empty() {
+            bail!(format!("can't find pane {}", self.target));
+        }
+
+        return Ok(pane_id.to_string());
+    }
+}
+
+#[async_trait]
+impl Editor for Tmux {
+    fn name(&self) -> EditorName {
+        return EditorName::Tmux;
+    }
+
+    #[allow(clippy::implicit_return)]
+    async fn health_check(&self) -> Result<()> {
+        if self.socket.is_none() && env::var("TMUX").is_err() {
+            bail!("Oatmeal isn't running inside of tmux");
+        }
+
+        if let Err(err) = self.pane_id().await {
+            bail!(format!(
+                "Failed to find tmux pane {}. Use /pane to pick one. {err}",
+                self.target
+            ));
+        }
+
+        return Ok(());
+    }
+
+    #[allow(clippy::implicit_return)]
+    async fn get_context(&self) -> Result<Option<EditorContext>> {
+        let pane_id = self.pane_id().await?;
+        let res = self
+            .run(&[
+                "capture-pane",
+                "-p",
+                "-J",
+                "-t",
+                &pane_id,
+                "-S",
+                &(-CAPTURE_HISTORY_LINES).to_string(),
+            ])
+            .await?;
+
+        let code = res.trim_end().to_string();
+        if code.is_empty() {
+            return Ok(None);
+        }
+
+        return Ok(Some(EditorContext {
+            file_path: format!("tmux pane {pane_id}"),
+            language: "text".to_string(),
+            end_line: Some(i64::try_from(code.lines().count())?),
+            code,
+            start_line: 1,
+        }));
+    }
+
+    #[allow(clippy::implicit_return)]
+    async fn clear_context(&self) -> Result<()> {
+        return Ok(());
+    }
+
+    /// Pastes the code block in to the pane without pressing enter, so it can
+    /// be reviewed before it runs. It's sent as a bracketed paste, so shells
+    /// don't run each line of multi-line code blocks as it arrives.
+    #[allow(clippy::implicit_return)]
+    async fn send_codeblock<'a>(
+        &self,
+        _context: EditorContext,
+        codeblock: String,
+        _accept_type: AcceptType,
+    ) -> Result<()> {
+        self.run_with_input(&["load-buffer", "-b", PASTE_BUFFER, "-"], Some(&codeblock))
+            .await?;
+        self.run(&[
+            "paste-buffer",
+            "-p",
+            "-d",
+            "-b",
+            PASTE_BUFFER,
+            "-t",
+            &self.target,
+        ])
+        .await?;
+
+        return Ok(());
+    }
+}
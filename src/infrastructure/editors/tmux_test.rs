@@ -0,0 +1,202 @@
+use std::process::Command;
+use std::time::Duration;
+
+use anyhow::Result;
+use tempfile::TempDir;
+
+use super::Tmux;
+use crate::domain::models::AcceptType;
+use crate::domain::models::Editor;
+use crate::domain::models::EditorContext;
+
+/// A private tmux server, so tests don't touch the tmux server they may be
+/// running in. Killed when dropped.
+struct TmuxServer {
+    _dir: TempDir,
+    socket: String,
+}
+
+impl TmuxServer {
+    /// Starts a session running `cat`, after printing some output. Returns
+    /// `None` when tmux isn't installed.
+    fn start() -> Result<Option<TmuxServer>> {
+        return TmuxServer::start_with("printf 'running tests\\nerror: it failed\\n'; exec cat");
+    }
+
+    /// Starts a session running `command`. Returns `None` when tmux isn't
+    /// installed.
+    fn start_with(command: &str) -> Result<Option<TmuxServer>> {
+        if Command::new("tmux").arg("-V").output().is_err() {
+            return Ok(None);
+        }
+
+        let dir = tempfile::tempdir()?;
+        let socket = dir.path().join("tmux.sock").to_string_lossy().to_string();
+        let status = Command::new("tmux")
+            .args(["-S", &socket, "-f", "/dev/null", "new-session", "-d"])
+            .args(["-s", "oatmeal", "-x", "80", "-y", "24"])
+            .arg(command)
+            .status()?;
+        assert!(status.success());
+
+        return Ok(Some(TmuxServer { _dir: dir, socket }));
+    }
+
+    fn editor(&self, target: &str) -> Tmux {
+        return Tmux {
+            socket: Some(self.socket.to_string()),
+            target: target.to_string(),
+        };
+    }
+}
+
+impl Drop for TmuxServer {
+    fn drop(&mut self) {
+        let _ = Command::new("tmux")
+            .args(["-S", &self.socket, "kill-server"])
+            .output();
+    }
+}
+
+/// Waits for the pane to show `text`, as programs in it run asynchronously.
+async fn wait_for_context(editor: &Tmux, text: &str) -> Result<EditorContext> {
+    for _ in 0..100 {
+        if let Some(context) = editor.get_context().await? {
+            if context.code.contains(text) {
+                return Ok(context);
+            }
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+
+    panic!("tmux pane never showed {text}");
+}
+
+#[tokio::test]
+async fn it_successfully_health_checks() -> Result<()> {
+    let Some(server) = TmuxServer::start()? else {
+        return Ok(());
+    };
+
+    server.editor("oatmeal:0.0").health_check().await?;
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_fails_health_checks_for_missing_panes() -> Result<()> {
+    let Some(server) = TmuxServer::start()? else {
+        return Ok(());
+    };
+
+    let err = server
+        .editor("missing:0.0")
+        .health_check()
+        .await
+        .unwrap_err();
+    assert!(err
+        .to_string()
+        .starts_with("Failed to find tmux pane missing:0.0. Use /pane to pick one."));
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_captures_panes_as_context() -> Result<()> {
+    let Some(server) = TmuxServer::start()? else {
+        return Ok(());
+    };
+
+    let editor = server.editor("oatmeal:0.0");
+    let context = wait_for_context(&editor, "error: it failed").await?;
+
+    assert!(context.file_path.starts_with("tmux pane %"));
+    assert_eq!(context.language, "text");
+    assert_eq!(context.code, "running tests\nerror: it failed");
+    assert_eq!(context.start_line, 1);
+    assert_eq!(context.end_line, Some(2));
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_sends_code_blocks_without_running_them() -> Result<()> {
+    let Some(server) = TmuxServer::start()? else {
+        return Ok(());
+    };
+
+    let editor = server.editor("oatmeal:0.0");
+    wait_for_context(&editor, "error: it failed").await?;
+    editor
+        .send_codeblock(
+            EditorContext::default(),
+            "cargo test -- it_fails".to_string(),
+            AcceptType::Append,
+        )
+        .await?;
+
+    let context = wait_for_context(&editor, "cargo test").await?;
+    assert_eq!(
+        context.code,
+        "running tests\nerror: it failed\ncargo test -- it_fails"
+    );
+
+    return Ok(());
+}
+
+#[tokio::test]
+async fn it_sends_multi_line_code_blocks_without_running_them() -> Result<()> {
+    let Some(server) = TmuxServer::start_with("env PS1='$ ' bash --norc --noprofile")? else {
+        return Ok(());
+    };
+
+    let dir = tempfile::tempdir()?;
+    let first = dir.path().join("first");
+    let second = dir.path().join("second");
+
+    let editor = server.editor("oatmeal:0.0");
+    wait_for_context(&editor, "$").await?;
+    editor
+        .send_codeblock(
+            EditorContext::default(),
+            format!("touch {}\ntouch {}", first.display(), second.display()),
+            AcceptType::Append,
+        )
+        .await?;
+
+    wait_for_context(&editor, &second.to_string_lossy()).await?;
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(!first.exists());
+    assert!(!second.exists());
+
+    // Both lines run together once enter is pressed.
+    editor
+        .run(&["send-keys", "-t", "oatmeal:0.0", "Enter"])
+        .await?;
+    for _ in 0..100 {
+        if first.exists() && second.exists() {
+            return Ok(());
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+
+    panic!("pasted code block never ran");
+}
+
+#[tokio::test]
+async fn it_lists_panes() -> Result<()> {
+    let Some(server) = TmuxServer::start()? else {
+        return Ok(());
+    };
+
+    // The session may not be listed until its pane is running.
+    let editor = server.editor("oatmeal:0.0");
+    wait_for_context(&editor, "error: it failed").await?;
+    let panes = editor.list_panes().await?;
+    assert_eq!(panes.len(), 1);
+    assert!(panes[0].id.starts_with('%'));
+    assert_eq!(panes[0].target, "oatmeal:0.0");
+    assert!(panes[0].active);
+
+    return Ok(());
+}
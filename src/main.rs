@@ -24,6 +24,7 @@ use crate::application::ui;
 use crate::configuration::Config;
 use crate::configuration::ConfigKey;
 use crate::domain::services::actions::ActionsService;
+use crate::domain::services::ModelCache;
 
 #[cfg(feature = "dhat-heap")]
 #[global_allocator]
@@ -102,11 +103,15 @@ async fn main() {
     let (action_tx, mut action_rx) = mpsc::unbounded_channel::<Action>();
     let (event_tx, event_rx) = mpsc::unbounded_channel::<Event>();
 
+    let model_cache = ModelCache::default();
+    let actions_model_cache = model_cache.clone();
+
     let mut background_futures = task::JoinSet::new();
     background_futures.spawn(async move {
         let backend = BackendName::parse(Config::get(ConfigKey::Backend)).unwrap();
         return ActionsService::start(
             BackendManager::get(backend).unwrap(),
+            actions_model_cache,
             event_tx,
             &mut action_rx,
         )
@@ -121,7 +126,7 @@ async fn main() {
         });
     }
 
-    let ui_future = ui::start(action_tx, event_rx);
+    let ui_future = ui::start(model_cache, action_tx, event_rx);
 
     let res = tokio::select!(
         res = background_futures.join_next() => res.unwrap().unwrap(),